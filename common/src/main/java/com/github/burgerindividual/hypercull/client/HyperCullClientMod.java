@@ -9,6 +9,10 @@ public class HyperCullClientMod {
     public static final String MOD_NAME = "HyperCull";
     public static final Logger LOGGER = LoggerFactory.getLogger(MOD_NAME);
 
+    // How far above and below the camera sections are searched, in blocks. A negative value uses Sodium's search
+    // distance, which is the same distance that's searched horizontally. Set from any thread, and read every frame.
+    public static volatile float verticalSearchDistance = -1.0f;
+
     public static void init() {
         loadNatives();
     }
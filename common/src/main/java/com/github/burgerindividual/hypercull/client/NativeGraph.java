@@ -26,24 +26,52 @@ public class NativeGraph implements Closeable {
     private static final int TILE_IDX_Y_OFFSET = 6;
     private static final int TILE_IDX_Z_OFFSET = 3;
 
-    private final long nativePtr;
+    private final long handle;
     private final RenderRegionManager regions;
 
-    public NativeGraph(RenderRegionManager regions, byte renderDistance, byte minSectionY, byte maxSectionY) {
-        this.nativePtr = HyperCullNativeLib.graphCreate(
-                renderDistance,
-                minSectionY,
-                maxSectionY
-        );
+    // Searches usually fail for the same reason every frame, so only the first error in a row is logged
+    private boolean searchFailing = false;
+    // Box tests run many times per frame, so they're logged the same way as searches
+    private boolean boxTestFailing = false;
+
+    private NativeGraph(long handle, RenderRegionManager regions) {
+        this.handle = handle;
         this.regions = regions;
     }
 
-    public void findVisible(
+    /**
+     * @return the new graph, or null if the graph could not be created, in which case the error is logged
+     */
+    public static NativeGraph create(RenderRegionManager regions, byte renderDistance, byte minSectionY, byte maxSectionY) {
+        try (var stack = MemoryStack.stackPush()) {
+            var handlePtr = stack.ncalloc(Long.BYTES, 1, Long.BYTES);
+            var status = HyperCullNativeLib.graphCreate(
+                    handlePtr,
+                    renderDistance,
+                    minSectionY,
+                    maxSectionY
+            );
+
+            if (status != HyperCullNativeLib.STATUS_OK) {
+                HyperCullClientMod.LOGGER.error(
+                        "Error creating native graph, falling back to Sodium's culler: {}",
+                        HyperCullNativeLib.getErrorMessage()
+                );
+                return null;
+            }
+
+            return new NativeGraph(MemoryUtil.memGetLong(handlePtr), regions);
+        }
+    }
+
+    /**
+     * @return false if the search failed, in which case the error is logged and the visitor has not been called
+     */
+    public boolean findVisible(
             OcclusionCuller.Visitor visitor,
             SixPlaneFrustum frustum,
             CameraTransform transform,
-            float searchDistance,
-            boolean useOcclusionCulling,
+            SearchOptions options,
             int frame
     ) {
         try (var stack = MemoryStack.stackPush()) {
@@ -53,21 +81,36 @@ public class NativeGraph implements Closeable {
                     frustum,
                     transform
             );
+            var optionsPtr = HyperCullNativeLib.searchOptionsCreate(stack, options);
 
-            HyperCullNativeLib.graphSearch(
+            var status = HyperCullNativeLib.graphSearch(
                     resultsPtr,
-                    this.nativePtr,
+                    this.handle,
                     cameraPtr,
-                    searchDistance,
-                    useOcclusionCulling
+                    optionsPtr
             );
 
+            if (status != HyperCullNativeLib.STATUS_OK) {
+                if (!this.searchFailing) {
+                    HyperCullClientMod.LOGGER.error(
+                            "Error searching native graph, falling back to Sodium's culler: {}",
+                            HyperCullNativeLib.getErrorMessage()
+                    );
+                    this.searchFailing = true;
+                }
+                return false;
+            }
+
+            this.searchFailing = false;
+
             var tilesDataPtr = MemoryUtil.memGetAddress(resultsPtr + HyperCullNativeLib.FFISLICE_DATA_PTR_OFFSET);
             var tileCount = MemoryUtil.memGetAddress(resultsPtr + HyperCullNativeLib.FFISLICE_COUNT_OFFSET);
 
             for (var tileIdx = 0L; tileIdx < tileCount; tileIdx++) {
                 this.readTile(tilesDataPtr + (tileIdx * HyperCullNativeLib.FFITILE_SIZE), visitor, frame);
             }
+
+            return true;
         }
     }
 
@@ -133,18 +176,78 @@ public class NativeGraph implements Closeable {
         );
     }
 
+    /**
+     * Tests whether a box in world coordinates, such as an entity's bounds, is inside the frustum from the last search
+     * and overlaps a section that it found visible.
+     *
+     * @return true if the test failed, so the box isn't hidden by mistake
+     */
+    public boolean isBoxVisible(double minX, double minY, double minZ, double maxX, double maxY, double maxZ) {
+        try (var stack = MemoryStack.stackPush()) {
+            var boxPtr = stack.nmalloc(HyperCullNativeLib.FFIBOUNDINGBOX_ALIGNMENT, HyperCullNativeLib.FFIBOUNDINGBOX_SIZE);
+            HyperCullNativeLib.boundingBoxPut(boxPtr, minX, minY, minZ, maxX, maxY, maxZ);
+            var resultPtr = stack.ncalloc(1, 1, 1);
+
+            if (!this.testBoxes(boxPtr, resultPtr, 1)) {
+                return true;
+            }
+
+            return MemoryUtil.memGetByte(resultPtr) != 0;
+        }
+    }
+
+    /**
+     * The batched version of {@link #isBoxVisible}, which is much faster when testing many boxes at once.
+     *
+     * @param boxesPtr   an array of {@code count} {@code FFIBoundingBox}es, which can be filled with
+     *                   {@link HyperCullNativeLib#boundingBoxPut}
+     * @param resultsPtr an array of {@code count} bytes, which are set to 1 for each visible box and 0 otherwise
+     * @return false if the test failed, in which case the error is logged and the results are unchanged
+     * @throws IllegalArgumentException if {@code count} is negative
+     */
+    public boolean testBoxes(long boxesPtr, long resultsPtr, int count) {
+        // the native side takes the count as a u32, so a negative count would be read as a huge one
+        if (count < 0) {
+            throw new IllegalArgumentException("Box count must not be negative: " + count);
+        }
+
+        var status = HyperCullNativeLib.graphTestBoxes(resultsPtr, this.handle, boxesPtr, count);
+
+        if (status != HyperCullNativeLib.STATUS_OK) {
+            if (!this.boxTestFailing) {
+                HyperCullClientMod.LOGGER.warn(
+                        "Error testing boxes in native graph: {}",
+                        HyperCullNativeLib.getErrorMessage()
+                );
+                this.boxTestFailing = true;
+            }
+            return false;
+        }
+
+        this.boxTestFailing = false;
+        return true;
+    }
+
     public void setSection(int x, int y, int z, long visibilityData) {
-        HyperCullNativeLib.graphSetSection(
-                this.nativePtr,
+        var status = HyperCullNativeLib.graphSetSection(
+                this.handle,
                 x,
                 y,
                 z,
                 visibilityData
         );
+
+        if (status != HyperCullNativeLib.STATUS_OK) {
+            HyperCullClientMod.LOGGER.warn("Error setting section in native graph: {}", HyperCullNativeLib.getErrorMessage());
+        }
     }
 
     @Override
     public void close() {
-        HyperCullNativeLib.graphDelete(this.nativePtr);
+        var status = HyperCullNativeLib.graphDelete(this.handle);
+
+        if (status != HyperCullNativeLib.STATUS_OK) {
+            HyperCullClientMod.LOGGER.warn("Error deleting native graph: {}", HyperCullNativeLib.getErrorMessage());
+        }
     }
 }
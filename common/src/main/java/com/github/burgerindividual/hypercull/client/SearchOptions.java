@@ -0,0 +1,23 @@
+package com.github.burgerindividual.hypercull.client;
+
+import com.github.burgerindividual.hypercull.client.ffi.HyperCullNativeLib;
+
+/**
+ * The settings for a single native search, matching {@code FFISearchOptions}. Each culling stage other than the
+ * frustum can be turned off on its own, for debugging or for compatibility with mods that render sections the stages
+ * don't expect to be visible.
+ */
+public class SearchOptions {
+    public float searchDistance;
+    // Sections further than this above or below the camera are culled, regardless of the fog shape
+    public float verticalSearchDistance;
+    // A negative value uses the bounds extension that the graph was created with
+    public float boundsExtension = -1.0f;
+    // One of the FOG_SHAPE_ constants in HyperCullNativeLib
+    public byte fogShape = HyperCullNativeLib.FOG_SHAPE_CYLINDRICAL;
+    public boolean useOcclusionCulling = true;
+    public boolean useAngleCulling = true;
+    public boolean useOutwardDirectionMasks = true;
+    public boolean useFogCulling = true;
+    public boolean useHeightCulling = true;
+}
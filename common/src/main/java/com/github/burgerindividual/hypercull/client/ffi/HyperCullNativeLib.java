@@ -1,19 +1,22 @@
 package com.github.burgerindividual.hypercull.client.ffi;
 
 import com.github.burgerindividual.hypercull.client.HyperCullClientMod;
+import com.github.burgerindividual.hypercull.client.SearchOptions;
 import com.github.burgerindividual.hypercull.client.SixPlaneFrustum;
 import net.caffeinemc.mods.sodium.client.render.viewport.CameraTransform;
 import org.lwjgl.system.*;
 
-import oshi.SystemInfo;
-
 import static org.joml.FrustumIntersection.*;
 
 public class HyperCullNativeLib {
     public static final boolean SUPPORTED;
 
-    // Size and alignment information obtained by hovering over Rust types with Rust Analyzer.
+    // The version of the native C API that this class was written against, matching ABI_VERSION in ffi.rs
+    public static final int ABI_VERSION = 4;
+
+    // Struct layouts, which are checked against the layouts reported by the native library when it's loaded
     public static final int FFITILE_SIZE = 80;
+    public static final int FFITILE_ALIGNMENT = 8;
     public static final int FFITILE_ORIGIN_SECTION_X_OFFSET = 0;
     public static final int FFITILE_ORIGIN_SECTION_Y_OFFSET = 4;
     public static final int FFITILE_ORIGIN_SECTION_Z_OFFSET = 8;
@@ -24,22 +27,117 @@ public class HyperCullNativeLib {
     public static final int FFISLICE_DATA_PTR_OFFSET = 0;
     public static final int FFISLICE_COUNT_OFFSET = Pointer.POINTER_SIZE;
 
+    public static final int FFITILECAPTURE_SIZE = 912;
+    public static final int FFITILECAPTURE_ALIGNMENT = 8;
+    public static final int FFITILECAPTURE_ORIGIN_SECTION_X_OFFSET = 0;
+    public static final int FFITILECAPTURE_ORIGIN_SECTION_Y_OFFSET = 4;
+    public static final int FFITILECAPTURE_ORIGIN_SECTION_Z_OFFSET = 8;
+    public static final int FFITILECAPTURE_TEST_RESULTS_OFFSET = 12;
+    public static final int FFITILECAPTURE_AFTER_FRUSTUM_OFFSET = 16;
+    public static final int FFITILECAPTURE_AFTER_FOG_OFFSET = 80;
+    public static final int FFITILECAPTURE_AFTER_HEIGHT_OFFSET = 144;
+    public static final int FFITILECAPTURE_ANGLE_MASKS_OFFSET = 208;
+    public static final int FFITILECAPTURE_AFTER_TRAVERSAL_OFFSET = 400;
+    public static final int FFITILECAPTURE_INCOMING_DIR_SECTION_SETS_OFFSET = 464;
+    public static final int FFITILECAPTURE_WITHOUT_ANGLE_CULLING_OFFSET = 848;
+
+    public static final int FFISECTIONDIAGNOSIS_SIZE = 5;
+    public static final int FFISECTIONDIAGNOSIS_ALIGNMENT = 1;
+    public static final int FFISECTIONDIAGNOSIS_STAGE_OFFSET = 0;
+    public static final int FFISECTIONDIAGNOSIS_FRUSTUM_PLANE_OFFSET = 1;
+    public static final int FFISECTIONDIAGNOSIS_INCOMING_DIRS_OFFSET = 2;
+    public static final int FFISECTIONDIAGNOSIS_VISIBLE_NEIGHBORS_OFFSET = 3;
+    public static final int FFISECTIONDIAGNOSIS_REACHED_FROM_OFFSET = 4;
+
+    // Values of FFISectionDiagnosis.stage
+    public static final byte DIAGNOSIS_NO_SEARCH = 0;
+    public static final byte DIAGNOSIS_OUTSIDE_FRUSTUM = 1;
+    public static final byte DIAGNOSIS_OUTSIDE_FOG = 2;
+    public static final byte DIAGNOSIS_OUTSIDE_WORLD_HEIGHT = 3;
+    public static final byte DIAGNOSIS_ANGLE_CULLED = 4;
+    public static final byte DIAGNOSIS_UNREACHABLE = 5;
+    public static final byte DIAGNOSIS_VISIBLE = 6;
+
+    // Status codes returned by fallible functions, matching the constants in GraphError
+    public static final byte STATUS_OK = 0;
+    public static final byte STATUS_NULL_POINTER = 1;
+    public static final byte STATUS_INVALID_WORLD_HEIGHT = 2;
+    public static final byte STATUS_GRAPH_TOO_LARGE = 3;
+    public static final byte STATUS_SECTION_OUT_OF_BOUNDS = 4;
+    public static final byte STATUS_INVALID_SEARCH_DISTANCE = 5;
+    public static final byte STATUS_INVALID_CAMERA_POSITION = 6;
+    public static final byte STATUS_INVALID_HANDLE = 7;
+    public static final byte STATUS_INVALID_SECTION_GEOMETRY = 8;
+    public static final byte STATUS_INVALID_FOG_SHAPE = 9;
+    public static final byte STATUS_INVALID_ARGUMENT = 10;
+    public static final byte STATUS_NO_CAPTURE = 11;
+    public static final byte STATUS_IO = 12;
+
+    // Values of FFISearchOptions.fog_shape, matching the constants in FogShape
+    public static final byte FOG_SHAPE_CYLINDRICAL = 0;
+    public static final byte FOG_SHAPE_SPHERICAL = 1;
+    public static final byte FOG_SHAPE_SQUARE = 2;
+
+    // Levels for the native logger, matching the constants in logging.rs
+    public static final byte LOG_LEVEL_OFF = 0;
+    public static final byte LOG_LEVEL_ERROR = 1;
+    public static final byte LOG_LEVEL_WARN = 2;
+    public static final byte LOG_LEVEL_INFO = 3;
+    public static final byte LOG_LEVEL_DEBUG = 4;
+    public static final byte LOG_LEVEL_TRACE = 5;
+
+    // Sets of CPU features that the native kernels can run with, matching the constants in SimdLevel
+    public static final byte SIMD_LEVEL_BASELINE = 0;
+    public static final byte SIMD_LEVEL_SSE41 = 1;
+    public static final byte SIMD_LEVEL_AVX2 = 2;
+    public static final byte SIMD_LEVEL_AVX512 = 3;
+
     public static final int FFICAMERA_SIZE = 120;
     public static final int FFICAMERA_ALIGNMENT = Pointer.POINTER_SIZE;
+    public static final int FFICAMERA_FRUSTUM_PLANES_OFFSET = 0;
+    public static final int FFICAMERA_POS_OFFSET = 96;
+
+    public static final int FFIBUILDINFO_SIZE = FFISLICE_SIZE * 2 + Pointer.POINTER_SIZE;
+    public static final int FFIBUILDINFO_ALIGNMENT = Pointer.POINTER_SIZE;
+    public static final int FFIBUILDINFO_VERSION_OFFSET = 0;
+    public static final int FFIBUILDINFO_FEATURES_OFFSET = FFISLICE_SIZE;
+    public static final int FFIBUILDINFO_COMPILED_SIMD_LEVEL_OFFSET = FFISLICE_SIZE * 2;
+    public static final int FFIBUILDINFO_DETECTED_SIMD_LEVEL_OFFSET = FFISLICE_SIZE * 2 + 1;
+
+    public static final int FFISEARCHOPTIONS_SIZE = 20;
+    public static final int FFISEARCHOPTIONS_ALIGNMENT = 4;
+    public static final int FFISEARCHOPTIONS_SEARCH_DISTANCE_OFFSET = 0;
+    public static final int FFISEARCHOPTIONS_VERTICAL_SEARCH_DISTANCE_OFFSET = 4;
+    public static final int FFISEARCHOPTIONS_BOUNDS_EXTENSION_OFFSET = 8;
+    public static final int FFISEARCHOPTIONS_FOG_SHAPE_OFFSET = 12;
+    public static final int FFISEARCHOPTIONS_USE_OCCLUSION_CULLING_OFFSET = 13;
+    public static final int FFISEARCHOPTIONS_USE_ANGLE_CULLING_OFFSET = 14;
+    public static final int FFISEARCHOPTIONS_USE_OUTWARD_DIRECTION_MASKS_OFFSET = 15;
+    public static final int FFISEARCHOPTIONS_USE_FOG_CULLING_OFFSET = 16;
+    public static final int FFISEARCHOPTIONS_USE_HEIGHT_CULLING_OFFSET = 17;
+
+    public static final int FFIBOUNDINGBOX_SIZE = 48;
+    public static final int FFIBOUNDINGBOX_ALIGNMENT = 8;
+    public static final int FFIBOUNDINGBOX_MIN_OFFSET = 0;
+    public static final int FFIBOUNDINGBOX_MAX_OFFSET = Double.BYTES * 3;
+
+    // The number of uint32_t fields in FFILayout
+    private static final int FFILAYOUT_FIELD_COUNT = 51;
 
     private static final PanicCallback PANIC_CALLBACK;
+    private static final LogCallback LOG_CALLBACK;
 
     static {
         var errorLoading = false;
         PanicCallback panicCallback = null;
+        LogCallback logCallback = null;
 
         try {
-            var architecture = Platform.getArchitecture();
+            // the native library picks the best kernels for the CPU at runtime, so there's only one per platform
             var systemType = String.format(
-                    "%s-%s%s",
+                    "%s-%s",
                     Platform.get().getName().toLowerCase(),
-                    architecture.name().toLowerCase(),
-                    getCPUFeatures(architecture)
+                    Platform.getArchitecture().name().toLowerCase()
             );
             var nativePath = String.format(
                     "assets/hypercull/natives/%s/%s",
@@ -55,7 +153,10 @@ public class HyperCullNativeLib {
                     nativePath
             );
 
+            verifyAbi();
             panicCallback = initPanicHandler();
+            logCallback = initLogHandler();
+            logBuildInfo();
         } catch (Throwable t) {
             HyperCullClientMod.LOGGER.error("Error loading native culling library", t);
             errorLoading = true;
@@ -63,33 +164,172 @@ public class HyperCullNativeLib {
 
         SUPPORTED = !errorLoading;
         PANIC_CALLBACK = panicCallback;
+        LOG_CALLBACK = logCallback;
+    }
+
+    /**
+     * Checks that the native library implements the same version of the C API as this class, and that every struct
+     * layout that this class hardcodes matches the native one, so a mismatched library is rejected instead of
+     * corrupting memory.
+     *
+     * @throws IllegalStateException if the version or any layout doesn't match
+     */
+    private static void verifyAbi() {
+        var abiVersion = getAbiVersion();
+        if (abiVersion != ABI_VERSION) {
+            throw new IllegalStateException(String.format(
+                    "Native library ABI version %d doesn't match the expected version %d",
+                    abiVersion,
+                    ABI_VERSION
+            ));
+        }
+
+        try (var stack = MemoryStack.stackPush()) {
+            long pLayout = stack.ncalloc(Integer.BYTES, FFILAYOUT_FIELD_COUNT, Integer.BYTES);
+            byte status = getLayout(pLayout);
+            if (status != STATUS_OK) {
+                // the error message can't be read yet, because FFISlice's layout hasn't been checked
+                throw new IllegalStateException("Error getting native struct layouts - Status: " + status);
+            }
+
+            // the fields are checked in the order they're declared in FFILayout
+            var layout = new LayoutReader(pLayout);
+
+            layout.check("FFITile size", FFITILE_SIZE);
+            layout.check("FFITile alignment", FFITILE_ALIGNMENT);
+            layout.check("FFITile.origin_section_coords offset", FFITILE_ORIGIN_SECTION_X_OFFSET);
+            layout.check("FFITile.visible_sections offset", FFITILE_VISIBLE_SECTIONS_OFFSET);
+
+            layout.check("FFISlice size", FFISLICE_SIZE);
+            layout.check("FFISlice alignment", FFISLICE_ALIGNMENT);
+            layout.check("FFISlice.data_ptr offset", FFISLICE_DATA_PTR_OFFSET);
+            layout.check("FFISlice.count offset", FFISLICE_COUNT_OFFSET);
+
+            layout.check("FFICamera size", FFICAMERA_SIZE);
+            layout.check("FFICamera alignment", FFICAMERA_ALIGNMENT);
+            layout.check("FFICamera.frustum_planes offset", FFICAMERA_FRUSTUM_PLANES_OFFSET);
+            layout.check("FFICamera.pos offset", FFICAMERA_POS_OFFSET);
+
+            layout.check("FFITileCapture size", FFITILECAPTURE_SIZE);
+            layout.check("FFITileCapture alignment", FFITILECAPTURE_ALIGNMENT);
+            layout.check("FFITileCapture.origin_section_coords offset", FFITILECAPTURE_ORIGIN_SECTION_X_OFFSET);
+            layout.check("FFITileCapture.test_results offset", FFITILECAPTURE_TEST_RESULTS_OFFSET);
+            layout.check("FFITileCapture.after_frustum offset", FFITILECAPTURE_AFTER_FRUSTUM_OFFSET);
+            layout.check("FFITileCapture.after_fog offset", FFITILECAPTURE_AFTER_FOG_OFFSET);
+            layout.check("FFITileCapture.after_height offset", FFITILECAPTURE_AFTER_HEIGHT_OFFSET);
+            layout.check("FFITileCapture.angle_masks offset", FFITILECAPTURE_ANGLE_MASKS_OFFSET);
+            layout.check("FFITileCapture.after_traversal offset", FFITILECAPTURE_AFTER_TRAVERSAL_OFFSET);
+            layout.check(
+                    "FFITileCapture.incoming_dir_section_sets offset",
+                    FFITILECAPTURE_INCOMING_DIR_SECTION_SETS_OFFSET
+            );
+            layout.check("FFITileCapture.without_angle_culling offset", FFITILECAPTURE_WITHOUT_ANGLE_CULLING_OFFSET);
+
+            layout.check("FFISectionDiagnosis size", FFISECTIONDIAGNOSIS_SIZE);
+            layout.check("FFISectionDiagnosis alignment", FFISECTIONDIAGNOSIS_ALIGNMENT);
+            layout.check("FFISectionDiagnosis.stage offset", FFISECTIONDIAGNOSIS_STAGE_OFFSET);
+            layout.check("FFISectionDiagnosis.frustum_plane offset", FFISECTIONDIAGNOSIS_FRUSTUM_PLANE_OFFSET);
+            layout.check("FFISectionDiagnosis.incoming_dirs offset", FFISECTIONDIAGNOSIS_INCOMING_DIRS_OFFSET);
+            layout.check("FFISectionDiagnosis.visible_neighbors offset", FFISECTIONDIAGNOSIS_VISIBLE_NEIGHBORS_OFFSET);
+            layout.check("FFISectionDiagnosis.reached_from offset", FFISECTIONDIAGNOSIS_REACHED_FROM_OFFSET);
+
+            layout.check("FFIBuildInfo size", FFIBUILDINFO_SIZE);
+            layout.check("FFIBuildInfo alignment", FFIBUILDINFO_ALIGNMENT);
+            layout.check("FFIBuildInfo.version offset", FFIBUILDINFO_VERSION_OFFSET);
+            layout.check("FFIBuildInfo.features offset", FFIBUILDINFO_FEATURES_OFFSET);
+            layout.check("FFIBuildInfo.compiled_simd_level offset", FFIBUILDINFO_COMPILED_SIMD_LEVEL_OFFSET);
+            layout.check("FFIBuildInfo.detected_simd_level offset", FFIBUILDINFO_DETECTED_SIMD_LEVEL_OFFSET);
+
+            layout.check("FFISearchOptions size", FFISEARCHOPTIONS_SIZE);
+            layout.check("FFISearchOptions alignment", FFISEARCHOPTIONS_ALIGNMENT);
+            layout.check("FFISearchOptions.search_distance offset", FFISEARCHOPTIONS_SEARCH_DISTANCE_OFFSET);
+            layout.check(
+                    "FFISearchOptions.vertical_search_distance offset",
+                    FFISEARCHOPTIONS_VERTICAL_SEARCH_DISTANCE_OFFSET
+            );
+            layout.check("FFISearchOptions.bounds_extension offset", FFISEARCHOPTIONS_BOUNDS_EXTENSION_OFFSET);
+            layout.check("FFISearchOptions.fog_shape offset", FFISEARCHOPTIONS_FOG_SHAPE_OFFSET);
+            layout.check(
+                    "FFISearchOptions.use_occlusion_culling offset",
+                    FFISEARCHOPTIONS_USE_OCCLUSION_CULLING_OFFSET
+            );
+            layout.check("FFISearchOptions.use_angle_culling offset", FFISEARCHOPTIONS_USE_ANGLE_CULLING_OFFSET);
+            layout.check(
+                    "FFISearchOptions.use_outward_direction_masks offset",
+                    FFISEARCHOPTIONS_USE_OUTWARD_DIRECTION_MASKS_OFFSET
+            );
+            layout.check("FFISearchOptions.use_fog_culling offset", FFISEARCHOPTIONS_USE_FOG_CULLING_OFFSET);
+            layout.check("FFISearchOptions.use_height_culling offset", FFISEARCHOPTIONS_USE_HEIGHT_CULLING_OFFSET);
+
+            layout.check("FFIBoundingBox size", FFIBOUNDINGBOX_SIZE);
+            layout.check("FFIBoundingBox alignment", FFIBOUNDINGBOX_ALIGNMENT);
+            layout.check("FFIBoundingBox.min offset", FFIBOUNDINGBOX_MIN_OFFSET);
+            layout.check("FFIBoundingBox.max offset", FFIBOUNDINGBOX_MAX_OFFSET);
+        }
     }
 
-    private static String getCPUFeatures(Platform.Architecture architecture) {
-        if (architecture.equals(Platform.Architecture.X64)) {
-            var cpuFeatureStrings = new SystemInfo().getHardware().getProcessor().getFeatureFlags();
+    private static class LayoutReader {
+        private final long pLayout;
+        private int fieldIdx = 0;
 
-            // Windows does not let us check for the presence of FMA in its API, so we'll just assume it's present if
-            // AVX2 is present. Are there any CPUs where this isn't the case?
-            var hasAVX2 = false;
-            var hasSSE41 = false;
-            var hasSSSE3 = false;
+        private LayoutReader(long pLayout) {
+            this.pLayout = pLayout;
+        }
 
-            for (var cpuFeatureString : cpuFeatureStrings) {
-                var lowercaseFeatureString = cpuFeatureString.toLowerCase();
-                hasAVX2 |= lowercaseFeatureString.contains("avx2");
-                hasSSE41 |= lowercaseFeatureString.contains("sse4_1") || lowercaseFeatureString.contains("sse4.1");
-                hasSSSE3 |= lowercaseFeatureString.contains("ssse3");
+        private void check(String name, int expected) {
+            var actual = MemoryUtil.memGetInt(this.pLayout + ((long) this.fieldIdx * Integer.BYTES));
+            this.fieldIdx++;
+
+            if (actual != expected) {
+                throw new IllegalStateException(String.format(
+                        "Native struct layout doesn't match - %s: expected %d, native library has %d",
+                        name,
+                        expected,
+                        actual
+                ));
             }
+        }
+    }
 
-            if (hasAVX2) {
-                return "-avx2+fma";
-            } else if (hasSSE41 && hasSSSE3) {
-                return "-sse4_1+ssse3";
+    private static void logBuildInfo() {
+        try (var stack = MemoryStack.stackPush()) {
+            long pBuildInfo = stack.ncalloc(FFIBUILDINFO_ALIGNMENT, 1, FFIBUILDINFO_SIZE);
+            byte status = getBuildInfo(pBuildInfo);
+            if (status != STATUS_OK) {
+                HyperCullClientMod.LOGGER.warn("Error getting native library build info: {}", getErrorMessage());
+                return;
             }
+
+            HyperCullClientMod.LOGGER.info(
+                    "Loaded native culling library {} - Features: [{}], Compiled SIMD Level: {}, Detected SIMD Level: {}",
+                    readString(pBuildInfo + FFIBUILDINFO_VERSION_OFFSET),
+                    readString(pBuildInfo + FFIBUILDINFO_FEATURES_OFFSET),
+                    getSimdLevelName(MemoryUtil.memGetByte(pBuildInfo + FFIBUILDINFO_COMPILED_SIMD_LEVEL_OFFSET)),
+                    getSimdLevelName(MemoryUtil.memGetByte(pBuildInfo + FFIBUILDINFO_DETECTED_SIMD_LEVEL_OFFSET))
+            );
         }
+    }
+
+    /**
+     * @param pSlice a pointer to an {@code FFISlice<u8>} holding a UTF-8 encoded string
+     */
+    private static String readString(long pSlice) {
+        var dataPtr = MemoryUtil.memGetAddress(pSlice + FFISLICE_DATA_PTR_OFFSET);
+        var count = (int) MemoryUtil.memGetAddress(pSlice + FFISLICE_COUNT_OFFSET);
+        return MemoryUtil.memUTF8(dataPtr, count);
+    }
 
-        return "";
+    /**
+     * @param simdLevel one of the {@code SIMD_LEVEL_} constants
+     */
+    public static String getSimdLevelName(byte simdLevel) {
+        return switch (simdLevel) {
+            case SIMD_LEVEL_BASELINE -> "baseline";
+            case SIMD_LEVEL_SSE41 -> "sse4.1";
+            case SIMD_LEVEL_AVX2 -> "avx2";
+            case SIMD_LEVEL_AVX512 -> "avx512";
+            default -> "unknown (" + simdLevel + ")";
+        };
     }
 
     private static PanicCallback initPanicHandler() {
@@ -104,6 +344,40 @@ public class HyperCullNativeLib {
         }
     }
 
+    private static LogCallback initLogHandler() {
+        var logCallback = LogCallback.defaultHandler();
+        setLogHandler(logCallback.address(), getDefaultLogLevel());
+        return logCallback;
+    }
+
+    /**
+     * @return the most verbose level enabled in the mod's logger, so native records that would be dropped by the
+     * logger aren't formatted in the first place
+     */
+    private static byte getDefaultLogLevel() {
+        var logger = HyperCullClientMod.LOGGER;
+
+        if (logger.isTraceEnabled()) {
+            return LOG_LEVEL_TRACE;
+        } else if (logger.isDebugEnabled()) {
+            return LOG_LEVEL_DEBUG;
+        } else if (logger.isInfoEnabled()) {
+            return LOG_LEVEL_INFO;
+        } else if (logger.isWarnEnabled()) {
+            return LOG_LEVEL_WARN;
+        } else if (logger.isErrorEnabled()) {
+            return LOG_LEVEL_ERROR;
+        } else {
+            return LOG_LEVEL_OFF;
+        }
+    }
+
+    public static void freeLogHandler() {
+        if (LOG_CALLBACK != null) {
+            LOG_CALLBACK.free();
+        }
+    }
+
     public static long frustumCreate(
             MemoryStack stack,
             SixPlaneFrustum frustum,
@@ -145,49 +419,222 @@ public class HyperCullNativeLib {
         MemoryUtil.memPutFloat(pFrustum + 88, planes[PLANE_PZ].z);
         MemoryUtil.memPutFloat(pFrustum + 92, planes[PLANE_PZ].w);
 
-        MemoryUtil.memPutDouble(pFrustum + 96, transform.x);
-        MemoryUtil.memPutDouble(pFrustum + 104, transform.y);
-        MemoryUtil.memPutDouble(pFrustum + 112, transform.z);
+        MemoryUtil.memPutDouble(pFrustum + FFICAMERA_POS_OFFSET, transform.x);
+        MemoryUtil.memPutDouble(pFrustum + FFICAMERA_POS_OFFSET + 8, transform.y);
+        MemoryUtil.memPutDouble(pFrustum + FFICAMERA_POS_OFFSET + 16, transform.z);
 
         return pFrustum;
     }
 
+    public static long searchOptionsCreate(MemoryStack stack, SearchOptions options) {
+        long pOptions = stack.nmalloc(FFISEARCHOPTIONS_ALIGNMENT, FFISEARCHOPTIONS_SIZE);
+
+        MemoryUtil.memPutFloat(pOptions + FFISEARCHOPTIONS_SEARCH_DISTANCE_OFFSET, options.searchDistance);
+        MemoryUtil.memPutFloat(
+                pOptions + FFISEARCHOPTIONS_VERTICAL_SEARCH_DISTANCE_OFFSET,
+                options.verticalSearchDistance
+        );
+        MemoryUtil.memPutFloat(pOptions + FFISEARCHOPTIONS_BOUNDS_EXTENSION_OFFSET, options.boundsExtension);
+        MemoryUtil.memPutByte(pOptions + FFISEARCHOPTIONS_FOG_SHAPE_OFFSET, options.fogShape);
+        putBool(pOptions + FFISEARCHOPTIONS_USE_OCCLUSION_CULLING_OFFSET, options.useOcclusionCulling);
+        putBool(pOptions + FFISEARCHOPTIONS_USE_ANGLE_CULLING_OFFSET, options.useAngleCulling);
+        putBool(pOptions + FFISEARCHOPTIONS_USE_OUTWARD_DIRECTION_MASKS_OFFSET, options.useOutwardDirectionMasks);
+        putBool(pOptions + FFISEARCHOPTIONS_USE_FOG_CULLING_OFFSET, options.useFogCulling);
+        putBool(pOptions + FFISEARCHOPTIONS_USE_HEIGHT_CULLING_OFFSET, options.useHeightCulling);
+
+        return pOptions;
+    }
+
+    public static void boundingBoxPut(
+            long pBox,
+            double minX,
+            double minY,
+            double minZ,
+            double maxX,
+            double maxY,
+            double maxZ
+    ) {
+        MemoryUtil.memPutDouble(pBox + FFIBOUNDINGBOX_MIN_OFFSET, minX);
+        MemoryUtil.memPutDouble(pBox + FFIBOUNDINGBOX_MIN_OFFSET + Double.BYTES, minY);
+        MemoryUtil.memPutDouble(pBox + FFIBOUNDINGBOX_MIN_OFFSET + (Double.BYTES * 2), minZ);
+        MemoryUtil.memPutDouble(pBox + FFIBOUNDINGBOX_MAX_OFFSET, maxX);
+        MemoryUtil.memPutDouble(pBox + FFIBOUNDINGBOX_MAX_OFFSET + Double.BYTES, maxY);
+        MemoryUtil.memPutDouble(pBox + FFIBOUNDINGBOX_MAX_OFFSET + (Double.BYTES * 2), maxZ);
+    }
+
+    private static void putBool(long ptr, boolean value) {
+        MemoryUtil.memPutByte(ptr, (byte) (value ? 1 : 0));
+    }
+
+    /**
+     * @return the version of the C API that the native library implements. Rust Type: {@code u32}
+     */
+    private static native int getAbiVersion();
+
+    /**
+     * @param return_value_ptr Rust Type: {@code *mut FFILayout}, which is set to the sizes, alignments and field
+     *                         offsets of every FFI struct, each as a {@code u32}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
+     */
+    private static native byte getLayout(long return_value_ptr);
+
+    /**
+     * @param return_value_ptr Rust Type: {@code *mut FFIBuildInfo}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
+     */
+    private static native byte getBuildInfo(long return_value_ptr);
+
     /**
      * @param panic_handler_fn_ptr Rust Type: {@code PanicHandlerFn}
      */
     private static native void setPanicHandler(long panic_handler_fn_ptr);
 
     /**
+     * @param log_handler_fn_ptr Rust Type: {@code LogHandlerFn}
+     * @param level              one of the {@code LOG_LEVEL_} constants. Rust Type: {@code u8}
+     */
+    private static native void setLogHandler(long log_handler_fn_ptr, byte level);
+
+    /**
+     * Sets the most verbose level that the native library will log, which can be changed at any time. For example,
+     * this can be set to {@link #LOG_LEVEL_TRACE} for a single frame to trace one search.
+     *
+     * @param level one of the {@code LOG_LEVEL_} constants. Rust Type: {@code u8}
+     */
+    public static native void setLogLevel(byte level);
+
+    /**
+     * @param return_value_ptr       Rust Type: {@code *mut GraphHandle}, which is set to the handle of the new graph
      * @param render_distance        Rust Type: {@code u8}
      * @param world_bottom_section_y Rust Type: {@code i8}
      * @param world_top_section_y    Rust Type: {@code i8}
-     * @return a native pointer to a Graph instance allocated with the system allocator.
-     *                               Rust Type: {@code *mut Graph}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
      */
-    public static native long graphCreate(byte render_distance, byte world_bottom_section_y, byte world_top_section_y);
+    public static native byte graphCreate(long return_value_ptr, byte render_distance, byte world_bottom_section_y, byte world_top_section_y);
 
     /**
-     * @param graph_ptr              Rust Type: {@code *mut Graph}
+     * @param graph_handle           Rust Type: {@code GraphHandle}
      * @param x                      Rust Type: {@code i32}
      * @param y                      Rust Type: {@code i32}
      * @param z                      Rust Type: {@code i32}
      * @param visibility_bitmask     Rust Type: {@code u64}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
+     */
+    public static native byte graphSetSection(long graph_handle, int x, int y, int z, long visibility_bitmask);
+
+    /**
+     * @param return_value_ptr Rust Type: {@code *mut FFISlice<FFITile>}
+     * @param graph_handle     Rust Type: {@code GraphHandle}
+     * @param camera_ptr       Rust Type: {@code *const FFICamera}
+     * @param options_ptr      Rust Type: {@code *const FFISearchOptions}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
      */
-    public static native void graphSetSection(long graph_ptr, int x, int y, int z, long visibility_bitmask);
+    public static native byte graphSearch(long return_value_ptr, long graph_handle, long camera_ptr, long options_ptr);
 
     /**
-     * @param return_value_ptr      Rust Type: {@code *mut FFISlice<FFIVisibleSectionsTile>}
-     * @param graph_ptr             Rust Type: {@code *mut Graph}
-     * @param camera_ptr            Rust Type: {@code *const FFICamera}
-     * @param search_distance       Rust Type: {@code f32}
-     * @param use_occlusion_culling Rust Type: {@code bool}
+     * Deletes the graph, after which its handle is invalid.
+     *
+     * @param graph_handle Rust Type: {@code GraphHandle}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
      */
-    public static native void graphSearch(long return_value_ptr, long graph_ptr, long camera_ptr, float search_distance, boolean use_occlusion_culling);
+    public static native byte graphDelete(long graph_handle);
 
     /**
-     * @param graph_ptr Rust Type: {@code *mut Graph}
+     * @param return_value_ptr Rust Type: {@code *mut u8}, which is set to the set of CPU features that the graph's
+     *                         searches run with, as one of the {@code SIMD_LEVEL_} constants
+     * @param graph_handle     Rust Type: {@code GraphHandle}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
+     */
+    public static native byte graphGetSimdLevel(long return_value_ptr, long graph_handle);
+
+    /**
+     * Records the result of every culling stage for each tile processed in the next search.
+     *
+     * @param graph_handle Rust Type: {@code GraphHandle}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
+     */
+    public static native byte graphCaptureNextSearch(long graph_handle);
+
+    /**
+     * @param return_value_ptr Rust Type: {@code *mut FFISlice<FFITileCapture>}
+     * @param graph_handle     Rust Type: {@code GraphHandle}
+     * @return one of the {@code STATUS_} constants, which is {@code STATUS_NO_CAPTURE} if no search has been captured
+     * yet. Rust Type: {@code u8}
+     */
+    public static native byte graphGetCapture(long return_value_ptr, long graph_handle);
+
+    /**
+     * @param graph_handle Rust Type: {@code GraphHandle}
+     * @param path_ptr     Rust Type: {@code *const u8}, pointing to a UTF-8 encoded path
+     * @param path_len     Rust Type: {@code usize}, the length of the path in bytes
+     * @return one of the {@code STATUS_} constants, which is {@code STATUS_NO_CAPTURE} if no search has been captured
+     * yet. Rust Type: {@code u8}
+     */
+    public static native byte graphWriteCapture(long graph_handle, long path_ptr, long path_len);
+
+    /**
+     * Determines which culling stage decided the visibility of a section, using the camera from the last search.
+     * This re-runs the last search, so it should only be used for debugging.
+     *
+     * @param return_value_ptr Rust Type: {@code *mut FFISectionDiagnosis}
+     * @param graph_handle     Rust Type: {@code GraphHandle}
+     * @param x                Rust Type: {@code i32}
+     * @param y                Rust Type: {@code i32}
+     * @param z                Rust Type: {@code i32}
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
+     */
+    public static native byte graphDiagnoseSection(long return_value_ptr, long graph_handle, int x, int y, int z);
+
+    /**
+     * Tests whether each box is inside the frustum from the last search, and overlaps a section that the search found
+     * visible. No boxes are visible before the first search.
+     *
+     * @param results_ptr  Rust Type: {@code *mut bool}, with one element for each box
+     * @param graph_handle Rust Type: {@code GraphHandle}
+     * @param boxes_ptr    Rust Type: {@code *const FFIBoundingBox}
+     * @param count        Rust Type: {@code u32}, so it must not be negative
+     * @return one of the {@code STATUS_} constants. Rust Type: {@code u8}
+     */
+    public static native byte graphTestBoxes(long results_ptr, long graph_handle, long boxes_ptr, int count);
+
+    /**
+     * The message stays valid until another error is returned on the same thread.
+     *
+     * @param return_value_ptr Rust Type: {@code *mut FFISlice<u8>}, which is set to the UTF-8 encoded message of the
+     *                         last error returned on the calling thread
+     */
+    private static native void errorGetMessage(long return_value_ptr);
+
+    /**
+     * @return the message of the last error returned by a native function on the calling thread
+     */
+    public static String getErrorMessage() {
+        try (var stack = MemoryStack.stackPush()) {
+            long pMessage = stack.ncalloc(FFISLICE_ALIGNMENT, 1, FFISLICE_SIZE);
+            errorGetMessage(pMessage);
+
+            return readString(pMessage);
+        }
+    }
+
+    /**
+     * Starts recording every graph call to a trace file, which can be replayed outside of the game. Any trace that
+     * was already being recorded is stopped.
+     *
+     * @param path_ptr Rust Type: {@code *const u8}, pointing to a UTF-8 encoded path
+     * @param path_len Rust Type: {@code usize}, the length of the path in bytes
+     * @return one of the {@code STATUS_} constants, which is {@code STATUS_IO} if the trace file could not be created.
+     * Rust Type: {@code u8}
+     */
+    public static native byte traceStart(long path_ptr, long path_len);
+
+    /**
+     * Stops recording the current trace.
+     *
+     * @return one of the {@code STATUS_} constants, which is {@code STATUS_IO} if the trace could not be fully
+     * written. Rust Type: {@code u8}
      */
-    public static native void graphDelete(long graph_ptr);
+    public static native byte traceStop();
 
     /**
      * <p>Rounds the integer {@param num} up to the next multiple of {@param alignment}. This multiple *MUST* be
@@ -0,0 +1,46 @@
+package com.github.burgerindividual.hypercull.client.ffi;
+
+import com.github.burgerindividual.hypercull.client.HyperCullClientMod;
+import org.lwjgl.system.Callback;
+import org.lwjgl.system.MemoryUtil;
+import org.slf4j.Logger;
+import org.slf4j.LoggerFactory;
+
+import java.util.Map;
+import java.util.concurrent.ConcurrentHashMap;
+
+public class LogCallback extends Callback {
+    private LogCallback(long address) {
+        super(address);
+    }
+
+    /**
+     * Forwards records to a logger for each native module, named after the mod and the module.
+     */
+    public static LogCallback defaultHandler() {
+        Map<String, Logger> loggers = new ConcurrentHashMap<>();
+
+        var callback = new LogCallbackI() {
+            @Override
+            public void invoke(byte level, long targetAddress, int targetLength, long messageAddress, int messageLength) {
+                var target = MemoryUtil.memUTF8(targetAddress, targetLength);
+                var message = MemoryUtil.memUTF8(messageAddress, messageLength);
+
+                var logger = loggers.computeIfAbsent(
+                        target,
+                        t -> LoggerFactory.getLogger(HyperCullClientMod.MOD_NAME + "/" + t)
+                );
+
+                switch (level) {
+                    case HyperCullNativeLib.LOG_LEVEL_ERROR -> logger.error(message);
+                    case HyperCullNativeLib.LOG_LEVEL_WARN -> logger.warn(message);
+                    case HyperCullNativeLib.LOG_LEVEL_INFO -> logger.info(message);
+                    case HyperCullNativeLib.LOG_LEVEL_DEBUG -> logger.debug(message);
+                    default -> logger.trace(message);
+                }
+            }
+        };
+
+        return new LogCallback(callback.address());
+    }
+}
@@ -0,0 +1,51 @@
+package com.github.burgerindividual.hypercull.client.ffi;
+
+import org.jetbrains.annotations.NotNull;
+import org.lwjgl.system.CallbackI;
+import org.lwjgl.system.NativeType;
+import org.lwjgl.system.libffi.FFICIF;
+
+import static org.lwjgl.system.APIUtil.apiCreateCIF;
+import static org.lwjgl.system.MemoryUtil.*;
+import static org.lwjgl.system.libffi.LibFFI.*;
+
+@FunctionalInterface
+public interface LogCallbackI extends CallbackI {
+    FFICIF CIF = apiCreateCIF(
+            FFI_DEFAULT_ABI,
+            ffi_type_void,
+            ffi_type_uint8, ffi_type_pointer, ffi_type_sint32, ffi_type_pointer, ffi_type_sint32);
+
+    @Override
+    default @NotNull FFICIF getCallInterface() {
+        return CIF;
+    }
+
+    @Override
+    default void callback(long ret, long args) {
+        this.invoke(
+                memGetByte(memGetAddress(args)),
+                memGetAddress(memGetAddress(args + POINTER_SIZE)),
+                memGetInt(memGetAddress(args + 2L * POINTER_SIZE)),
+                memGetAddress(memGetAddress(args + 3L * POINTER_SIZE)),
+                memGetInt(memGetAddress(args + 4L * POINTER_SIZE)));
+    }
+
+    /**
+     * Will be called for each log record from the native library. The strings are only valid until this function
+     * returns.
+     *
+     * @param level          one of the {@code LOG_LEVEL_} constants in {@link HyperCullNativeLib}
+     * @param targetAddress  a pointer to a UTF-8 encoded string containing the module that logged the record
+     * @param targetLength   the length of the target (in bytes)
+     * @param messageAddress a pointer to a UTF-8 encoded string containing the message
+     * @param messageLength  the length of the message (in bytes)
+     */
+    void invoke(
+            @NativeType("uint8_t") byte level,
+            @NativeType("char *") long targetAddress,
+            @NativeType("int") int targetLength,
+            @NativeType("char *") long messageAddress,
+            @NativeType("int") int messageLength
+    );
+}
@@ -17,12 +17,14 @@ public class PanicCallback extends Callback {
         var callback = new PanicCallbackI() {
             @Override
             public void invoke(long address, int length) {
-                String panicMessage;
+                PanicReport report = null;
 
                 if (address != MemoryUtil.NULL) {
-                    panicMessage = MemoryUtil.memUTF8(address, length);
-                } else {
-                    panicMessage = "(no available information.)";
+                    try {
+                        report = PanicReport.read(address, length);
+                    } catch (RuntimeException e) {
+                        // a malformed report shouldn't stop the rest of the log from being written
+                    }
                 }
 
                 Thread thread = Thread.currentThread();
@@ -40,12 +42,28 @@ public class PanicCallback extends Callback {
 
                 log.append("# Details:\n");
 
-                log.append("# \tDescription:\n")
-                        // prepends each line with a prefix to match the surrounding text
-                        .append(panicMessage.replaceAll(
-                                ".*\\R|.+\\z",
-                                Matcher.quoteReplacement("# \t\t") + "$0"))
-                        .append('\n');
+                if (report != null) {
+                    log.append("# \tDescription:\n")
+                            // prepends each line with a prefix to match the surrounding text
+                            .append(report.message().replaceAll(
+                                    ".*\\R|.+\\z",
+                                    Matcher.quoteReplacement("# \t\t") + "$0"))
+                            .append('\n');
+
+                    log.append("# \tLocation: ");
+                    if (report.file() != null) {
+                        log.append(report.file())
+                                .append(':').append(report.line())
+                                .append(':').append(report.column());
+                    } else {
+                        log.append("(unknown)");
+                    }
+                    log.append('\n');
+
+                    log.append("# \tNative thread: '").append(report.threadName()).append("'\n");
+                } else {
+                    log.append("# \tDescription:\n# \t\t(no available information.)\n");
+                }
 
                 log.append("# \tFaulting thread: ")
                         .append("'")
@@ -71,6 +89,14 @@ public class PanicCallback extends Callback {
                     log.append("# \t\t(no stack trace information is available...)\n");
                 }
 
+                if (report != null && !report.frames().isEmpty()) {
+                    log.append("# \tNative backtrace:\n");
+
+                    for (int i = 0; i < report.frames().size(); i++) {
+                        log.append("# \t\t").append(i).append(": ").append(report.frames().get(i)).append('\n');
+                    }
+                }
+
                 log.append("# \n");
                 log.append("# This is not a recoverable error. The Java process will now be forcefully aborted.");
 
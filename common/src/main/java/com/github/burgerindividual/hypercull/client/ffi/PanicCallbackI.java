@@ -15,7 +15,7 @@ public interface PanicCallbackI extends CallbackI {
     FFICIF CIF = apiCreateCIF(
             FFI_DEFAULT_ABI,
             ffi_type_void,
-            ffi_type_sint32, ffi_type_pointer);
+            ffi_type_pointer, ffi_type_sint32);
 
     @Override
     default @NotNull FFICIF getCallInterface() {
@@ -34,8 +34,8 @@ public interface PanicCallbackI extends CallbackI {
      * that the JVM aborts
      * before this function returns!
      *
-     * @param address a pointer to a UTF-8 encoded string describing the panic
-     * @param length  the length of the string (in bytes)
+     * @param address a pointer to a serialized {@link PanicReport} describing the panic
+     * @param length  the length of the report (in bytes)
      */
     void invoke(@NativeType("char *") long address, @NativeType("int") int length);
 }
\ No newline at end of file
@@ -0,0 +1,59 @@
+package com.github.burgerindividual.hypercull.client.ffi;
+
+import org.lwjgl.system.MemoryUtil;
+
+import java.nio.ByteBuffer;
+import java.nio.ByteOrder;
+import java.nio.charset.StandardCharsets;
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * A panic report serialized by the native library. See {@code PanicReport} in panic.rs for the layout.
+ *
+ * @param file   the file the panic occurred in, or null if it is unknown
+ * @param frames the frames of the native backtrace, which is empty unless the library was built with the
+ *               {@code backtrace} feature
+ */
+public record PanicReport(String message, String file, int line, int column, String threadName, List<Frame> frames) {
+    /**
+     * @param file the file of the frame, or null if it is unknown
+     */
+    public record Frame(String function, String file, int line) {
+        @Override
+        public String toString() {
+            if (this.file == null) {
+                return this.function;
+            } else {
+                return this.function + " (" + this.file + ":" + this.line + ")";
+            }
+        }
+    }
+
+    public static PanicReport read(long address, int length) {
+        var buffer = MemoryUtil.memByteBuffer(address, length).order(ByteOrder.LITTLE_ENDIAN);
+
+        var message = readString(buffer);
+        var file = readString(buffer);
+        var line = buffer.getInt();
+        var column = buffer.getInt();
+        var threadName = readString(buffer);
+
+        var frameCount = buffer.getInt();
+        var frames = new ArrayList<Frame>(frameCount);
+        for (int i = 0; i < frameCount; i++) {
+            var function = readString(buffer);
+            var frameFile = readString(buffer);
+            var frameLine = buffer.getInt();
+            frames.add(new Frame(function, frameFile.isEmpty() ? null : frameFile, frameLine));
+        }
+
+        return new PanicReport(message, file.isEmpty() ? null : file, line, column, threadName, frames);
+    }
+
+    private static String readString(ByteBuffer buffer) {
+        var bytes = new byte[buffer.getInt()];
+        buffer.get(bytes);
+        return new String(bytes, StandardCharsets.UTF_8);
+    }
+}
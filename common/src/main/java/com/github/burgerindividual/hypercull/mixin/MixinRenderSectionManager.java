@@ -1,11 +1,12 @@
 package com.github.burgerindividual.hypercull.mixin;
 
+import com.github.burgerindividual.hypercull.client.HyperCullClientMod;
 import com.github.burgerindividual.hypercull.client.SixPlaneFrustum;
 import com.github.burgerindividual.hypercull.client.NativeGraph;
+import com.github.burgerindividual.hypercull.client.SearchOptions;
 import com.github.burgerindividual.hypercull.client.ffi.HyperCullNativeLib;
 import com.llamalad7.mixinextras.injector.wrapoperation.Operation;
 import com.llamalad7.mixinextras.injector.wrapoperation.WrapOperation;
-import it.unimi.dsi.fastutil.longs.Long2ReferenceMap;
 import net.caffeinemc.mods.sodium.client.gl.device.CommandList;
 import net.caffeinemc.mods.sodium.client.render.chunk.RenderSection;
 import net.caffeinemc.mods.sodium.client.render.chunk.RenderSectionManager;
@@ -14,7 +15,6 @@ import net.caffeinemc.mods.sodium.client.render.chunk.occlusion.OcclusionCuller;
 import net.caffeinemc.mods.sodium.client.render.chunk.region.RenderRegionManager;
 import net.caffeinemc.mods.sodium.client.render.viewport.Viewport;
 import net.minecraft.client.multiplayer.ClientLevel;
-import net.minecraft.world.level.Level;
 import org.spongepowered.asm.mixin.*;
 import org.spongepowered.asm.mixin.injection.At;
 import org.spongepowered.asm.mixin.injection.Inject;
@@ -29,26 +29,15 @@ public class MixinRenderSectionManager {
     @Unique
     private NativeGraph nativeGraph = null;
 
-    @WrapOperation(
-            method = "<init>",
-            at = @At(value = "NEW", target = "net/caffeinemc/mods/sodium/client/render/chunk/occlusion/OcclusionCuller")
-    )
-    private OcclusionCuller skipJavaCullerCreation(
-            Long2ReferenceMap<RenderSection> sections,
-            Level level,
-            Operation<OcclusionCuller> original
-    ) {
-        if (HyperCullNativeLib.SUPPORTED) {
-            return null;
-        } else {
-            return original.call(sections, level);
-        }
-    }
+    // Reused every frame. The search distance and occlusion culling follow Sodium, the vertical search distance can be
+    // set with HyperCullClientMod.verticalSearchDistance, and the other stages can be changed here.
+    @Unique
+    private final SearchOptions searchOptions = new SearchOptions();
 
     @Inject(method = "<init>", at = @At(value = "TAIL"))
     private void initNativeGraph(ClientLevel level, int renderDistance, CommandList commandList, CallbackInfo ci) {
         if (HyperCullNativeLib.SUPPORTED) {
-            this.nativeGraph = new NativeGraph(
+            this.nativeGraph = NativeGraph.create(
                     this.regions,
                     (byte) renderDistance,
                     (byte) level.getMinSectionY(),
@@ -70,21 +59,29 @@ public class MixinRenderSectionManager {
             int frame,
             Operation<Void> original
     ) {
+        var verticalSearchDistance = HyperCullClientMod.verticalSearchDistance;
+        this.searchOptions.searchDistance = searchDistance;
+        this.searchOptions.verticalSearchDistance = verticalSearchDistance >= 0.0f
+                ? verticalSearchDistance
+                : searchDistance;
+        this.searchOptions.useOcclusionCulling = useOcclusionCulling;
+
+        // Sodium's culler is still created, so it can be used whenever the native search fails
         //noinspection ConstantValue
         if (HyperCullNativeLib.SUPPORTED
                 && this.nativeGraph != null
-                && ((ViewportAccessor) (Object) viewport).getFrustum() instanceof SixPlaneFrustum sixPlaneFrustum) {
-            this.nativeGraph.findVisible(
-                    visitor,
-                    sixPlaneFrustum,
-                    viewport.getTransform(),
-                    searchDistance,
-                    useOcclusionCulling,
-                    frame
-            );
-        } else {
-            original.call(instance, visitor, viewport, searchDistance, useOcclusionCulling, frame);
+                && ((ViewportAccessor) (Object) viewport).getFrustum() instanceof SixPlaneFrustum sixPlaneFrustum
+                && this.nativeGraph.findVisible(
+                        visitor,
+                        sixPlaneFrustum,
+                        viewport.getTransform(),
+                        this.searchOptions,
+                        frame
+                )) {
+            return;
         }
+
+        original.call(instance, visitor, viewport, searchDistance, useOcclusionCulling, frame);
     }
 
     @WrapOperation(
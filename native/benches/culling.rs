@@ -0,0 +1,352 @@
+//! Benchmarks for the graph search and the culling stages it's built from.
+//!
+//! Results can be compared against a saved baseline with Criterion's baseline
+//! options. Save one before making a change, then compare against it after:
+//!
+//! ```text
+//! cargo bench --features bench -- --save-baseline before
+//! cargo bench --features bench -- --baseline before
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use hypercull_native::internal::*;
+
+const WORLD_BOTTOM_SECTION_Y: i8 = -4;
+const WORLD_TOP_SECTION_Y: i8 = 19;
+
+const RENDER_DISTANCES: [u8; 3] = [8, 16, 32];
+// camera yaw and pitch in degrees
+const CAMERA_HEADINGS: [(f32, f32); 4] = [(0.0, 0.0), (45.0, 0.0), (135.0, -30.0), (270.0, 60.0)];
+
+const AIR: u64 = connect_faces(ALL_ORDINALS);
+const SOLID: u64 = 0;
+// a section with the ground in the lower half, so nothing can pass through the
+// bottom face
+const SURFACE: u64 = connect_faces(ALL_ORDINALS & !(1 << DOWN));
+// a section with a ceiling in the upper half, like the underside of an island
+const CEILING: u64 = connect_faces(ALL_ORDINALS & !(1 << UP));
+
+#[derive(Clone, Copy)]
+enum World {
+    Superflat,
+    OpenOcean,
+    Mountains,
+    DenseCaves,
+    SkyIsland,
+}
+
+impl World {
+    const ALL: [World; 5] = [
+        World::Superflat,
+        World::OpenOcean,
+        World::Mountains,
+        World::DenseCaves,
+        World::SkyIsland,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            World::Superflat => "superflat",
+            World::OpenOcean => "open_ocean",
+            World::Mountains => "mountains",
+            World::DenseCaves => "dense_caves",
+            World::SkyIsland => "sky_island",
+        }
+    }
+
+    /// The block height of the camera, which is placed just above the ground.
+    fn camera_y(self) -> f64 {
+        match self {
+            World::Superflat => -58.5,
+            World::OpenOcean => 63.5,
+            World::Mountains => 180.5,
+            World::DenseCaves => 20.5,
+            World::SkyIsland => 200.5,
+        }
+    }
+
+    fn section_visibility(self, x: i32, y: i32, z: i32) -> u64 {
+        match self {
+            World::Superflat => ground(y, -4),
+            // water doesn't block visibility, so only the sea floor matters
+            World::OpenOcean => ground(y, 2),
+            World::Mountains => {
+                let height = 5.0
+                    + 4.0 * ((x as f32 * 0.21).sin() + (z as f32 * 0.17).cos())
+                    + 2.0 * ((x + z) as f32 * 0.43).sin();
+                ground(y, height as i32)
+            }
+            World::DenseCaves => {
+                if y >= 4 {
+                    ground(y, 4)
+                } else {
+                    // a deterministic scramble of the coordinates, so caves open in
+                    // roughly half of the directions of each section
+                    let hash = (x as u64)
+                        .wrapping_mul(0x9e3779b97f4a7c15)
+                        .wrapping_add((y as u64).wrapping_mul(0xc2b2ae3d27d4eb4f))
+                        .wrapping_add((z as u64).wrapping_mul(0x165667b19e3779f9));
+                    AIR & (hash ^ (hash >> 29))
+                }
+            }
+            World::SkyIsland => {
+                let island_distance_sq = x * x + z * z;
+                if island_distance_sq > 36 {
+                    AIR
+                } else if y == 12 {
+                    SURFACE
+                } else if y == 11 {
+                    SOLID
+                } else if y == 10 {
+                    CEILING
+                } else {
+                    AIR
+                }
+            }
+        }
+    }
+}
+
+fn ground(y: i32, surface_y: i32) -> u64 {
+    if y < surface_y {
+        SOLID
+    } else if y == surface_y {
+        SURFACE
+    } else {
+        AIR
+    }
+}
+
+fn populate_graph(graph: &mut Graph, world: World) {
+    let xz_length_sections = graph.coord_space.xz_length_tiles as i32 * 8;
+    let xz_range = -(xz_length_sections / 2)..(xz_length_sections / 2);
+
+    for y in WORLD_BOTTOM_SECTION_Y as i32..=WORLD_TOP_SECTION_Y as i32 {
+        for z in xz_range.clone() {
+            for x in xz_range.clone() {
+                graph.set_section(i32x3::from_xyz(x, y, z), world.section_visibility(x, y, z));
+            }
+        }
+    }
+}
+
+fn build_graph(world: World, render_distance: u8) -> Graph {
+    let mut graph = Graph::new(render_distance, WORLD_BOTTOM_SECTION_Y, WORLD_TOP_SECTION_Y);
+    populate_graph(&mut graph, world);
+    graph
+}
+
+/// Creates the planes of a perspective frustum with a 70 degree vertical FOV
+/// and a 16:9 aspect ratio.
+fn frustum_planes(yaw_degrees: f32, pitch_degrees: f32, far_distance: f32) -> [f32x4; 6] {
+    perspective_planes(
+        yaw_degrees.to_radians(),
+        pitch_degrees.to_radians(),
+        70.0_f32.to_radians(),
+        16.0 / 9.0,
+        far_distance,
+    )
+}
+
+/// Tile positions relative to the camera, spread over a full render distance
+/// in every direction.
+fn relative_tile_positions() -> Vec<f32x3> {
+    let mut positions = Vec::new();
+
+    for y in -2..2 {
+        for z in -4..4 {
+            for x in -4..4 {
+                let offset = f32x3::from_xyz(3.3, 7.9, 11.1);
+                positions.push(
+                    f32x3::from_xyz(x as f32, y as f32, z as f32) * Simd::splat(128.0) + offset,
+                );
+            }
+        }
+    }
+
+    positions
+}
+
+fn bench_cull(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cull");
+
+    for world in World::ALL {
+        for render_distance in RENDER_DISTANCES {
+            let mut graph = build_graph(world, render_distance);
+            let search_distance = render_distance as f32 * 16.0;
+
+            for (yaw, pitch) in CAMERA_HEADINGS {
+                let context = GraphSearchContext::new(
+                    &graph.coord_space,
+                    frustum_planes(yaw, pitch, search_distance + 32.0),
+                    f64x3::from_xyz(8.5, world.camera_y(), 8.5),
+                    &SearchOptions::new(search_distance),
+                );
+
+                group.bench_function(
+                    BenchmarkId::new(
+                        format!("{}/rd{render_distance}", world.name()),
+                        format!("yaw{yaw}_pitch{pitch}"),
+                    ),
+                    |b| {
+                        b.iter(|| {
+                            graph.cull(black_box(&context));
+                            black_box(graph.visible_tiles.len())
+                        })
+                    },
+                );
+            }
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_test_boxes(c: &mut Criterion) {
+    const RENDER_DISTANCE: u8 = 16;
+    let mut group = c.benchmark_group("test_boxes");
+
+    for world in World::ALL {
+        let mut graph = build_graph(world, RENDER_DISTANCE);
+        let search_distance = RENDER_DISTANCE as f32 * 16.0;
+        let camera_y = world.camera_y();
+
+        let context = GraphSearchContext::new(
+            &graph.coord_space,
+            frustum_planes(0.0, 0.0, search_distance + 32.0),
+            f64x3::from_xyz(8.5, camera_y, 8.5),
+            &SearchOptions::new(search_distance),
+        );
+        graph.cull(&context);
+
+        // 4096 entity-sized boxes spread around the camera, in and out of the frustum
+        let mut boxes = Vec::new();
+        for y in -4..4 {
+            for z in -16..16 {
+                for x in -8..8 {
+                    let min = [x as f64 * 13.7, camera_y + y as f64 * 9.3, z as f64 * 7.1];
+                    let max = [min[0] + 0.6, min[1] + 1.8, min[2] + 0.6];
+                    boxes.push(BoundingBox { min, max });
+                }
+            }
+        }
+        let mut results = vec![false; boxes.len()];
+
+        group.bench_function(world.name(), |b| {
+            b.iter(|| {
+                graph.test_boxes(black_box(&boxes).iter().copied(), &mut results);
+                black_box(&results);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_set_section(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_section");
+
+    for world in World::ALL {
+        let mut graph = Graph::new(16, WORLD_BOTTOM_SECTION_Y, WORLD_TOP_SECTION_Y);
+
+        group.bench_function(world.name(), |b| {
+            b.iter(|| populate_graph(black_box(&mut graph), world))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_voxelize_planes(c: &mut Criterion) {
+    let positions = relative_tile_positions();
+
+    for level in SimdLevel::supported() {
+        for (yaw, pitch) in CAMERA_HEADINGS {
+            let frustum = Frustum::new(
+                frustum_planes(yaw, pitch, 512.0),
+                SectionGeometry::MINECRAFT,
+            );
+
+            c.bench_function(
+                &format!("voxelize_planes/{level}/yaw{yaw}_pitch{pitch}"),
+                |b| {
+                    b.iter(|| {
+                        for &position in &positions {
+                            let mut visible_sections = u8x64::splat(!0);
+                            // all planes are voxelized to keep the amount of work consistent
+                            voxelize_planes(
+                                level,
+                                &frustum,
+                                0b111111,
+                                black_box(position),
+                                &mut visible_sections,
+                            );
+                            black_box(visible_sections);
+                        }
+                    })
+                },
+            );
+        }
+    }
+}
+
+fn bench_voxelize_fog(c: &mut Criterion) {
+    let positions = relative_tile_positions();
+
+    for level in SimdLevel::supported() {
+        for fog_shape in FogShape::ALL {
+            for render_distance in RENDER_DISTANCES {
+                let fog_distance = render_distance as f32 * 16.0;
+
+                c.bench_function(
+                    &format!("voxelize_fog/{fog_shape}/{level}/rd{render_distance}"),
+                    |b| {
+                        b.iter(|| {
+                            for &position in &positions {
+                                black_box(voxelize_fog(
+                                    level,
+                                    fog_shape,
+                                    black_box(position),
+                                    fog_distance,
+                                    fog_distance,
+                                    SectionGeometry::MINECRAFT,
+                                ));
+                            }
+                        })
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn bench_gen_visibility_masks(c: &mut Criterion) {
+    let positions = relative_tile_positions();
+
+    for level in SimdLevel::supported() {
+        c.bench_function(&format!("gen_visibility_masks/{level}"), |b| {
+            b.iter(|| {
+                for &position in &positions {
+                    black_box(gen_visibility_masks(
+                        level,
+                        black_box(position),
+                        SectionGeometry::MINECRAFT,
+                    ));
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    bench_cull,
+    bench_test_boxes,
+    bench_set_section,
+    bench_voxelize_planes,
+    bench_voxelize_fog,
+    bench_gen_visibility_masks
+);
+criterion_main!(benches);
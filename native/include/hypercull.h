@@ -0,0 +1,422 @@
+#ifndef HYPERCULL_H
+#define HYPERCULL_H
+
+/* Generated with cbindgen from src/ffi.rs. Do not edit this file by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define HYPERCULL_ABI_VERSION 4
+
+#define HYPERCULL_DIRECTION_COUNT 6
+
+#define HYPERCULL_STATUS_OK 0
+#define HYPERCULL_STATUS_NULL_POINTER 1
+#define HYPERCULL_STATUS_INVALID_WORLD_HEIGHT 2
+#define HYPERCULL_STATUS_GRAPH_TOO_LARGE 3
+#define HYPERCULL_STATUS_SECTION_OUT_OF_BOUNDS 4
+#define HYPERCULL_STATUS_INVALID_SEARCH_DISTANCE 5
+#define HYPERCULL_STATUS_INVALID_CAMERA_POSITION 6
+#define HYPERCULL_STATUS_INVALID_HANDLE 7
+#define HYPERCULL_STATUS_INVALID_SECTION_GEOMETRY 8
+#define HYPERCULL_STATUS_INVALID_FOG_SHAPE 9
+#define HYPERCULL_STATUS_INVALID_ARGUMENT 10
+#define HYPERCULL_STATUS_NO_CAPTURE 11
+#define HYPERCULL_STATUS_IO 12
+
+#define HYPERCULL_LOG_LEVEL_OFF 0
+#define HYPERCULL_LOG_LEVEL_ERROR 1
+#define HYPERCULL_LOG_LEVEL_WARN 2
+#define HYPERCULL_LOG_LEVEL_INFO 3
+#define HYPERCULL_LOG_LEVEL_DEBUG 4
+#define HYPERCULL_LOG_LEVEL_TRACE 5
+
+#define HYPERCULL_SIMD_LEVEL_BASELINE 0
+#define HYPERCULL_SIMD_LEVEL_SSE41 1
+#define HYPERCULL_SIMD_LEVEL_AVX2 2
+#define HYPERCULL_SIMD_LEVEL_AVX512 3
+
+#define HYPERCULL_DIAGNOSIS_NO_SEARCH 0
+#define HYPERCULL_DIAGNOSIS_OUTSIDE_FRUSTUM 1
+#define HYPERCULL_DIAGNOSIS_OUTSIDE_FOG 2
+#define HYPERCULL_DIAGNOSIS_OUTSIDE_WORLD_HEIGHT 3
+#define HYPERCULL_DIAGNOSIS_ANGLE_CULLED 4
+#define HYPERCULL_DIAGNOSIS_UNREACHABLE 5
+#define HYPERCULL_DIAGNOSIS_VISIBLE 6
+
+#define HYPERCULL_FOG_SHAPE_CYLINDRICAL 0
+#define HYPERCULL_FOG_SHAPE_SPHERICAL 1
+#define HYPERCULL_FOG_SHAPE_SQUARE 2
+
+/**
+ * Identifies a graph created with `hypercull_graph_create`. Handles are
+ * checked on every call, so using one after its graph was deleted returns
+ * `HYPERCULL_STATUS_INVALID_HANDLE` instead of touching freed memory. 0 is
+ * never a valid handle.
+ *
+ * Calls on different graphs can run at the same time from different threads,
+ * while calls on the same graph wait for each other.
+ */
+typedef uint64_t HyperCullGraphHandle;
+
+typedef void (*PanicHandlerFn)(const uint8_t *data, int32_t len);
+
+typedef void (*LogHandlerFn)(uint8_t level,
+                             const uint8_t *target_ptr,
+                             int32_t target_len,
+                             const uint8_t *message_ptr,
+                             int32_t message_len);
+
+/**
+ * The settings for a single search. `fog_shape` is one of the
+ * `HYPERCULL_FOG_SHAPE_` constants, and a negative `bounds_extension` uses the
+ * extension that the graph was created with.
+ */
+typedef struct FFISearchOptions {
+  float search_distance;
+  float vertical_search_distance;
+  float bounds_extension;
+  uint8_t fog_shape;
+  bool use_occlusion_culling;
+  bool use_angle_culling;
+  bool use_outward_direction_masks;
+  bool use_fog_culling;
+  bool use_height_culling;
+} FFISearchOptions;
+
+typedef struct FFITile {
+  int32_t origin_section_coords[3];
+  uint64_t visible_sections[8];
+} FFITile;
+
+typedef struct FFISlice_FFITile {
+  const FFITile *data_ptr;
+  size_t count;
+} FFISlice_FFITile;
+
+typedef struct FFICamera {
+  float frustum_planes[6][4];
+  double pos[3];
+} FFICamera;
+
+/**
+ * The state of a tile after each culling stage, recorded when a search is
+ * captured. Stages that did not run for the tile repeat the previous stage's
+ * sections, and the angle masks are all 1s when occlusion culling did not run.
+ */
+typedef struct FFITileCapture {
+  int32_t origin_section_coords[3];
+  uint16_t test_results;
+  uint64_t after_frustum[8];
+  uint64_t after_fog[8];
+  uint64_t after_height[8];
+  uint64_t angle_masks[3][8];
+  uint64_t after_traversal[8];
+  uint64_t incoming_dir_section_sets[HYPERCULL_DIRECTION_COUNT][8];
+  uint64_t without_angle_culling[8];
+} FFITileCapture;
+
+typedef struct FFISlice_FFITileCapture {
+  const FFITileCapture *data_ptr;
+  size_t count;
+} FFISlice_FFITileCapture;
+
+/**
+ * The result of a section diagnosis. `stage` is one of the
+ * `HYPERCULL_DIAGNOSIS_` constants, and the direction sets are only populated
+ * for the stages that use them.
+ */
+typedef struct FFISectionDiagnosis {
+  uint8_t stage;
+  uint8_t frustum_plane;
+  uint8_t incoming_dirs;
+  uint8_t visible_neighbors;
+  uint8_t reached_from;
+} FFISectionDiagnosis;
+
+/**
+ * An axis-aligned box in global block coordinates, for testing whether
+ * entities and block entities are visible.
+ */
+typedef struct FFIBoundingBox {
+  double min[3];
+  double max[3];
+} FFIBoundingBox;
+
+/**
+ * The sizes, alignments and field offsets of every FFI struct, in bytes, so
+ * callers that can't include the C header can check that their copy of each
+ * layout matches the library. Every field is a `uint32_t`, so field `n` is at
+ * offset `4 * n`. Fields are only ever added to the end.
+ */
+typedef struct FFILayout {
+  uint32_t tile_size;
+  uint32_t tile_alignment;
+  uint32_t tile_origin_section_coords_offset;
+  uint32_t tile_visible_sections_offset;
+  uint32_t slice_size;
+  uint32_t slice_alignment;
+  uint32_t slice_data_ptr_offset;
+  uint32_t slice_count_offset;
+  uint32_t camera_size;
+  uint32_t camera_alignment;
+  uint32_t camera_frustum_planes_offset;
+  uint32_t camera_pos_offset;
+  uint32_t tile_capture_size;
+  uint32_t tile_capture_alignment;
+  uint32_t tile_capture_origin_section_coords_offset;
+  uint32_t tile_capture_test_results_offset;
+  uint32_t tile_capture_after_frustum_offset;
+  uint32_t tile_capture_after_fog_offset;
+  uint32_t tile_capture_after_height_offset;
+  uint32_t tile_capture_angle_masks_offset;
+  uint32_t tile_capture_after_traversal_offset;
+  uint32_t tile_capture_incoming_dir_section_sets_offset;
+  uint32_t tile_capture_without_angle_culling_offset;
+  uint32_t section_diagnosis_size;
+  uint32_t section_diagnosis_alignment;
+  uint32_t section_diagnosis_stage_offset;
+  uint32_t section_diagnosis_frustum_plane_offset;
+  uint32_t section_diagnosis_incoming_dirs_offset;
+  uint32_t section_diagnosis_visible_neighbors_offset;
+  uint32_t section_diagnosis_reached_from_offset;
+  uint32_t build_info_size;
+  uint32_t build_info_alignment;
+  uint32_t build_info_version_offset;
+  uint32_t build_info_features_offset;
+  uint32_t build_info_compiled_simd_level_offset;
+  uint32_t build_info_detected_simd_level_offset;
+  uint32_t search_options_size;
+  uint32_t search_options_alignment;
+  uint32_t search_options_search_distance_offset;
+  uint32_t search_options_vertical_search_distance_offset;
+  uint32_t search_options_bounds_extension_offset;
+  uint32_t search_options_fog_shape_offset;
+  uint32_t search_options_use_occlusion_culling_offset;
+  uint32_t search_options_use_angle_culling_offset;
+  uint32_t search_options_use_outward_direction_masks_offset;
+  uint32_t search_options_use_fog_culling_offset;
+  uint32_t search_options_use_height_culling_offset;
+  uint32_t bounding_box_size;
+  uint32_t bounding_box_alignment;
+  uint32_t bounding_box_min_offset;
+  uint32_t bounding_box_max_offset;
+} FFILayout;
+
+typedef struct FFISlice_uint8_t {
+  const uint8_t *data_ptr;
+  size_t count;
+} FFISlice_uint8_t;
+
+/**
+ * How the library was built. `version` is the crate version and `features`
+ * is a comma-separated list of the enabled Cargo features, both UTF-8 encoded
+ * and valid for the lifetime of the library. `compiled_simd_level` is the
+ * level that the library requires, and `detected_simd_level` is the best level
+ * that the CPU supports, both as `HYPERCULL_SIMD_LEVEL_` constants.
+ */
+typedef struct FFIBuildInfo {
+  FFISlice_uint8_t version;
+  FFISlice_uint8_t features;
+  uint8_t compiled_simd_level;
+  uint8_t detected_simd_level;
+} FFIBuildInfo;
+
+/**
+ * Returns the version of the C API that the library implements, which should
+ * be equal to the `HYPERCULL_ABI_VERSION` of the header that the caller was
+ * built with.
+ */
+uint32_t hypercull_get_abi_version(void);
+
+/**
+ * Writes the layouts of the FFI structs to `return_value_ptr`. Returns one of
+ * the `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_get_layout(FFILayout *return_value_ptr);
+
+/**
+ * Writes the version, features and SIMD levels of the library to
+ * `return_value_ptr`. Returns one of the `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_get_build_info(FFIBuildInfo *return_value_ptr);
+
+void hypercull_set_panic_handler(PanicHandlerFn panic_handler_fn_ptr);
+
+/**
+ * Forwards log records to the handler, starting at the given level, which is
+ * one of the `HYPERCULL_LOG_LEVEL_` constants.
+ */
+void hypercull_set_log_handler(LogHandlerFn log_handler_fn_ptr, uint8_t level);
+
+/**
+ * Sets the most verbose level that will be logged, which is one of the
+ * `HYPERCULL_LOG_LEVEL_` constants.
+ */
+void hypercull_set_log_level(uint8_t level);
+
+/**
+ * Writes the handle of the new graph to `return_value_ptr`. Returns one of the
+ * `HYPERCULL_STATUS_` codes, and leaves `return_value_ptr` untouched if
+ * creating the graph failed.
+ */
+uint8_t hypercull_graph_create(HyperCullGraphHandle *return_value_ptr,
+                               uint8_t render_distance,
+                               int8_t world_bottom_section_y,
+                               int8_t world_top_section_y);
+
+/**
+ * Same as `hypercull_graph_create`, but with sections that are
+ * `section_length` blocks long on each side, which must be a power of 2 no
+ * larger than 256. Each section's bounding box is extended by
+ * `bounds_extension` blocks on every side to fit block models that stick out
+ * of it.
+ */
+uint8_t hypercull_graph_create_with_geometry(HyperCullGraphHandle *return_value_ptr,
+                                             uint8_t render_distance,
+                                             int8_t world_bottom_section_y,
+                                             int8_t world_top_section_y,
+                                             uint32_t section_length,
+                                             float bounds_extension);
+
+/**
+ * Same as `hypercull_graph_create_with_geometry`, but for cubic chunks style
+ * worlds with no height limit. The graph extends `vertical_render_distance`
+ * sections above and below the camera, and wraps around on the Y axis.
+ */
+uint8_t hypercull_graph_create_cubic(HyperCullGraphHandle *return_value_ptr,
+                                     uint8_t render_distance,
+                                     uint8_t vertical_render_distance,
+                                     uint32_t section_length,
+                                     float bounds_extension);
+
+/**
+ * Returns one of the `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_graph_set_section(HyperCullGraphHandle graph_handle,
+                                    int32_t x,
+                                    int32_t y,
+                                    int32_t z,
+                                    uint64_t visibility_bitmask);
+
+/**
+ * Writes the visible tiles to `return_value_ptr`, which stay valid until the
+ * next search or until the graph is deleted. Returns one of the
+ * `HYPERCULL_STATUS_` codes, and leaves `return_value_ptr` untouched if the
+ * search failed.
+ */
+uint8_t hypercull_graph_search(FFISlice_FFITile *return_value_ptr,
+                               HyperCullGraphHandle graph_handle,
+                               const FFICamera *camera_ptr,
+                               float search_distance,
+                               bool use_occlusion_culling);
+
+/**
+ * Same as `hypercull_graph_search`, but with the shape of the fog given by one
+ * of the `HYPERCULL_FOG_SHAPE_` constants instead of always being cylindrical.
+ */
+uint8_t hypercull_graph_search_with_fog_shape(FFISlice_FFITile *return_value_ptr,
+                                              HyperCullGraphHandle graph_handle,
+                                              const FFICamera *camera_ptr,
+                                              float search_distance,
+                                              bool use_occlusion_culling,
+                                              uint8_t fog_shape);
+
+/**
+ * Same as `hypercull_graph_search`, but with every setting of the search
+ * given by `options_ptr`, including which culling stages are enabled.
+ */
+uint8_t hypercull_graph_search_with_options(FFISlice_FFITile *return_value_ptr,
+                                            HyperCullGraphHandle graph_handle,
+                                            const FFICamera *camera_ptr,
+                                            const FFISearchOptions *options_ptr);
+
+/**
+ * Deletes the graph, after which its handle is invalid. Returns one of the
+ * `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_graph_delete(HyperCullGraphHandle graph_handle);
+
+/**
+ * Writes the SIMD level that was picked for the graph's CPU to
+ * `return_value_ptr`, as one of the `HYPERCULL_SIMD_LEVEL_` constants. Returns
+ * one of the `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_graph_get_simd_level(uint8_t *return_value_ptr, HyperCullGraphHandle graph_handle);
+
+/**
+ * Returns one of the `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_graph_capture_next_search(HyperCullGraphHandle graph_handle);
+
+/**
+ * Writes the tiles of the last captured search to `return_value_ptr`. The
+ * slice stays valid until the next captured search or until the graph is
+ * deleted. Returns one of the `HYPERCULL_STATUS_` codes, which is
+ * `HYPERCULL_STATUS_NO_CAPTURE` if no search has been captured.
+ */
+uint8_t hypercull_graph_get_capture(FFISlice_FFITileCapture *return_value_ptr,
+                                    HyperCullGraphHandle graph_handle);
+
+/**
+ * Writes the last captured search to a file at the given UTF-8 encoded path,
+ * which is `path_len` bytes long. Returns one of the `HYPERCULL_STATUS_`
+ * codes, which is `HYPERCULL_STATUS_NO_CAPTURE` if no search has been
+ * captured.
+ */
+uint8_t hypercull_graph_write_capture(HyperCullGraphHandle graph_handle,
+                                      const uint8_t *path_ptr,
+                                      size_t path_len);
+
+/**
+ * Determines which culling stage decided the visibility of the section at the
+ * given global section coordinates, using the camera from the last search.
+ * This re-runs the last search, so it should only be used for debugging.
+ * Returns one of the `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_graph_diagnose_section(FFISectionDiagnosis *return_value_ptr,
+                                         HyperCullGraphHandle graph_handle,
+                                         int32_t x,
+                                         int32_t y,
+                                         int32_t z);
+
+/**
+ * Writes whether each of the `count` boxes at `boxes_ptr` is visible to the
+ * matching element of `results_ptr`, using the results of the last search. A
+ * box is visible when it's inside the frustum from the last search and
+ * overlaps a section that the search found visible, and no boxes are visible
+ * before the first search. Returns one of the `HYPERCULL_STATUS_` codes.
+ */
+uint8_t hypercull_graph_test_boxes(bool *results_ptr,
+                                   HyperCullGraphHandle graph_handle,
+                                   const FFIBoundingBox *boxes_ptr,
+                                   uint32_t count);
+
+/**
+ * Writes the UTF-8 encoded message of the last error returned on the calling
+ * thread to `return_value_ptr`. The message stays valid until another error is
+ * returned on the same thread, and is empty if no error has been returned.
+ * Does nothing if `return_value_ptr` is null, because there's no way to report
+ * an error about getting the last error.
+ */
+void hypercull_error_get_message(FFISlice_uint8_t *return_value_ptr);
+
+/**
+ * Starts recording every graph call to a trace file at the given UTF-8 encoded
+ * path, which is `path_len` bytes long, replacing any trace that was already
+ * being recorded. Returns one of the `HYPERCULL_STATUS_` codes, which is
+ * `HYPERCULL_STATUS_IO` if the file could not be created.
+ */
+uint8_t hypercull_trace_start(const uint8_t *path_ptr, size_t path_len);
+
+/**
+ * Stops recording the current trace. Returns one of the `HYPERCULL_STATUS_`
+ * codes, which is `HYPERCULL_STATUS_IO` if the trace could not be fully
+ * written.
+ */
+uint8_t hypercull_trace_stop(void);
+
+#endif /* HYPERCULL_H */
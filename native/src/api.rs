@@ -0,0 +1,493 @@
+//! The safe Rust API. The C API in `ffi` is a thin layer over these types, so
+//! anything it can do is available here without raw pointers.
+
+use std::iter::FusedIterator;
+use std::slice;
+
+use crate::ffi::FFITile;
+use crate::graph;
+use crate::graph::context::GraphSearchContext;
+use crate::graph::coords::SectionGeometry;
+use crate::graph::diagnosis::SectionDiagnosis;
+use crate::graph::error::GraphError;
+use crate::graph::tile::fog::FogShape;
+use crate::simd::prelude::*;
+
+/// A section graph covering the area around the camera that the client keeps
+/// loaded. Sections are added with [`Graph::set_section`], and
+/// [`Graph::search`] finds the ones that are visible from a camera.
+pub struct Graph {
+    inner: graph::Graph,
+}
+
+impl Graph {
+    /// Creates an empty graph for the given render distance in chunks, and the
+    /// range of section Y coordinates in the world, which is inclusive.
+    pub fn new(
+        render_distance: u8,
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+    ) -> Result<Self, GraphError> {
+        Self::with_geometry(
+            render_distance,
+            world_bottom_section_y,
+            world_top_section_y,
+            SectionGeometry::MINECRAFT,
+        )
+    }
+
+    /// Creates an empty graph with sections of the given size, for engines that
+    /// don't use Minecraft's 16-block sections. The render distance and world
+    /// height are still in sections.
+    pub fn with_geometry(
+        render_distance: u8,
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+        geometry: SectionGeometry,
+    ) -> Result<Self, GraphError> {
+        Ok(Self {
+            inner: graph::Graph::try_new_with_geometry(
+                render_distance,
+                world_bottom_section_y,
+                world_top_section_y,
+                geometry,
+            )?,
+        })
+    }
+
+    /// Creates an empty graph for cubic chunks style worlds, which have no
+    /// height limit. The graph extends `vertical_render_distance` sections
+    /// above and below the camera, and wraps around on the Y axis like it does
+    /// on the X and Z axes.
+    pub fn cubic(
+        render_distance: u8,
+        vertical_render_distance: u8,
+        geometry: SectionGeometry,
+    ) -> Result<Self, GraphError> {
+        Ok(Self {
+            inner: graph::Graph::try_new_cubic(
+                render_distance,
+                vertical_render_distance,
+                geometry,
+            )?,
+        })
+    }
+
+    /// Sets which faces of the section at the given global section coordinates
+    /// can see each other. `visibility_data` uses the same layout as Sodium's
+    /// `VisibilityEncoding`, where bit `a * 8 + b` is set when faces `a` and
+    /// `b` are connected, with the faces numbered in the order of
+    /// Minecraft's `Direction` enum.
+    pub fn set_section(
+        &mut self,
+        section_coords: [i32; 3],
+        visibility_data: u64,
+    ) -> Result<(), GraphError> {
+        self.inner
+            .try_set_section(Simd::from_array(section_coords), visibility_data)
+    }
+
+    /// Finds the sections that are visible from the camera. The results borrow
+    /// the graph, and are also available from [`Graph::results`] until the
+    /// next search.
+    pub fn search(
+        &mut self,
+        camera: &Camera,
+        options: &SearchOptions,
+    ) -> Result<SearchResults<'_>, GraphError> {
+        log::debug!("Search - Camera: {camera:?}, Options: {options:?}");
+
+        let context = GraphSearchContext::try_new(
+            &self.inner.coord_space,
+            camera.frustum_planes.map(Simd::from_array),
+            Simd::from_array(camera.pos),
+            options,
+        )?;
+
+        self.inner.cull(&context);
+
+        #[cfg(debug_assertions)]
+        {
+            use std::collections::HashSet;
+
+            let mut coords_set = HashSet::<[i32; 3]>::with_capacity(100);
+            for tile in &self.inner.visible_tiles {
+                if coords_set.contains(&tile.origin_section_coords) {
+                    panic!("Duplicate coords found in visible_tiles");
+                } else {
+                    coords_set.insert(tile.origin_section_coords);
+                }
+            }
+        }
+
+        Ok(self.results())
+    }
+
+    /// Returns the results of the last search, which are empty if there hasn't
+    /// been one.
+    pub fn results(&self) -> SearchResults<'_> {
+        SearchResults {
+            tiles: &self.inner.visible_tiles,
+        }
+    }
+
+    /// Returns whether the box is inside the frustum from the last search, and
+    /// overlaps a section that the search found visible. This is meant for
+    /// culling entities and block entities without repeating the search's
+    /// work. No boxes are visible before the first search.
+    pub fn is_box_visible(&self, bb: &BoundingBox) -> bool {
+        let mut result = [false];
+        self.inner.test_boxes(std::iter::once(*bb), &mut result);
+        result[0]
+    }
+
+    /// Same as [`Graph::is_box_visible`], but writes whether each box is
+    /// visible to the matching element of `results`. The boxes are tested
+    /// against the frustum 8 at a time, so this is much faster than testing
+    /// thousands of boxes one by one.
+    ///
+    /// # Panics
+    /// If `results` isn't the same length as `boxes`.
+    pub fn test_boxes(&self, boxes: &[BoundingBox], results: &mut [bool]) {
+        self.inner.test_boxes(boxes.iter().copied(), results);
+    }
+
+    /// Determines which culling stage decided the visibility of the section at
+    /// the given global section coordinates, using the camera from the last
+    /// search. This re-runs the last search, so it should only be used for
+    /// debugging.
+    pub fn diagnose_section(&mut self, section_coords: [i32; 3]) -> SectionDiagnosis {
+        self.inner
+            .diagnose_section(Simd::from_array(section_coords))
+    }
+
+    /// Returns the size of the graph's sections.
+    pub fn geometry(&self) -> SectionGeometry {
+        self.inner.coord_space.geometry
+    }
+
+    /// Returns the set of target features that searches run with.
+    pub fn simd_level(&self) -> SimdLevel {
+        self.inner.simd_level()
+    }
+
+    /// Overrides the detected SIMD level, which is mostly useful for comparing
+    /// levels in tests and benchmarks. Returns an error and keeps the current
+    /// level if the CPU doesn't support `level`.
+    pub fn set_simd_level(&mut self, level: SimdLevel) -> Result<(), GraphError> {
+        if !level.is_supported() {
+            return Err(GraphError::InvalidArgument {
+                name: "level",
+                reason: "SIMD level is not supported by this CPU",
+            });
+        }
+
+        self.inner.set_simd_level(level);
+        Ok(())
+    }
+
+    pub(crate) fn inner(&self) -> &graph::Graph {
+        &self.inner
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut graph::Graph {
+        &mut self.inner
+    }
+}
+
+/// The camera to search from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// The planes of the view frustum, relative to the camera position. Each
+    /// plane is `[x, y, z, w]`, and a point `p` is inside of it when
+    /// `x * p.x + y * p.y + z * p.z + w >= 0`.
+    pub frustum_planes: [[f32; 4]; 6],
+    /// The global position of the camera in blocks.
+    pub pos: [f64; 3],
+}
+
+/// An axis-aligned box in global block coordinates, such as the bounds of an
+/// entity. Boxes with a minimum corner greater than their maximum corner, or
+/// with NaN coordinates, are never visible.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    /// The corner with the lowest coordinates.
+    pub min: [f64; 3],
+    /// The corner with the highest coordinates.
+    pub max: [f64; 3],
+}
+
+/// Settings that can change between searches. Each culling stage other than
+/// the frustum can be turned off on its own, which is mostly useful for
+/// debugging, or for working around mods that render sections the stages
+/// don't expect to be visible.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchOptions {
+    /// Sections further than this many blocks from the camera are culled, with
+    /// the distance measured according to `fog_shape`.
+    pub search_distance: f32,
+    /// Sections further than this many blocks above or below the camera are
+    /// culled, regardless of the fog shape.
+    pub vertical_search_distance: f32,
+    /// The shape of the area around the camera that sections have to be in.
+    pub fog_shape: FogShape,
+    /// Whether to cull sections that can't be seen through their neighbors.
+    /// Sections are only culled by the frustum, fog and world height when this
+    /// is disabled.
+    pub use_occlusion_culling: bool,
+    /// Whether occlusion culling skips paths that pass through sections at too
+    /// steep of an angle from the camera to see through them.
+    pub use_angle_culling: bool,
+    /// Whether occlusion culling only moves away from the camera within each
+    /// tile, like it does between tiles.
+    pub use_outward_direction_masks: bool,
+    /// Whether to cull sections by their distance from the camera. The search
+    /// distances still limit which tiles are searched when this is disabled,
+    /// so sections up to a tile past them can be visible.
+    pub use_fog_culling: bool,
+    /// Whether to cull sections above the top of the world. When this is
+    /// disabled, only the sections above the world that share a tile with
+    /// sections inside it can be visible.
+    pub use_height_culling: bool,
+    /// Overrides the distance in blocks that each section's bounding box is
+    /// extended by, which is otherwise the one the graph was created with.
+    pub bounds_extension: Option<f32>,
+}
+
+impl SearchOptions {
+    /// Creates options for the given search distance, with cylindrical fog and
+    /// every culling stage enabled. The same distance is used vertically.
+    pub fn new(search_distance: f32) -> Self {
+        Self {
+            search_distance,
+            vertical_search_distance: search_distance,
+            fog_shape: FogShape::Cylindrical,
+            use_occlusion_culling: true,
+            use_angle_culling: true,
+            use_outward_direction_masks: true,
+            use_fog_culling: true,
+            use_height_culling: true,
+            bounds_extension: None,
+        }
+    }
+}
+
+/// The visible sections found by a search, grouped into tiles of 8x8x8
+/// sections.
+#[derive(Clone, Copy)]
+pub struct SearchResults<'a> {
+    tiles: &'a [FFITile],
+}
+
+impl<'a> SearchResults<'a> {
+    /// Returns the tiles with at least one visible section, in the order the
+    /// search reached them.
+    pub fn tiles(&self) -> VisibleTiles<'a> {
+        VisibleTiles {
+            tiles: self.tiles.iter(),
+        }
+    }
+
+    /// Returns the global coordinates of every visible section, grouped by
+    /// tile in the same order as [`SearchResults::tiles`].
+    pub fn sections(&self) -> VisibleSections<'a> {
+        VisibleSections::new(self.tiles)
+    }
+
+    /// Returns the number of tiles with at least one visible section.
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub(crate) fn as_slice(&self) -> &'a [FFITile] {
+        self.tiles
+    }
+}
+
+/// A tile of 8x8x8 sections with at least one visible section.
+#[derive(Clone, Copy)]
+pub struct VisibleTile<'a> {
+    tile: &'a FFITile,
+}
+
+impl<'a> VisibleTile<'a> {
+    /// Returns the global coordinates of the section in the tile's negative
+    /// corner.
+    pub fn origin_section_coords(&self) -> [i32; 3] {
+        self.tile.origin_section_coords
+    }
+
+    /// Returns whether the section at the given coordinates within the tile is
+    /// visible, or `None` if any of the coordinates are 8 or more, which is
+    /// outside of the tile.
+    pub fn is_section_visible(&self, section_coords_in_tile: [u8; 3]) -> Option<bool> {
+        if section_coords_in_tile.iter().any(|&coord| coord >= 8) {
+            return None;
+        }
+
+        let [x, y, z] = section_coords_in_tile;
+        Some((self.tile.visible_sections[y as usize] >> ((z << 3) | x)) & 1 != 0)
+    }
+
+    /// Returns the global coordinates of the tile's visible sections.
+    pub fn sections(&self) -> VisibleSections<'a> {
+        VisibleSections::new(slice::from_ref(self.tile))
+    }
+}
+
+/// An iterator over the tiles in [`SearchResults`].
+#[derive(Clone)]
+pub struct VisibleTiles<'a> {
+    tiles: slice::Iter<'a, FFITile>,
+}
+
+impl<'a> Iterator for VisibleTiles<'a> {
+    type Item = VisibleTile<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tiles.next().map(|tile| VisibleTile { tile })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.tiles.size_hint()
+    }
+}
+
+impl ExactSizeIterator for VisibleTiles<'_> {}
+
+impl FusedIterator for VisibleTiles<'_> {}
+
+/// An iterator over the global coordinates of visible sections.
+#[derive(Clone)]
+pub struct VisibleSections<'a> {
+    tiles: slice::Iter<'a, FFITile>,
+    origin_section_coords: [i32; 3],
+    // The sections of the current tile that haven't been returned yet, with one
+    // Y layer in each element. Each layer is indexed with the pattern ZZZXXX.
+    remaining_sections: [u64; 8],
+    layer: usize,
+}
+
+impl<'a> VisibleSections<'a> {
+    fn new(tiles: &'a [FFITile]) -> Self {
+        Self {
+            tiles: tiles.iter(),
+            origin_section_coords: [0; 3],
+            remaining_sections: [0; 8],
+            layer: 8,
+        }
+    }
+}
+
+impl Iterator for VisibleSections<'_> {
+    type Item = [i32; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.layer < 8 {
+                let sections = &mut self.remaining_sections[self.layer];
+
+                if *sections != 0 {
+                    let index = sections.trailing_zeros() as i32;
+                    // clear the lowest set bit
+                    *sections &= *sections - 1;
+
+                    let [origin_x, origin_y, origin_z] = self.origin_section_coords;
+                    return Some([
+                        origin_x + (index & 0b111),
+                        origin_y + self.layer as i32,
+                        origin_z + (index >> 3),
+                    ]);
+                }
+
+                self.layer += 1;
+            }
+
+            let tile = self.tiles.next()?;
+            self.origin_section_coords = tile.origin_section_coords;
+            self.remaining_sections = tile.visible_sections;
+            self.layer = 0;
+        }
+    }
+}
+
+impl FusedIterator for VisibleSections<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::graph::reference;
+
+    const FRUSTUM_PLANES: [[f32; 4]; 6] = [[0.0, 0.0, 0.0, 1.0]; 6];
+
+    #[test]
+    fn search_results_test() {
+        let mut graph = Graph::new(4, -4, 19).unwrap();
+
+        for x in -4..4 {
+            for y in -4..20 {
+                for z in -4..4 {
+                    let visibility_data = if (x + y + z) % 3 == 0 { 0 } else { !0 };
+                    graph.set_section([x, y, z], visibility_data).unwrap();
+                }
+            }
+        }
+
+        assert_eq!(graph.results().tile_count(), 0);
+        assert_eq!(graph.results().sections().next(), None);
+
+        let camera = Camera {
+            frustum_planes: FRUSTUM_PLANES,
+            pos: [8.5, 72.5, 8.5],
+        };
+        let results = graph.search(&camera, &SearchOptions::new(64.0)).unwrap();
+
+        let sections: Vec<_> = results.sections().collect();
+        let unique_sections: HashSet<_> = sections.iter().copied().collect();
+        assert_eq!(sections.len(), unique_sections.len());
+        assert_eq!(
+            unique_sections,
+            reference::visible_sections(results.as_slice())
+        );
+
+        for tile in results.tiles() {
+            let origin = tile.origin_section_coords();
+
+            for section_coords in tile.sections() {
+                let section_coords_in_tile: [u8; 3] =
+                    std::array::from_fn(|axis| (section_coords[axis] - origin[axis]) as u8);
+                assert_eq!(tile.is_section_visible(section_coords_in_tile), Some(true));
+            }
+            assert_eq!(tile.is_section_visible([0, 8, 0]), None);
+        }
+        assert_eq!(
+            results
+                .tiles()
+                .map(|tile| tile.sections().count())
+                .sum::<usize>(),
+            sections.len()
+        );
+    }
+
+    #[test]
+    fn set_simd_level_test() {
+        let mut graph = Graph::new(4, -4, 19).unwrap();
+        let detected_level = graph.simd_level();
+
+        for level in SimdLevel::ALL {
+            let result = graph.set_simd_level(level);
+            if level.is_supported() {
+                assert_eq!(result, Ok(()));
+                assert_eq!(graph.simd_level(), level);
+            } else {
+                assert!(matches!(result, Err(GraphError::InvalidArgument { .. })));
+                assert_ne!(graph.simd_level(), level);
+            }
+        }
+
+        graph.set_simd_level(detected_level).unwrap();
+        assert_eq!(graph.simd_level(), detected_level);
+    }
+}
@@ -0,0 +1,817 @@
+//! Replays traces recorded in-game, so culling problems can be investigated
+//! without launching Minecraft.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use hypercull_native::internal::trace::{self, Replayer, TraceEvent, TraceReader};
+use hypercull_native::internal::*;
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+Usage:
+  hypercull-cli replay <trace> [--compare] [--slices <dir>] [--ppm]
+      Replays every event in a trace, printing the visible section count and
+      timing of each search, and checking it against the recorded hash.
+
+  hypercull-cli search <trace> --pos <x,y,z> --distance <blocks> [--yaw <degrees>]
+                       [--pitch <degrees>] [--fov <degrees>] [--no-occlusion]
+                       [--fog <cylindrical|spherical|square>]
+                       [--vertical-distance <blocks>] [--graph <id>]
+                       [--compare] [--slices <dir>] [--ppm]
+      Loads the graphs from a trace or snapshot, skipping its searches, then runs a
+      single search with the given camera on the last graph that was created. The
+      vertical distance defaults to --distance.
+
+  hypercull-cli snapshot <trace> <output> [--at-search <index>]
+      Writes a snapshot containing only the events needed to rebuild the graphs as
+      they were right before the given search, followed by that search. Without
+      --at-search, the snapshot contains the final state of the graphs.
+
+  hypercull-cli bench <trace> [--iterations <count>]
+      Replays a trace, running each search multiple times and printing timings.
+
+Options:
+  --compare       Also run the reference culler, and report the sections only one
+                  of the cullers found visible.
+  --slices <dir>  Write the visible sections of each search to <dir>, one Y level
+                  at a time. With --compare, sections only the graph found visible
+                  are marked with G, and sections only the reference found visible
+                  are marked with R.
+  --ppm           Write the slices as PPM images instead of text.
+";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match run(&args) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> CliResult<ExitCode> {
+    let Some((command, args)) = args.split_first() else {
+        eprint!("{USAGE}");
+        return Ok(ExitCode::FAILURE);
+    };
+    let args = Args::parse(args)?;
+
+    match command.as_str() {
+        "replay" => replay(&args),
+        "search" => search(&args),
+        "snapshot" => snapshot(&args),
+        "bench" => bench(&args),
+        "help" | "--help" | "-h" => {
+            print!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => Err(format!("Unknown command: {command}\n\n{USAGE}").into()),
+    }
+}
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    const FLAGS: [&str; 3] = ["--compare", "--ppm", "--no-occlusion"];
+
+    fn parse(args: &[String]) -> CliResult<Self> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                positional.push(arg.clone());
+            } else if Self::FLAGS.contains(&arg.as_str()) {
+                options.insert(arg.clone(), None);
+            } else {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("Missing value for option {arg}"))?;
+                options.insert(arg.clone(), Some(value.clone()));
+            }
+        }
+
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn positional(&self, index: usize, name: &str) -> CliResult<&str> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| format!("Missing argument <{name}>\n\n{USAGE}").into())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn value<T: std::str::FromStr>(&self, name: &str) -> CliResult<Option<T>> {
+        match self.options.get(name) {
+            Some(Some(value)) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid value for option {name}: {value}").into()),
+            _ => Ok(None),
+        }
+    }
+
+    fn output_options(&self) -> CliResult<OutputOptions> {
+        Ok(OutputOptions {
+            compare: self.flag("--compare"),
+            slices_dir: self.value::<PathBuf>("--slices")?,
+            ppm: self.flag("--ppm"),
+        })
+    }
+}
+
+struct OutputOptions {
+    compare: bool,
+    slices_dir: Option<PathBuf>,
+    ppm: bool,
+}
+
+fn replay(args: &Args) -> CliResult<ExitCode> {
+    let options = args.output_options()?;
+    let mut reader = TraceReader::open(Path::new(args.positional(0, "trace")?))?;
+    let mut replayer = Replayer::new();
+
+    if let Some(slices_dir) = &options.slices_dir {
+        fs::create_dir_all(slices_dir)?;
+    }
+
+    let mut total_duration = Duration::ZERO;
+    while let Some(event) = reader.next_event()? {
+        let Some(search) = replayer.apply(event)? else {
+            continue;
+        };
+        total_duration += search.duration;
+
+        let graph = replayer
+            .graph_mut(search.graph_id)
+            .expect("searched graph should exist");
+        let hash_status = if search.actual_hash == search.expected_hash {
+            "ok"
+        } else {
+            "MISMATCH"
+        };
+
+        report_search(
+            graph,
+            &search.context,
+            &format!("search{}", search.search_index),
+            search.duration,
+            hash_status,
+            &options,
+        )?;
+    }
+
+    let report = &replayer.report;
+    println!(
+        "{} events, {} searches, {} mismatches, {:.1} ms searching",
+        report.event_count,
+        report.search_count,
+        report.mismatches.len(),
+        total_duration.as_secs_f64() * 1000.0,
+    );
+
+    Ok(if report.mismatches.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn search(args: &Args) -> CliResult<ExitCode> {
+    let options = args.output_options()?;
+    let pos = parse_pos(
+        &args
+            .value::<String>("--pos")?
+            .ok_or("Missing option --pos")?,
+    )?;
+    let search_distance = args
+        .value::<f32>("--distance")?
+        .ok_or("Missing option --distance")?;
+    let vertical_search_distance = args
+        .value::<f32>("--vertical-distance")?
+        .unwrap_or(search_distance);
+    let yaw = args.value::<f32>("--yaw")?.unwrap_or(0.0);
+    let pitch = args.value::<f32>("--pitch")?.unwrap_or(0.0);
+    let fov = args.value::<f32>("--fov")?.unwrap_or(70.0);
+    let fog_shape = match args.value::<String>("--fog")? {
+        Some(name) => FogShape::ALL
+            .into_iter()
+            .find(|shape| shape.name() == name)
+            .ok_or_else(|| format!("Unknown fog shape: {name}"))?,
+        None => FogShape::Cylindrical,
+    };
+
+    let mut reader = TraceReader::open(Path::new(args.positional(0, "trace")?))?;
+    let mut replayer = Replayer::new();
+    let mut last_created = None;
+
+    while let Some(event) = reader.next_event()? {
+        match event {
+            TraceEvent::Search { .. } => continue,
+            TraceEvent::Create { graph_id, .. } | TraceEvent::CreateCubic { graph_id, .. } => {
+                last_created = Some(graph_id)
+            }
+            _ => {}
+        }
+        replayer.apply(event)?;
+    }
+
+    let graph_id = match args.value::<String>("--graph")? {
+        Some(id) => u64::from_str_radix(id.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("Invalid graph ID: {id}"))?,
+        None => last_created.ok_or("The trace doesn't create any graphs")?,
+    };
+    let graph = replayer
+        .graph_mut(graph_id)
+        .ok_or_else(|| format!("Graph {graph_id:#x} doesn't exist at the end of the trace"))?;
+
+    let context = GraphSearchContext::try_new(
+        &graph.coord_space,
+        frustum_planes(yaw, pitch, fov, search_distance + 32.0),
+        pos,
+        &SearchOptions {
+            fog_shape,
+            vertical_search_distance,
+            use_occlusion_culling: !args.flag("--no-occlusion"),
+            ..SearchOptions::new(search_distance)
+        },
+    )?;
+
+    let start = Instant::now();
+    graph.cull(&context);
+    let duration = start.elapsed();
+
+    if let Some(slices_dir) = &options.slices_dir {
+        fs::create_dir_all(slices_dir)?;
+    }
+    report_search(graph, &context, "search", duration, "-", &options)?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn snapshot(args: &Args) -> CliResult<ExitCode> {
+    let mut reader = TraceReader::open(Path::new(args.positional(0, "trace")?))?;
+    let output_path = args.positional(1, "output")?;
+    let at_search = args.value::<usize>("--at-search")?;
+
+    // the creation event and the latest visibility data of every section, for each
+    // graph that exists at the current point in the trace. Sections that are far
+    // enough apart share a slot in the graph, so each one is stored with the index
+    // of the event that last set it, and they're written out in that order.
+    let mut graphs = HashMap::<u64, (TraceEvent, HashMap<[i32; 3], (usize, u64)>)>::new();
+    let mut creation_order = Vec::new();
+    let mut event_index = 0;
+    let mut search_count = 0;
+    let mut snapshot_search = None;
+
+    while let Some(event) = reader.next_event()? {
+        event_index += 1;
+
+        match &event {
+            TraceEvent::Create { graph_id, .. } | TraceEvent::CreateCubic { graph_id, .. } => {
+                creation_order.retain(|id| id != graph_id);
+                creation_order.push(*graph_id);
+                graphs.insert(*graph_id, (event.clone(), HashMap::new()));
+            }
+            TraceEvent::SetSection {
+                graph_id,
+                section_coords,
+                visibility_data,
+            } => {
+                if let Some((_, sections)) = graphs.get_mut(graph_id) {
+                    sections.insert(*section_coords, (event_index, *visibility_data));
+                }
+            }
+            TraceEvent::Search { .. } => {
+                if at_search == Some(search_count) {
+                    snapshot_search = Some(event);
+                    break;
+                }
+                search_count += 1;
+            }
+            TraceEvent::Delete { graph_id } => {
+                graphs.remove(graph_id);
+                creation_order.retain(|id| id != graph_id);
+            }
+        }
+    }
+
+    if let (Some(index), None) = (at_search, &snapshot_search) {
+        return Err(format!(
+            "The trace only has {search_count} searches, not {}",
+            index + 1
+        )
+        .into());
+    }
+
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    trace::write_header(&mut writer)?;
+
+    let mut event_count = 0;
+    for graph_id in &creation_order {
+        let (create_event, sections) = &graphs[graph_id];
+        create_event.write_to(&mut writer)?;
+
+        let mut sections = sections.iter().collect::<Vec<_>>();
+        sections.sort_unstable_by_key(|(_, &(set_index, _))| set_index);
+
+        for (&section_coords, &(_, visibility_data)) in &sections {
+            TraceEvent::SetSection {
+                graph_id: *graph_id,
+                section_coords,
+                visibility_data,
+            }
+            .write_to(&mut writer)?;
+        }
+
+        event_count += 1 + sections.len();
+    }
+
+    if let Some(search_event) = snapshot_search {
+        search_event.write_to(&mut writer)?;
+        event_count += 1;
+    }
+
+    writer.flush()?;
+    println!(
+        "Wrote {event_count} events for {} graphs to {output_path}",
+        creation_order.len()
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn bench(args: &Args) -> CliResult<ExitCode> {
+    let iterations = args.value::<u32>("--iterations")?.unwrap_or(100).max(1);
+    let mut reader = TraceReader::open(Path::new(args.positional(0, "trace")?))?;
+    let mut replayer = Replayer::new();
+
+    println!("SIMD level: {}", SimdLevel::detect());
+
+    let mut total_duration = Duration::ZERO;
+    let mut total_searches = 0;
+
+    while let Some(event) = reader.next_event()? {
+        let Some(search) = replayer.apply(event)? else {
+            continue;
+        };
+        let graph = replayer
+            .graph_mut(search.graph_id)
+            .expect("searched graph should exist");
+
+        let mut min_duration = search.duration;
+        let mut sum_duration = search.duration;
+        for _ in 1..iterations {
+            let start = Instant::now();
+            graph.cull(&search.context);
+            let duration = start.elapsed();
+
+            min_duration = min_duration.min(duration);
+            sum_duration += duration;
+        }
+
+        println!(
+            "search{}: min {:.1} µs, mean {:.1} µs",
+            search.search_index,
+            min_duration.as_secs_f64() * 1_000_000.0,
+            sum_duration.as_secs_f64() * 1_000_000.0 / iterations as f64,
+        );
+
+        total_duration += sum_duration;
+        total_searches += iterations as u64;
+    }
+
+    if total_searches > 0 {
+        println!(
+            "{total_searches} searches, mean {:.1} µs",
+            total_duration.as_secs_f64() * 1_000_000.0 / total_searches as f64,
+        );
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn report_search(
+    graph: &mut Graph,
+    context: &GraphSearchContext,
+    name: &str,
+    duration: Duration,
+    hash_status: &str,
+    options: &OutputOptions,
+) -> CliResult<()> {
+    let visible = reference::visible_sections(&graph.visible_tiles);
+
+    print!(
+        "{name}: {} sections in {} tiles, {:.1} µs, hash {hash_status}",
+        visible.len(),
+        graph.visible_tiles.len(),
+        duration.as_secs_f64() * 1_000_000.0,
+    );
+
+    let reference_visible = options
+        .compare
+        .then(|| reference::find_visible(graph, context));
+    if let Some(reference_visible) = &reference_visible {
+        print!(
+            ", only graph {}, only reference {}",
+            visible.difference(reference_visible).count(),
+            reference_visible.difference(&visible).count(),
+        );
+    }
+    println!();
+
+    if let Some(slices_dir) = &options.slices_dir {
+        let slices = Slices::new(&visible, reference_visible.as_ref());
+
+        if options.ppm {
+            slices.write_ppm(slices_dir, name)?;
+        } else {
+            slices.write_text(&slices_dir.join(format!("{name}.txt")))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum SectionState {
+    Hidden,
+    Visible,
+    OnlyGraph,
+    OnlyReference,
+}
+
+impl SectionState {
+    fn to_char(self) -> char {
+        match self {
+            SectionState::Hidden => '.',
+            SectionState::Visible => '#',
+            SectionState::OnlyGraph => 'G',
+            SectionState::OnlyReference => 'R',
+        }
+    }
+
+    fn to_rgb(self) -> [u8; 3] {
+        match self {
+            SectionState::Hidden => [24, 24, 24],
+            SectionState::Visible => [230, 230, 230],
+            SectionState::OnlyGraph => [60, 110, 255],
+            SectionState::OnlyReference => [255, 60, 60],
+        }
+    }
+}
+
+/// The visible sections of a search, and optionally the reference search,
+/// within the bounds of everything either of them found visible.
+struct Slices<'a> {
+    visible: &'a HashSet<[i32; 3]>,
+    reference_visible: Option<&'a HashSet<[i32; 3]>>,
+    min: i32x3,
+    max: i32x3,
+}
+
+impl<'a> Slices<'a> {
+    fn new(
+        visible: &'a HashSet<[i32; 3]>,
+        reference_visible: Option<&'a HashSet<[i32; 3]>>,
+    ) -> Self {
+        let mut min = i32x3::splat(i32::MAX);
+        let mut max = i32x3::splat(i32::MIN);
+        for &section in visible
+            .iter()
+            .chain(reference_visible.into_iter().flatten())
+        {
+            min = min.simd_min(i32x3::from_array(section));
+            max = max.simd_max(i32x3::from_array(section));
+        }
+
+        Self {
+            visible,
+            reference_visible,
+            min,
+            max,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.min.simd_gt(self.max).any()
+    }
+
+    fn state(&self, section: i32x3) -> SectionState {
+        let section = section.to_array();
+        let in_graph = self.visible.contains(&section);
+
+        match self
+            .reference_visible
+            .map(|reference| reference.contains(&section))
+        {
+            Some(true) if !in_graph => SectionState::OnlyReference,
+            Some(false) if in_graph => SectionState::OnlyGraph,
+            _ if in_graph => SectionState::Visible,
+            _ => SectionState::Hidden,
+        }
+    }
+
+    fn write_text(&self, path: &Path) -> CliResult<()> {
+        let mut output = String::new();
+
+        if !self.is_empty() {
+            write_layers(&mut output, self.min, self.max, |section| {
+                self.state(section).to_char()
+            })?;
+        }
+
+        fs::write(path, output)?;
+        Ok(())
+    }
+
+    /// Writes one image per Y level, where each section is a square of pixels,
+    /// X increases to the right, and Z increases downwards.
+    fn write_ppm(&self, dir: &Path, name: &str) -> CliResult<()> {
+        const SECTION_PIXELS: usize = 4;
+
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let lengths = (self.max - self.min + Simd::splat(1)).cast::<usize>();
+        let width = lengths[X] * SECTION_PIXELS;
+        let height = lengths[Z] * SECTION_PIXELS;
+
+        for y in self.min[Y]..=self.max[Y] {
+            let mut writer = BufWriter::new(File::create(dir.join(format!("{name}_y{y}.ppm")))?);
+            write!(writer, "P6\n{width} {height}\n255\n")?;
+
+            for pixel_z in 0..height {
+                for pixel_x in 0..width {
+                    let section = i32x3::from_xyz(
+                        self.min[X] + (pixel_x / SECTION_PIXELS) as i32,
+                        y,
+                        self.min[Z] + (pixel_z / SECTION_PIXELS) as i32,
+                    );
+                    writer.write_all(&self.state(section).to_rgb())?;
+                }
+            }
+
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_pos(value: &str) -> CliResult<f64x3> {
+    let components = value
+        .split(',')
+        .map(|component| component.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("Invalid position: {value}"))?;
+
+    match components.as_slice() {
+        &[x, y, z] => Ok(f64x3::from_xyz(x, y, z)),
+        _ => Err(format!("Position must have 3 components: {value}").into()),
+    }
+}
+
+/// Creates the planes of a perspective frustum with a 16:9 aspect ratio. The
+/// angles are in degrees and match Minecraft's.
+fn frustum_planes(
+    yaw_degrees: f32,
+    pitch_degrees: f32,
+    fov_degrees: f32,
+    far_distance: f32,
+) -> [f32x4; 6] {
+    perspective_planes(
+        yaw_degrees.to_radians(),
+        pitch_degrees.to_radians(),
+        fov_degrees.to_radians(),
+        16.0 / 9.0,
+        far_distance,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // slot 3 in its first generation, like a handle from the C API
+    const GRAPH_ID: u64 = (1 << 32) | 3;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn run_args(args: &[&str]) -> ExitCode {
+        run(&to_args(args)).unwrap_or_else(|error| panic!("{args:?} failed: {error}"))
+    }
+
+    fn create_event() -> TraceEvent {
+        let geometry = SectionGeometry::MINECRAFT;
+        TraceEvent::Create {
+            graph_id: GRAPH_ID,
+            render_distance: 4,
+            world_bottom_section_y: -4,
+            world_top_section_y: 19,
+            section_length: geometry.section_length(),
+            bounds_extension: geometry.bounds_extension(),
+        }
+    }
+
+    // Sets the sections in an 8x8 area centered on `center_x`, through the whole
+    // height of the world.
+    fn set_section_events(
+        center_x: i32,
+        visibility_data: impl Fn([i32; 3]) -> u64,
+    ) -> Vec<TraceEvent> {
+        let mut events = Vec::new();
+        for x in center_x - 4..center_x + 4 {
+            for y in -4..20 {
+                for z in -4..4 {
+                    events.push(TraceEvent::SetSection {
+                        graph_id: GRAPH_ID,
+                        section_coords: [x, y, z],
+                        visibility_data: visibility_data([x, y, z]),
+                    });
+                }
+            }
+        }
+        events
+    }
+
+    fn search_event(yaw: f32, camera_pos: [f64; 3]) -> TraceEvent {
+        TraceEvent::Search {
+            graph_id: GRAPH_ID,
+            frustum_planes: frustum_planes(yaw, 20.0, 70.0, 96.0).map(|plane| plane.to_array()),
+            camera_pos,
+            options: FFISearchOptions::new(64.0, true, FogShape::Cylindrical as u8),
+            visible_hash: 0,
+        }
+    }
+
+    // Writes a trace in the same format as the recorder, with the hashes that the
+    // searches produce when they're replayed. If `corrupt_hashes` is set, every
+    // hash is changed so the searches are reported as mismatches.
+    fn write_trace(path: &Path, mut events: Vec<TraceEvent>, corrupt_hashes: bool) {
+        let mut replayer = Replayer::new();
+        for event in &mut events {
+            if let Some(search) = replayer.apply(event.clone()).unwrap() {
+                if let TraceEvent::Search { visible_hash, .. } = event {
+                    *visible_hash = search.actual_hash ^ corrupt_hashes as u64;
+                }
+            }
+        }
+
+        let mut writer = BufWriter::new(File::create(path).unwrap());
+        trace::write_header(&mut writer).unwrap();
+        for event in &events {
+            event.write_to(&mut writer).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    fn checkerboard_events() -> Vec<TraceEvent> {
+        let mut events = vec![create_event()];
+        events.extend(set_section_events(0, |[x, y, z]| {
+            if (x + y + z) % 3 == 0 {
+                0
+            } else {
+                !0
+            }
+        }));
+        events.push(search_event(0.0, [8.5, 72.5, 8.5]));
+        events.push(search_event(135.0, [8.5, 8.0, 8.5]));
+        events
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hypercull-cli-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn commands_test() {
+        let dir = temp_dir("commands");
+
+        let trace_path = dir.join("trace.bin");
+        let corrupt_trace_path = dir.join("corrupt.bin");
+        let snapshot_path = dir.join("snapshot.bin");
+        let slices_dir = dir.join("slices");
+        write_trace(&trace_path, checkerboard_events(), false);
+        write_trace(&corrupt_trace_path, checkerboard_events(), true);
+
+        let trace = trace_path.to_str().unwrap();
+        let snapshot = snapshot_path.to_str().unwrap();
+        let slices = slices_dir.to_str().unwrap();
+
+        assert_eq!(
+            run_args(&["replay", trace, "--compare", "--slices", slices]),
+            ExitCode::SUCCESS
+        );
+        assert!(slices_dir.join("search0.txt").is_file());
+        assert!(slices_dir.join("search1.txt").is_file());
+
+        assert_eq!(
+            run_args(&["replay", corrupt_trace_path.to_str().unwrap()]),
+            ExitCode::FAILURE
+        );
+
+        assert_eq!(
+            run_args(&[
+                "search",
+                trace,
+                "--pos",
+                "8.5,72.5,8.5",
+                "--distance",
+                "64",
+                "--yaw",
+                "45",
+                "--fog",
+                "spherical",
+                "--compare",
+                "--slices",
+                slices,
+                "--ppm",
+            ]),
+            ExitCode::SUCCESS
+        );
+        // out of range distances are reported instead of panicking
+        assert!(run(&to_args(&["search", trace, "--distance", "-1"])).is_err());
+        assert!(run(&to_args(&[
+            "search",
+            trace,
+            "--vertical-distance",
+            "100000"
+        ]))
+        .is_err());
+
+        // the snapshot rebuilds the graph from its final sections, so its search should
+        // still match the recorded hash
+        assert_eq!(
+            run_args(&["snapshot", trace, snapshot, "--at-search", "1"]),
+            ExitCode::SUCCESS
+        );
+        assert_eq!(run_args(&["replay", snapshot]), ExitCode::SUCCESS);
+        // there are only 2 searches
+        assert!(run(&to_args(&["snapshot", trace, snapshot, "--at-search", "2"])).is_err());
+
+        assert_eq!(
+            run_args(&["bench", trace, "--iterations", "2"]),
+            ExitCode::SUCCESS
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn snapshot_wrapped_sections_test() {
+        let dir = temp_dir("wrapped");
+        let trace_path = dir.join("trace.bin");
+        let snapshot_path = dir.join("snapshot.bin");
+
+        // the camera moves a whole graph length towards -X, so the open sections loaded
+        // around it land in the same slots as the closed ones loaded at the start. The
+        // open sections have lower coordinates, so they'd be overwritten if the
+        // snapshot wrote the sections out by coordinate.
+        let graph = Graph::new(4, -4, 19);
+        let xz_length_sections = graph.coord_space.xz_length_tiles as i32 * 8;
+        let moved_x = -xz_length_sections;
+
+        let mut events = vec![create_event()];
+        events.extend(set_section_events(0, |_| 0));
+        events.extend(set_section_events(moved_x, |_| !0));
+        events.push(search_event(0.0, [moved_x as f64 * 16.0 + 8.5, 72.5, 8.5]));
+        write_trace(&trace_path, events, false);
+
+        let trace = trace_path.to_str().unwrap();
+        let snapshot = snapshot_path.to_str().unwrap();
+        assert_eq!(
+            run_args(&["snapshot", trace, snapshot, "--at-search", "0"]),
+            ExitCode::SUCCESS
+        );
+        assert_eq!(run_args(&["replay", snapshot]), ExitCode::SUCCESS);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,19 +1,25 @@
-#![allow(non_snake_case)]
+use std::cell::RefCell;
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, PoisonError};
 
-use std::boxed::Box;
-use std::ffi::c_void;
-
-use context::GraphSearchContext;
-use core_simd::simd::prelude::*;
-use core_simd::simd::ToBytes;
+use context::CombinedTestResults;
+use coords::SectionGeometry;
+use diagnosis::SectionDiagnosis;
+use error::GraphError;
+use tile::fog::FogShape;
 
+use crate::api::{BoundingBox, Camera, Graph, SearchOptions};
+use crate::graph::direction::DIRECTION_COUNT;
 use crate::graph::*;
+use crate::handle::HandleTable;
+use crate::logging::{self, LogHandlerFn};
 use crate::math::*;
 use crate::panic;
 use crate::panic::PanicHandlerFn;
-
-type JNIEnv = c_void;
-type JClass = c_void;
+use crate::simd::level::SimdLevel;
+use crate::simd::prelude::*;
+use crate::trace::{self, TraceEvent};
 
 #[repr(C)]
 pub struct FFISlice<T> {
@@ -36,6 +42,72 @@ pub struct FFICamera {
     pub pos: [f64; 3],
 }
 
+impl From<&FFICamera> for Camera {
+    fn from(value: &FFICamera) -> Self {
+        Self {
+            frustum_planes: value.frustum_planes,
+            pos: value.pos,
+        }
+    }
+}
+
+/// The settings for a single search. `fog_shape` is one of the
+/// `HYPERCULL_FOG_SHAPE_` constants, and a negative `bounds_extension` uses the
+/// extension that the graph was created with.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FFISearchOptions {
+    pub search_distance: f32,
+    pub vertical_search_distance: f32,
+    pub bounds_extension: f32,
+    pub fog_shape: u8,
+    pub use_occlusion_culling: bool,
+    pub use_angle_culling: bool,
+    pub use_outward_direction_masks: bool,
+    pub use_fog_culling: bool,
+    pub use_height_culling: bool,
+}
+
+impl FFISearchOptions {
+    /// The options used by the search functions that only take a search
+    /// distance, with every other stage enabled.
+    pub fn new(search_distance: f32, use_occlusion_culling: bool, fog_shape: u8) -> Self {
+        Self {
+            search_distance,
+            vertical_search_distance: search_distance,
+            bounds_extension: -1.0,
+            fog_shape,
+            use_occlusion_culling,
+            use_angle_culling: true,
+            use_outward_direction_masks: true,
+            use_fog_culling: true,
+            use_height_culling: true,
+        }
+    }
+}
+
+impl TryFrom<&FFISearchOptions> for SearchOptions {
+    type Error = GraphError;
+
+    fn try_from(value: &FFISearchOptions) -> Result<Self, GraphError> {
+        Ok(Self {
+            search_distance: value.search_distance,
+            vertical_search_distance: value.vertical_search_distance,
+            fog_shape: FogShape::from_u8(value.fog_shape).ok_or(GraphError::InvalidFogShape {
+                fog_shape: value.fog_shape,
+            })?,
+            use_occlusion_culling: value.use_occlusion_culling,
+            use_angle_culling: value.use_angle_culling,
+            use_outward_direction_masks: value.use_outward_direction_masks,
+            use_fog_culling: value.use_fog_culling,
+            use_height_culling: value.use_height_culling,
+            // NaN is passed on so it gets rejected, rather than being treated as the default
+            bounds_extension: (value.bounds_extension.is_nan() || value.bounds_extension >= 0.0)
+                .then_some(value.bounds_extension),
+        })
+    }
+}
+
 #[repr(C)]
 pub struct FFITile {
     pub origin_section_coords: [i32; 3],
@@ -46,117 +118,1168 @@ impl FFITile {
     pub fn new(origin_section_coords: i32x3, visible_sections: u8x64) -> Self {
         Self {
             origin_section_coords: origin_section_coords.to_array(),
-            visible_sections: u64x8::from_le_bytes(visible_sections).to_array(),
+            visible_sections: sections_to_ffi(visible_sections),
+        }
+    }
+}
+
+/// The state of a tile after each culling stage, recorded when a search is
+/// captured. Stages that did not run for the tile repeat the previous stage's
+/// sections, and the angle masks are all 1s when occlusion culling did not run.
+#[repr(C)]
+#[derive(Clone)]
+pub struct FFITileCapture {
+    pub origin_section_coords: [i32; 3],
+    pub test_results: u16,
+    pub after_frustum: [u64; 8],
+    pub after_fog: [u64; 8],
+    pub after_height: [u64; 8],
+    pub angle_masks: [[u64; 8]; 3],
+    pub after_traversal: [u64; 8],
+    // The sections reached from each direction once the traversal finished
+    pub incoming_dir_section_sets: [[u64; 8]; DIRECTION_COUNT],
+    // The result of traversing the tile again with all 1s for the angle masks
+    pub without_angle_culling: [u64; 8],
+}
+
+impl FFITileCapture {
+    pub fn new(origin_section_coords: i32x3, test_results: CombinedTestResults) -> Self {
+        Self {
+            origin_section_coords: origin_section_coords.to_array(),
+            test_results: test_results.to_bits(),
+            after_frustum: [0; 8],
+            after_fog: [0; 8],
+            after_height: [0; 8],
+            angle_masks: [[!0; 8]; 3],
+            after_traversal: [0; 8],
+            incoming_dir_section_sets: [[0; 8]; DIRECTION_COUNT],
+            without_angle_culling: [0; 8],
+        }
+    }
+}
+
+/// An axis-aligned box in global block coordinates, for testing whether
+/// entities and block entities are visible.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FFIBoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl From<&FFIBoundingBox> for BoundingBox {
+    fn from(value: &FFIBoundingBox) -> Self {
+        Self {
+            min: value.min,
+            max: value.max,
         }
     }
 }
 
+/// The result of a section diagnosis. `stage` is one of the
+/// `HYPERCULL_DIAGNOSIS_` constants, and the direction sets are only populated
+/// for the stages that use them.
+#[repr(C)]
+pub struct FFISectionDiagnosis {
+    pub stage: u8,
+    pub frustum_plane: u8,
+    pub incoming_dirs: u8,
+    pub visible_neighbors: u8,
+    pub reached_from: u8,
+}
+
+impl From<SectionDiagnosis> for FFISectionDiagnosis {
+    fn from(value: SectionDiagnosis) -> Self {
+        let mut result = Self {
+            stage: 0,
+            frustum_plane: 0,
+            incoming_dirs: 0,
+            visible_neighbors: 0,
+            reached_from: 0,
+        };
+
+        match value {
+            SectionDiagnosis::NoSearch => result.stage = SectionDiagnosis::NO_SEARCH,
+            SectionDiagnosis::OutsideFrustum { plane } => {
+                result.stage = SectionDiagnosis::OUTSIDE_FRUSTUM;
+                result.frustum_plane = plane;
+            }
+            SectionDiagnosis::OutsideFog => result.stage = SectionDiagnosis::OUTSIDE_FOG,
+            SectionDiagnosis::OutsideWorldHeight => {
+                result.stage = SectionDiagnosis::OUTSIDE_WORLD_HEIGHT
+            }
+            SectionDiagnosis::AngleCulled {
+                incoming_dirs,
+                visible_neighbors,
+            } => {
+                result.stage = SectionDiagnosis::ANGLE_CULLED;
+                result.incoming_dirs = incoming_dirs;
+                result.visible_neighbors = visible_neighbors;
+            }
+            SectionDiagnosis::Unreachable {
+                incoming_dirs,
+                visible_neighbors,
+            } => {
+                result.stage = SectionDiagnosis::UNREACHABLE;
+                result.incoming_dirs = incoming_dirs;
+                result.visible_neighbors = visible_neighbors;
+            }
+            SectionDiagnosis::Visible { reached_from } => {
+                result.stage = SectionDiagnosis::VISIBLE;
+                result.reached_from = reached_from;
+            }
+        }
+
+        result
+    }
+}
+
+/// The version of the C API, which is bumped whenever a function signature or
+/// the layout of an FFI struct changes. Callers should check it with
+/// `hypercull_get_abi_version` before calling anything else.
+pub const ABI_VERSION: u32 = 4;
+
+/// The sizes, alignments and field offsets of every FFI struct, in bytes, so
+/// callers that can't include the C header can check that their copy of each
+/// layout matches the library. Every field is a `uint32_t`, so field `n` is at
+/// offset `4 * n`. Fields are only ever added to the end.
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FFILayout {
+    pub tile_size: u32,
+    pub tile_alignment: u32,
+    pub tile_origin_section_coords_offset: u32,
+    pub tile_visible_sections_offset: u32,
+
+    pub slice_size: u32,
+    pub slice_alignment: u32,
+    pub slice_data_ptr_offset: u32,
+    pub slice_count_offset: u32,
+
+    pub camera_size: u32,
+    pub camera_alignment: u32,
+    pub camera_frustum_planes_offset: u32,
+    pub camera_pos_offset: u32,
+
+    pub tile_capture_size: u32,
+    pub tile_capture_alignment: u32,
+    pub tile_capture_origin_section_coords_offset: u32,
+    pub tile_capture_test_results_offset: u32,
+    pub tile_capture_after_frustum_offset: u32,
+    pub tile_capture_after_fog_offset: u32,
+    pub tile_capture_after_height_offset: u32,
+    pub tile_capture_angle_masks_offset: u32,
+    pub tile_capture_after_traversal_offset: u32,
+    pub tile_capture_incoming_dir_section_sets_offset: u32,
+    pub tile_capture_without_angle_culling_offset: u32,
+
+    pub section_diagnosis_size: u32,
+    pub section_diagnosis_alignment: u32,
+    pub section_diagnosis_stage_offset: u32,
+    pub section_diagnosis_frustum_plane_offset: u32,
+    pub section_diagnosis_incoming_dirs_offset: u32,
+    pub section_diagnosis_visible_neighbors_offset: u32,
+    pub section_diagnosis_reached_from_offset: u32,
+
+    pub build_info_size: u32,
+    pub build_info_alignment: u32,
+    pub build_info_version_offset: u32,
+    pub build_info_features_offset: u32,
+    pub build_info_compiled_simd_level_offset: u32,
+    pub build_info_detected_simd_level_offset: u32,
+
+    pub search_options_size: u32,
+    pub search_options_alignment: u32,
+    pub search_options_search_distance_offset: u32,
+    pub search_options_vertical_search_distance_offset: u32,
+    pub search_options_bounds_extension_offset: u32,
+    pub search_options_fog_shape_offset: u32,
+    pub search_options_use_occlusion_culling_offset: u32,
+    pub search_options_use_angle_culling_offset: u32,
+    pub search_options_use_outward_direction_masks_offset: u32,
+    pub search_options_use_fog_culling_offset: u32,
+    pub search_options_use_height_culling_offset: u32,
+    pub bounding_box_size: u32,
+    pub bounding_box_alignment: u32,
+    pub bounding_box_min_offset: u32,
+    pub bounding_box_max_offset: u32,
+}
+
+impl FFILayout {
+    pub fn new() -> Self {
+        macro_rules! size_of {
+            ($T:ty) => {
+                mem::size_of::<$T>() as u32
+            };
+        }
+        macro_rules! align_of {
+            ($T:ty) => {
+                mem::align_of::<$T>() as u32
+            };
+        }
+        macro_rules! offset_of {
+            ($T:ty, $field:ident) => {
+                mem::offset_of!($T, $field) as u32
+            };
+        }
+
+        Self {
+            tile_size: size_of!(FFITile),
+            tile_alignment: align_of!(FFITile),
+            tile_origin_section_coords_offset: offset_of!(FFITile, origin_section_coords),
+            tile_visible_sections_offset: offset_of!(FFITile, visible_sections),
+
+            // every FFISlice has the same layout, regardless of the element type
+            slice_size: size_of!(FFISlice<u8>),
+            slice_alignment: align_of!(FFISlice<u8>),
+            slice_data_ptr_offset: offset_of!(FFISlice<u8>, data_ptr),
+            slice_count_offset: offset_of!(FFISlice<u8>, count),
+
+            camera_size: size_of!(FFICamera),
+            camera_alignment: align_of!(FFICamera),
+            camera_frustum_planes_offset: offset_of!(FFICamera, frustum_planes),
+            camera_pos_offset: offset_of!(FFICamera, pos),
+
+            tile_capture_size: size_of!(FFITileCapture),
+            tile_capture_alignment: align_of!(FFITileCapture),
+            tile_capture_origin_section_coords_offset: offset_of!(
+                FFITileCapture,
+                origin_section_coords
+            ),
+            tile_capture_test_results_offset: offset_of!(FFITileCapture, test_results),
+            tile_capture_after_frustum_offset: offset_of!(FFITileCapture, after_frustum),
+            tile_capture_after_fog_offset: offset_of!(FFITileCapture, after_fog),
+            tile_capture_after_height_offset: offset_of!(FFITileCapture, after_height),
+            tile_capture_angle_masks_offset: offset_of!(FFITileCapture, angle_masks),
+            tile_capture_after_traversal_offset: offset_of!(FFITileCapture, after_traversal),
+            tile_capture_incoming_dir_section_sets_offset: offset_of!(
+                FFITileCapture,
+                incoming_dir_section_sets
+            ),
+            tile_capture_without_angle_culling_offset: offset_of!(
+                FFITileCapture,
+                without_angle_culling
+            ),
+
+            section_diagnosis_size: size_of!(FFISectionDiagnosis),
+            section_diagnosis_alignment: align_of!(FFISectionDiagnosis),
+            section_diagnosis_stage_offset: offset_of!(FFISectionDiagnosis, stage),
+            section_diagnosis_frustum_plane_offset: offset_of!(FFISectionDiagnosis, frustum_plane),
+            section_diagnosis_incoming_dirs_offset: offset_of!(FFISectionDiagnosis, incoming_dirs),
+            section_diagnosis_visible_neighbors_offset: offset_of!(
+                FFISectionDiagnosis,
+                visible_neighbors
+            ),
+            section_diagnosis_reached_from_offset: offset_of!(FFISectionDiagnosis, reached_from),
+
+            build_info_size: size_of!(FFIBuildInfo),
+            build_info_alignment: align_of!(FFIBuildInfo),
+            build_info_version_offset: offset_of!(FFIBuildInfo, version),
+            build_info_features_offset: offset_of!(FFIBuildInfo, features),
+            build_info_compiled_simd_level_offset: offset_of!(FFIBuildInfo, compiled_simd_level),
+            build_info_detected_simd_level_offset: offset_of!(FFIBuildInfo, detected_simd_level),
+
+            search_options_size: size_of!(FFISearchOptions),
+            search_options_alignment: align_of!(FFISearchOptions),
+            search_options_search_distance_offset: offset_of!(FFISearchOptions, search_distance),
+            search_options_vertical_search_distance_offset: offset_of!(
+                FFISearchOptions,
+                vertical_search_distance
+            ),
+            search_options_bounds_extension_offset: offset_of!(FFISearchOptions, bounds_extension),
+            search_options_fog_shape_offset: offset_of!(FFISearchOptions, fog_shape),
+            search_options_use_occlusion_culling_offset: offset_of!(
+                FFISearchOptions,
+                use_occlusion_culling
+            ),
+            search_options_use_angle_culling_offset: offset_of!(
+                FFISearchOptions,
+                use_angle_culling
+            ),
+            search_options_use_outward_direction_masks_offset: offset_of!(
+                FFISearchOptions,
+                use_outward_direction_masks
+            ),
+            search_options_use_fog_culling_offset: offset_of!(FFISearchOptions, use_fog_culling),
+            search_options_use_height_culling_offset: offset_of!(
+                FFISearchOptions,
+                use_height_culling
+            ),
+            bounding_box_size: size_of!(FFIBoundingBox),
+            bounding_box_alignment: align_of!(FFIBoundingBox),
+            bounding_box_min_offset: offset_of!(FFIBoundingBox, min),
+            bounding_box_max_offset: offset_of!(FFIBoundingBox, max),
+        }
+    }
+}
+
+/// How the library was built. `version` is the crate version and `features`
+/// is a comma-separated list of the enabled Cargo features, both UTF-8 encoded
+/// and valid for the lifetime of the library. `compiled_simd_level` is the
+/// level that the library requires, and `detected_simd_level` is the best level
+/// that the CPU supports, both as `HYPERCULL_SIMD_LEVEL_` constants.
+#[repr(C)]
+pub struct FFIBuildInfo {
+    pub version: FFISlice<u8>,
+    pub features: FFISlice<u8>,
+    pub compiled_simd_level: u8,
+    pub detected_simd_level: u8,
+}
+
+// the Cargo features that change the behavior of the library at runtime
+const FEATURES: &[(&str, bool)] = &[
+    ("panic_handler", cfg!(feature = "panic_handler")),
+    ("backtrace", cfg!(feature = "backtrace")),
+    ("scalar_simd", cfg!(feature = "scalar_simd")),
+];
+
+static ENABLED_FEATURES: LazyLock<String> = LazyLock::new(|| {
+    FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+});
+
+pub fn sections_to_ffi(sections: u8x64) -> [u64; 8] {
+    u64x8::from_le_bytes(sections).to_array()
+}
+
+pub fn sections_from_ffi(sections: [u64; 8]) -> u8x64 {
+    u64x8::from_array(sections).to_le_bytes()
+}
+
+/// Identifies a graph created with `hypercull_graph_create`. Handles are
+/// checked on every call, so using one after its graph was deleted returns
+/// `HYPERCULL_STATUS_INVALID_HANDLE` instead of touching freed memory. 0 is
+/// never a valid handle.
+///
+/// Calls on different graphs can run at the same time from different threads,
+/// while calls on the same graph wait for each other.
+pub type GraphHandle = u64;
+
+// every graph created through the C API, which owns them until they are deleted.
+// each graph has its own lock, so the table is only locked while resolving a
+// handle, and a search on one graph doesn't block calls on the others.
+static GRAPHS: Mutex<HandleTable<Arc<Mutex<Graph>>>> = Mutex::new(HandleTable::new());
+
+thread_local! {
+    // the message of the last error returned on this thread, which stays valid until
+    // another error is returned
+    static LAST_ERROR_MESSAGE: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+fn set_last_error(error: &GraphError) {
+    LAST_ERROR_MESSAGE.with_borrow_mut(|message| *message = error.to_string());
+}
+
+/// Reads a UTF-8 encoded path passed across the FFI boundary as a pointer and a
+/// length in bytes.
+///
+/// # Safety
+/// If `path_ptr` isn't null, it must point to `path_len` readable bytes that
+/// stay valid for `'a`.
+unsafe fn path_from_ffi<'a>(path_ptr: *const u8, path_len: usize) -> Result<&'a Path, GraphError> {
+    if path_ptr.is_null() {
+        return Err(GraphError::NullPointer { name: "path_ptr" });
+    }
+    if path_len > isize::MAX as usize {
+        return Err(GraphError::InvalidArgument {
+            name: "path_len",
+            reason: "length is larger than isize::MAX",
+        });
+    }
+
+    let path_bytes = std::slice::from_raw_parts(path_ptr, path_len);
+    let path = std::str::from_utf8(path_bytes).map_err(|_| GraphError::InvalidArgument {
+        name: "path_ptr",
+        reason: "path is not valid UTF-8",
+    })?;
+
+    Ok(Path::new(path))
+}
+
+/// Checks that an out-pointer isn't null. This is done before the call does
+/// anything else, so a null pointer never leaves work half finished.
+fn check_return_value_ptr<T>(return_value_ptr: *mut T) -> Result<(), GraphError> {
+    if return_value_ptr.is_null() {
+        return Err(GraphError::NullPointer {
+            name: "return_value_ptr",
+        });
+    }
+
+    Ok(())
+}
+
+/// Converts the result of an FFI call to a status code, storing the error's
+/// message so it can be retrieved with `hypercull_error_get_message`.
+fn to_status(result: Result<(), GraphError>) -> u8 {
+    match result {
+        Ok(()) => GraphError::OK,
+        Err(error) => {
+            set_last_error(&error);
+            error.status()
+        }
+    }
+}
+
+fn lock_graphs() -> MutexGuard<'static, HandleTable<Arc<Mutex<Graph>>>> {
+    // a panic while the lock is held can't leave the table itself inconsistent
+    GRAPHS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn lock_graph(graph: &Mutex<Graph>) -> MutexGuard<'_, Graph> {
+    // a panic can't unwind out of the C API, so a poisoned graph is only seen by
+    // tests
+    graph.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Serialises the tests that go through the C API. The trace being recorded is
+/// global, so a test that records one would otherwise pick up the calls made
+/// by tests on other threads.
+#[cfg(test)]
+pub(crate) fn lock_ffi_tests() -> MutexGuard<'static, ()> {
+    static FFI_TESTS: Mutex<()> = Mutex::new(());
+
+    // a failing test shouldn't fail every test that runs after it
+    FFI_TESTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs `f` with the graph that the handle refers to, holding the lock on that
+/// graph until it returns. The lock on the graph table is only held while the
+/// handle is resolved.
+fn with_graph<R>(
+    graph_handle: GraphHandle,
+    f: impl FnOnce(&mut Graph) -> Result<R, GraphError>,
+) -> Result<R, GraphError> {
+    let graph = lock_graphs()
+        .get(graph_handle)
+        .cloned()
+        .ok_or(GraphError::InvalidHandle {
+            handle: graph_handle,
+        })?;
+
+    let mut graph = lock_graph(&graph);
+    f(&mut graph)
+}
+
+/// Returns the version of the C API that the library implements, which should
+/// be equal to the `HYPERCULL_ABI_VERSION` of the header that the caller was
+/// built with.
+#[no_mangle]
+pub extern "C" fn hypercull_get_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Writes the layouts of the FFI structs to `return_value_ptr`. Returns one of
+/// the `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_get_layout(return_value_ptr: *mut FFILayout) -> u8 {
+    to_status((|| {
+        check_return_value_ptr(return_value_ptr)?;
+
+        *return_value_ptr = FFILayout::new();
+        Ok(())
+    })())
+}
+
+/// Writes the version, features and SIMD levels of the library to
+/// `return_value_ptr`. Returns one of the `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_get_build_info(return_value_ptr: *mut FFIBuildInfo) -> u8 {
+    to_status((|| {
+        check_return_value_ptr(return_value_ptr)?;
+
+        *return_value_ptr = FFIBuildInfo {
+            version: env!("CARGO_PKG_VERSION").as_bytes().into(),
+            features: ENABLED_FEATURES.as_bytes().into(),
+            compiled_simd_level: SimdLevel::compiled() as u8,
+            detected_simd_level: SimdLevel::detect() as u8,
+        };
+        Ok(())
+    })())
+}
+
 #[no_mangle]
-pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_setPanicHandler(
-    _: *mut JNIEnv,
-    _: *mut JClass,
-    panic_handler_fn_ptr: PanicHandlerFn,
-) {
+pub unsafe extern "C" fn hypercull_set_panic_handler(panic_handler_fn_ptr: PanicHandlerFn) {
     if cfg!(feature = "panic_handler") {
         panic::set_panic_handler(panic_handler_fn_ptr);
     }
 }
 
+/// Forwards log records to the handler, starting at the given level, which is
+/// one of the `HYPERCULL_LOG_LEVEL_` constants.
+#[no_mangle]
+pub extern "C" fn hypercull_set_log_handler(log_handler_fn_ptr: LogHandlerFn, level: u8) {
+    logging::set_log_handler(log_handler_fn_ptr, level);
+}
+
+/// Sets the most verbose level that will be logged, which is one of the
+/// `HYPERCULL_LOG_LEVEL_` constants.
+#[no_mangle]
+pub extern "C" fn hypercull_set_log_level(level: u8) {
+    logging::set_level(level);
+}
+
+/// Writes the handle of the new graph to `return_value_ptr`. Returns one of the
+/// `HYPERCULL_STATUS_` codes, and leaves `return_value_ptr` untouched if
+/// creating the graph failed.
 #[no_mangle]
-pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphCreate(
-    _: *mut JNIEnv,
-    _: *mut JClass,
+pub unsafe extern "C" fn hypercull_graph_create(
+    return_value_ptr: *mut GraphHandle,
     render_distance: u8,
     world_bottom_section_y: i8,
     world_top_section_y: i8,
-) -> *mut Graph {
-    let graph = Box::new(Graph::new(
+) -> u8 {
+    let geometry = SectionGeometry::MINECRAFT;
+    hypercull_graph_create_with_geometry(
+        return_value_ptr,
         render_distance,
         world_bottom_section_y,
         world_top_section_y,
-    ));
+        geometry.section_length(),
+        geometry.bounds_extension(),
+    )
+}
+
+/// Same as `hypercull_graph_create`, but with sections that are
+/// `section_length` blocks long on each side, which must be a power of 2 no
+/// larger than 256. Each section's bounding box is extended by
+/// `bounds_extension` blocks on every side to fit block models that stick out
+/// of it.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_create_with_geometry(
+    return_value_ptr: *mut GraphHandle,
+    render_distance: u8,
+    world_bottom_section_y: i8,
+    world_top_section_y: i8,
+    section_length: u32,
+    bounds_extension: f32,
+) -> u8 {
+    to_status((|| {
+        check_return_value_ptr(return_value_ptr)?;
+
+        let geometry = SectionGeometry::new(section_length, bounds_extension)?;
+        let graph = Graph::with_geometry(
+            render_distance,
+            world_bottom_section_y,
+            world_top_section_y,
+            geometry,
+        )?;
 
-    Box::leak(graph)
+        log::info!("Created graph with SIMD level {}", graph.simd_level());
+
+        let graph_handle = lock_graphs().insert(Arc::new(Mutex::new(graph)));
+
+        trace::record(|| TraceEvent::Create {
+            graph_id: graph_handle,
+            render_distance,
+            world_bottom_section_y,
+            world_top_section_y,
+            section_length,
+            bounds_extension,
+        });
+
+        *return_value_ptr = graph_handle;
+        Ok(())
+    })())
 }
 
+/// Same as `hypercull_graph_create_with_geometry`, but for cubic chunks style
+/// worlds with no height limit. The graph extends `vertical_render_distance`
+/// sections above and below the camera, and wraps around on the Y axis.
 #[no_mangle]
-pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphSetSection(
-    _: *mut JNIEnv,
-    _: *mut JClass,
-    graph_ptr: *mut Graph,
+pub unsafe extern "C" fn hypercull_graph_create_cubic(
+    return_value_ptr: *mut GraphHandle,
+    render_distance: u8,
+    vertical_render_distance: u8,
+    section_length: u32,
+    bounds_extension: f32,
+) -> u8 {
+    to_status((|| {
+        check_return_value_ptr(return_value_ptr)?;
+
+        let geometry = SectionGeometry::new(section_length, bounds_extension)?;
+        let graph = Graph::cubic(render_distance, vertical_render_distance, geometry)?;
+
+        log::info!("Created cubic graph with SIMD level {}", graph.simd_level());
+
+        let graph_handle = lock_graphs().insert(Arc::new(Mutex::new(graph)));
+
+        trace::record(|| TraceEvent::CreateCubic {
+            graph_id: graph_handle,
+            render_distance,
+            vertical_render_distance,
+            section_length,
+            bounds_extension,
+        });
+
+        *return_value_ptr = graph_handle;
+        Ok(())
+    })())
+}
+
+/// Returns one of the `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub extern "C" fn hypercull_graph_set_section(
+    graph_handle: GraphHandle,
     x: i32,
     y: i32,
     z: i32,
     visibility_bitmask: u64,
-) {
-    let graph = graph_ptr
-        .as_mut()
-        .expect("expected pointer to graph to be valid");
+) -> u8 {
+    to_status(with_graph(graph_handle, |graph| {
+        graph.set_section([x, y, z], visibility_bitmask)?;
+
+        trace::record(|| TraceEvent::SetSection {
+            graph_id: graph_handle,
+            section_coords: [x, y, z],
+            visibility_data: visibility_bitmask,
+        });
+
+        Ok(())
+    }))
+}
 
-    graph.set_section(i32x3::from_xyz(x, y, z), visibility_bitmask);
+/// Writes the visible tiles to `return_value_ptr`, which stay valid until the
+/// next search or until the graph is deleted. Returns one of the
+/// `HYPERCULL_STATUS_` codes, and leaves `return_value_ptr` untouched if the
+/// search failed.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_search(
+    return_value_ptr: *mut FFISlice<FFITile>,
+    graph_handle: GraphHandle,
+    camera_ptr: *const FFICamera,
+    search_distance: f32,
+    use_occlusion_culling: bool,
+) -> u8 {
+    hypercull_graph_search_with_fog_shape(
+        return_value_ptr,
+        graph_handle,
+        camera_ptr,
+        search_distance,
+        use_occlusion_culling,
+        FogShape::Cylindrical as u8,
+    )
 }
 
+/// Same as `hypercull_graph_search`, but with the shape of the fog given by one
+/// of the `HYPERCULL_FOG_SHAPE_` constants instead of always being cylindrical.
 #[no_mangle]
-pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphSearch(
-    _: *mut JNIEnv,
-    _: *mut JClass,
+pub unsafe extern "C" fn hypercull_graph_search_with_fog_shape(
     return_value_ptr: *mut FFISlice<FFITile>,
-    graph_ptr: *mut Graph,
+    graph_handle: GraphHandle,
     camera_ptr: *const FFICamera,
     search_distance: f32,
     use_occlusion_culling: bool,
-) {
-    #[cfg(debug_assertions)]
-    println!("start search --------------------------");
+    fog_shape: u8,
+) -> u8 {
+    hypercull_graph_search_with_options(
+        return_value_ptr,
+        graph_handle,
+        camera_ptr,
+        &FFISearchOptions::new(search_distance, use_occlusion_culling, fog_shape),
+    )
+}
 
-    let graph = graph_ptr
-        .as_mut()
-        .expect("expected pointer to graph to be valid");
+/// Same as `hypercull_graph_search`, but with every setting of the search
+/// given by `options_ptr`, including which culling stages are enabled.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_search_with_options(
+    return_value_ptr: *mut FFISlice<FFITile>,
+    graph_handle: GraphHandle,
+    camera_ptr: *const FFICamera,
+    options_ptr: *const FFISearchOptions,
+) -> u8 {
+    to_status(with_graph(graph_handle, |graph| {
+        check_return_value_ptr(return_value_ptr)?;
 
-    let camera = camera_ptr
-        .as_ref()
-        .expect("expected pointer to camera to be valid");
+        let camera = camera_ptr
+            .as_ref()
+            .ok_or(GraphError::NullPointer { name: "camera_ptr" })?;
+        let options = options_ptr.as_ref().ok_or(GraphError::NullPointer {
+            name: "options_ptr",
+        })?;
 
-    let simd_camera_pos = Simd::from_array(camera.pos);
-    let simd_frustum_planes = camera.frustum_planes.map(Simd::from_array);
+        let results = graph.search(&camera.into(), &options.try_into()?)?;
 
-    let context = GraphSearchContext::new(
-        &graph.coord_space,
-        simd_frustum_planes,
-        simd_camera_pos,
-        search_distance,
-        use_occlusion_culling,
-    );
+        trace::record(|| TraceEvent::Search {
+            graph_id: graph_handle,
+            frustum_planes: camera.frustum_planes,
+            camera_pos: camera.pos,
+            options: *options,
+            visible_hash: trace::hash_visible_tiles(results.as_slice()),
+        });
 
-    graph.cull(&context);
+        *return_value_ptr = results.as_slice().into();
+        Ok(())
+    }))
+}
 
-    #[cfg(debug_assertions)]
-    {
-        use std::collections::HashSet;
+/// Deletes the graph, after which its handle is invalid. Returns one of the
+/// `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub extern "C" fn hypercull_graph_delete(graph_handle: GraphHandle) -> u8 {
+    to_status((|| {
+        let graph = lock_graphs()
+            .remove(graph_handle)
+            .ok_or(GraphError::InvalidHandle {
+                handle: graph_handle,
+            })?;
 
-        let mut coords_set = HashSet::<[i32; 3]>::with_capacity(100);
-        for tile in &graph.visible_tiles {
-            if coords_set.contains(&tile.origin_section_coords) {
-                panic!("Duplicate coords found in visible_tiles");
-            } else {
-                coords_set.insert(tile.origin_section_coords);
-            }
+        // wait for any call that resolved the handle before it was removed, so the
+        // delete is recorded after that call. the graph is freed once the last
+        // of those calls returns.
+        let _graph = lock_graph(&graph);
+        trace::record(|| TraceEvent::Delete {
+            graph_id: graph_handle,
+        });
+
+        Ok(())
+    })())
+}
+
+/// Writes the SIMD level that was picked for the graph's CPU to
+/// `return_value_ptr`, as one of the `HYPERCULL_SIMD_LEVEL_` constants. Returns
+/// one of the `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_get_simd_level(
+    return_value_ptr: *mut u8,
+    graph_handle: GraphHandle,
+) -> u8 {
+    to_status(with_graph(graph_handle, |graph| {
+        check_return_value_ptr(return_value_ptr)?;
+
+        *return_value_ptr = graph.simd_level() as u8;
+        Ok(())
+    }))
+}
+
+/// Returns one of the `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub extern "C" fn hypercull_graph_capture_next_search(graph_handle: GraphHandle) -> u8 {
+    to_status(with_graph(graph_handle, |graph| {
+        graph.inner_mut().capture_next_search();
+        Ok(())
+    }))
+}
+
+/// Writes the tiles of the last captured search to `return_value_ptr`. The
+/// slice stays valid until the next captured search or until the graph is
+/// deleted. Returns one of the `HYPERCULL_STATUS_` codes, which is
+/// `HYPERCULL_STATUS_NO_CAPTURE` if no search has been captured.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_get_capture(
+    return_value_ptr: *mut FFISlice<FFITileCapture>,
+    graph_handle: GraphHandle,
+) -> u8 {
+    to_status(with_graph(graph_handle, |graph| {
+        check_return_value_ptr(return_value_ptr)?;
+
+        let capture = graph
+            .inner()
+            .last_capture
+            .as_ref()
+            .ok_or(GraphError::NoCapture)?;
+        *return_value_ptr = capture.tiles.as_slice().into();
+        Ok(())
+    }))
+}
+
+/// Writes the last captured search to a file at the given UTF-8 encoded path,
+/// which is `path_len` bytes long. Returns one of the `HYPERCULL_STATUS_`
+/// codes, which is `HYPERCULL_STATUS_NO_CAPTURE` if no search has been
+/// captured.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_write_capture(
+    graph_handle: GraphHandle,
+    path_ptr: *const u8,
+    path_len: usize,
+) -> u8 {
+    to_status((|| {
+        let path = path_from_ffi(path_ptr, path_len)?;
+
+        with_graph(graph_handle, |graph| {
+            let capture = graph
+                .inner()
+                .last_capture
+                .as_ref()
+                .ok_or(GraphError::NoCapture)?;
+
+            capture.write_to_file(path).map_err(|error| GraphError::Io {
+                message: error.to_string(),
+            })
+        })
+    })())
+}
+
+/// Determines which culling stage decided the visibility of the section at the
+/// given global section coordinates, using the camera from the last search.
+/// This re-runs the last search, so it should only be used for debugging.
+/// Returns one of the `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_diagnose_section(
+    return_value_ptr: *mut FFISectionDiagnosis,
+    graph_handle: GraphHandle,
+    x: i32,
+    y: i32,
+    z: i32,
+) -> u8 {
+    to_status(with_graph(graph_handle, |graph| {
+        check_return_value_ptr(return_value_ptr)?;
+
+        *return_value_ptr = graph.diagnose_section([x, y, z]).into();
+        Ok(())
+    }))
+}
+
+/// Writes whether each of the `count` boxes at `boxes_ptr` is visible to the
+/// matching element of `results_ptr`, using the results of the last search. A
+/// box is visible when it's inside the frustum from the last search and
+/// overlaps a section that the search found visible, and no boxes are visible
+/// before the first search. Returns one of the `HYPERCULL_STATUS_` codes.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_graph_test_boxes(
+    results_ptr: *mut bool,
+    graph_handle: GraphHandle,
+    boxes_ptr: *const FFIBoundingBox,
+    count: u32,
+) -> u8 {
+    to_status(with_graph(graph_handle, |graph| {
+        if results_ptr.is_null() {
+            return Err(GraphError::NullPointer {
+                name: "results_ptr",
+            });
         }
+        if boxes_ptr.is_null() {
+            return Err(GraphError::NullPointer { name: "boxes_ptr" });
+        }
+
+        let boxes = std::slice::from_raw_parts(boxes_ptr, count as usize);
+        let results = std::slice::from_raw_parts_mut(results_ptr, count as usize);
+        graph
+            .inner()
+            .test_boxes(boxes.iter().map(BoundingBox::from), results);
+        Ok(())
+    }))
+}
+
+/// Writes the UTF-8 encoded message of the last error returned on the calling
+/// thread to `return_value_ptr`. The message stays valid until another error is
+/// returned on the same thread, and is empty if no error has been returned.
+/// Does nothing if `return_value_ptr` is null, because there's no way to report
+/// an error about getting the last error.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_error_get_message(return_value_ptr: *mut FFISlice<u8>) {
+    if return_value_ptr.is_null() {
+        return;
     }
 
-    *return_value_ptr = graph.visible_tiles.as_slice().into();
+    // the String's buffer lives in the thread local, so it stays valid after the
+    // borrow ends
+    *return_value_ptr = LAST_ERROR_MESSAGE.with_borrow(|message| message.as_bytes().into());
+}
+
+/// Starts recording every graph call to a trace file at the given UTF-8 encoded
+/// path, which is `path_len` bytes long, replacing any trace that was already
+/// being recorded. Returns one of the `HYPERCULL_STATUS_` codes, which is
+/// `HYPERCULL_STATUS_IO` if the file could not be created.
+#[no_mangle]
+pub unsafe extern "C" fn hypercull_trace_start(path_ptr: *const u8, path_len: usize) -> u8 {
+    to_status((|| {
+        let path = path_from_ffi(path_ptr, path_len)?;
+
+        trace::start_recording(path).map_err(|error| GraphError::Io {
+            message: error.to_string(),
+        })
+    })())
 }
 
+/// Stops recording the current trace. Returns one of the `HYPERCULL_STATUS_`
+/// codes, which is `HYPERCULL_STATUS_IO` if the trace could not be fully
+/// written.
 #[no_mangle]
-pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphDelete(
-    _: *mut JNIEnv,
-    _: *mut JClass,
-    graph_ptr: *mut Graph,
-) {
-    let graph = graph_ptr
-        .as_mut()
-        .expect("expected pointer to graph to be valid");
+pub extern "C" fn hypercull_trace_stop() -> u8 {
+    to_status(trace::stop_recording().map_err(|error| GraphError::Io {
+        message: error.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simd::level::SimdLevel;
+
+    const HEADER: &str = include_str!("../include/hypercull.h");
+
+    #[test]
+    fn c_header_declares_functions_test() {
+        let exported_fns = include_str!("ffi.rs")
+            .lines()
+            .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+            .filter_map(|signature| signature.split('(').next())
+            .filter(|name| name.starts_with("hypercull_"));
+
+        for name in exported_fns {
+            assert!(
+                HEADER.contains(&format!(" {name}(")),
+                "{name} is missing from the header"
+            );
+        }
+    }
+
+    #[test]
+    fn c_header_constants_test() {
+        let constants = [
+            ("HYPERCULL_DIRECTION_COUNT", DIRECTION_COUNT as u8),
+            ("HYPERCULL_STATUS_OK", GraphError::OK),
+            ("HYPERCULL_STATUS_NULL_POINTER", GraphError::NULL_POINTER),
+            (
+                "HYPERCULL_STATUS_INVALID_WORLD_HEIGHT",
+                GraphError::INVALID_WORLD_HEIGHT,
+            ),
+            (
+                "HYPERCULL_STATUS_GRAPH_TOO_LARGE",
+                GraphError::GRAPH_TOO_LARGE,
+            ),
+            (
+                "HYPERCULL_STATUS_SECTION_OUT_OF_BOUNDS",
+                GraphError::SECTION_OUT_OF_BOUNDS,
+            ),
+            (
+                "HYPERCULL_STATUS_INVALID_SEARCH_DISTANCE",
+                GraphError::INVALID_SEARCH_DISTANCE,
+            ),
+            (
+                "HYPERCULL_STATUS_INVALID_CAMERA_POSITION",
+                GraphError::INVALID_CAMERA_POSITION,
+            ),
+            (
+                "HYPERCULL_STATUS_INVALID_HANDLE",
+                GraphError::INVALID_HANDLE,
+            ),
+            (
+                "HYPERCULL_STATUS_INVALID_SECTION_GEOMETRY",
+                GraphError::INVALID_SECTION_GEOMETRY,
+            ),
+            (
+                "HYPERCULL_STATUS_INVALID_FOG_SHAPE",
+                GraphError::INVALID_FOG_SHAPE,
+            ),
+            (
+                "HYPERCULL_STATUS_INVALID_ARGUMENT",
+                GraphError::INVALID_ARGUMENT,
+            ),
+            ("HYPERCULL_STATUS_NO_CAPTURE", GraphError::NO_CAPTURE),
+            ("HYPERCULL_STATUS_IO", GraphError::IO),
+            ("HYPERCULL_LOG_LEVEL_OFF", logging::LEVEL_OFF),
+            ("HYPERCULL_LOG_LEVEL_ERROR", logging::LEVEL_ERROR),
+            ("HYPERCULL_LOG_LEVEL_WARN", logging::LEVEL_WARN),
+            ("HYPERCULL_LOG_LEVEL_INFO", logging::LEVEL_INFO),
+            ("HYPERCULL_LOG_LEVEL_DEBUG", logging::LEVEL_DEBUG),
+            ("HYPERCULL_LOG_LEVEL_TRACE", logging::LEVEL_TRACE),
+            ("HYPERCULL_SIMD_LEVEL_BASELINE", SimdLevel::Baseline as u8),
+            ("HYPERCULL_SIMD_LEVEL_SSE41", SimdLevel::Sse41 as u8),
+            ("HYPERCULL_SIMD_LEVEL_AVX2", SimdLevel::Avx2 as u8),
+            ("HYPERCULL_SIMD_LEVEL_AVX512", SimdLevel::Avx512 as u8),
+            ("HYPERCULL_DIAGNOSIS_NO_SEARCH", SectionDiagnosis::NO_SEARCH),
+            (
+                "HYPERCULL_DIAGNOSIS_OUTSIDE_FRUSTUM",
+                SectionDiagnosis::OUTSIDE_FRUSTUM,
+            ),
+            (
+                "HYPERCULL_DIAGNOSIS_OUTSIDE_FOG",
+                SectionDiagnosis::OUTSIDE_FOG,
+            ),
+            (
+                "HYPERCULL_DIAGNOSIS_OUTSIDE_WORLD_HEIGHT",
+                SectionDiagnosis::OUTSIDE_WORLD_HEIGHT,
+            ),
+            (
+                "HYPERCULL_DIAGNOSIS_ANGLE_CULLED",
+                SectionDiagnosis::ANGLE_CULLED,
+            ),
+            (
+                "HYPERCULL_DIAGNOSIS_UNREACHABLE",
+                SectionDiagnosis::UNREACHABLE,
+            ),
+            ("HYPERCULL_DIAGNOSIS_VISIBLE", SectionDiagnosis::VISIBLE),
+            (
+                "HYPERCULL_FOG_SHAPE_CYLINDRICAL",
+                FogShape::Cylindrical as u8,
+            ),
+            ("HYPERCULL_FOG_SHAPE_SPHERICAL", FogShape::Spherical as u8),
+            ("HYPERCULL_FOG_SHAPE_SQUARE", FogShape::Square as u8),
+        ];
+
+        for (name, value) in constants {
+            assert!(
+                HEADER.contains(&format!("#define {name} {value}\n")),
+                "{name} is missing from the header or isn't {value}"
+            );
+        }
 
-    let graph_box = Box::from_raw(graph);
-    drop(graph_box);
+        assert!(HEADER.contains(&format!("#define HYPERCULL_ABI_VERSION {ABI_VERSION}\n")));
+    }
+
+    #[test]
+    fn invalid_handle_test() {
+        let _lock = lock_ffi_tests();
+        let mut graph_handle = 0;
+        let mut simd_level = u8::MAX;
+        unsafe {
+            assert_eq!(
+                hypercull_graph_create(&mut graph_handle, 2, -4, 19),
+                GraphError::OK
+            );
+            assert_eq!(
+                hypercull_graph_get_simd_level(&mut simd_level, graph_handle),
+                GraphError::OK
+            );
+        }
+        assert_ne!(simd_level, u8::MAX);
+
+        assert_eq!(hypercull_graph_delete(graph_handle), GraphError::OK);
+
+        // use after delete
+        let mut visible_tiles = FFISlice {
+            data_ptr: std::ptr::null(),
+            count: 0,
+        };
+        let camera = FFICamera {
+            frustum_planes: [[0.0, 0.0, 0.0, 1.0]; 6],
+            pos: [8.5, 64.5, 8.5],
+        };
+        unsafe {
+            assert_eq!(
+                hypercull_graph_search(&mut visible_tiles, graph_handle, &camera, 32.0, true),
+                GraphError::INVALID_HANDLE
+            );
+            assert!(visible_tiles.data_ptr.is_null());
+            assert_eq!(
+                hypercull_graph_get_simd_level(&mut simd_level, graph_handle),
+                GraphError::INVALID_HANDLE
+            );
+        }
+        assert_eq!(
+            hypercull_graph_set_section(graph_handle, 0, 0, 0, !0),
+            GraphError::INVALID_HANDLE
+        );
+        assert_eq!(
+            LAST_ERROR_MESSAGE.with_borrow(String::clone),
+            format!("Graph handle is invalid or was deleted - Handle: {graph_handle:#x}")
+        );
+
+        // double delete
+        assert_eq!(
+            hypercull_graph_delete(graph_handle),
+            GraphError::INVALID_HANDLE
+        );
+    }
+
+    #[test]
+    fn separate_graph_locks_test() {
+        let _lock = lock_ffi_tests();
+        let mut graph_handles = [0; 2];
+        unsafe {
+            for graph_handle in &mut graph_handles {
+                assert_eq!(
+                    hypercull_graph_create(graph_handle, 2, -4, 19),
+                    GraphError::OK
+                );
+            }
+        }
+
+        // this would deadlock if the graph table stayed locked while a graph was in use
+        let status = with_graph(graph_handles[0], |_| {
+            Ok(hypercull_graph_set_section(graph_handles[1], 0, 4, 0, !0))
+        });
+        assert_eq!(status, Ok(GraphError::OK));
+
+        for graph_handle in graph_handles {
+            assert_eq!(hypercull_graph_delete(graph_handle), GraphError::OK);
+        }
+    }
+
+    #[test]
+    fn layout_test() {
+        let _lock = lock_ffi_tests();
+        let layout = FFILayout::new();
+
+        // the sizes that HyperCullNativeLib.java was originally written against
+        assert_eq!(layout.tile_size, 80);
+        assert_eq!(layout.camera_size, 120);
+        assert_eq!(layout.tile_capture_size, 912);
+        assert_eq!(layout.section_diagnosis_size, 5);
+        assert_eq!(layout.search_options_size, 20);
+        assert_eq!(layout.bounding_box_size, 48);
+
+        let mut ffi_layout = mem::MaybeUninit::<FFILayout>::uninit();
+        unsafe {
+            assert_eq!(
+                hypercull_get_layout(ffi_layout.as_mut_ptr()),
+                GraphError::OK
+            );
+            assert_eq!(ffi_layout.assume_init(), layout);
+        }
+    }
+
+    #[test]
+    fn c_header_layout_fields_test() {
+        fn struct_body<'a>(source: &'a str, start: &str, end: &str) -> &'a str {
+            let body_start = source.find(start).unwrap() + start.len();
+            let body_len = source[body_start..].find(end).unwrap();
+            &source[body_start..body_start + body_len]
+        }
+
+        let rust_fields: Vec<_> =
+            struct_body(include_str!("ffi.rs"), "pub struct FFILayout {", "}")
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("pub "))
+                .filter_map(|line| line.strip_suffix(": u32,"))
+                .collect();
+        let c_fields: Vec<_> = struct_body(HEADER, "typedef struct FFILayout {", "}")
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("uint32_t "))
+            .filter_map(|line| line.strip_suffix(';'))
+            .collect();
+
+        assert_eq!(mem::size_of::<FFILayout>(), rust_fields.len() * 4);
+        assert_eq!(rust_fields, c_fields);
+    }
+
+    #[test]
+    fn build_info_test() {
+        let _lock = lock_ffi_tests();
+        let mut build_info = mem::MaybeUninit::<FFIBuildInfo>::uninit();
+        let build_info = unsafe {
+            assert_eq!(
+                hypercull_get_build_info(build_info.as_mut_ptr()),
+                GraphError::OK
+            );
+            build_info.assume_init()
+        };
+
+        let version = unsafe {
+            std::slice::from_raw_parts(build_info.version.data_ptr, build_info.version.count)
+        };
+        assert_eq!(version, env!("CARGO_PKG_VERSION").as_bytes());
+
+        let features = unsafe {
+            std::slice::from_raw_parts(build_info.features.data_ptr, build_info.features.count)
+        };
+        let features = std::str::from_utf8(features).unwrap();
+        assert_eq!(
+            features
+                .split(',')
+                .any(|feature| feature == "panic_handler"),
+            cfg!(feature = "panic_handler")
+        );
+
+        let compiled_simd_level = SimdLevel::from_u8(build_info.compiled_simd_level).unwrap();
+        let detected_simd_level = SimdLevel::from_u8(build_info.detected_simd_level).unwrap();
+        assert!(compiled_simd_level.is_supported());
+        assert!(compiled_simd_level <= detected_simd_level);
+    }
 }
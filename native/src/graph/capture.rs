@@ -0,0 +1,137 @@
+use std::path::Path;
+use std::{fmt, fs, io};
+
+use super::context::GraphSearchContext;
+use super::direction::*;
+use super::tile;
+use super::tile::fog::FogShape;
+use crate::ffi::{self, FFITileCapture};
+use crate::math::*;
+
+/// Holds the per-tile culling results of a single search, so that the reason a
+/// section was culled can be inspected in-game or offline.
+pub struct SearchCapture {
+    pub camera_pos: f64x3,
+    pub search_distance: f32,
+    pub fog_shape: FogShape,
+    pub bounds_extension: f32,
+    pub use_occlusion_culling: bool,
+    pub use_angle_culling: bool,
+    pub use_fog_culling: bool,
+    pub use_height_culling: bool,
+
+    pub tiles: Vec<FFITileCapture>,
+}
+
+impl SearchCapture {
+    pub fn new(context: &GraphSearchContext) -> Self {
+        Self {
+            camera_pos: context.global_camera_pos(),
+            search_distance: context.fog_distance,
+            fog_shape: context.fog_shape,
+            bounds_extension: context.geometry.bounds_extension(),
+            use_occlusion_culling: context.use_occlusion_culling,
+            use_angle_culling: context.use_angle_culling,
+            use_fog_culling: context.use_fog_culling,
+            use_height_culling: context.use_height_culling,
+            tiles: Vec::with_capacity(128),
+        }
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for SearchCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Camera Pos: {:?}, Search Distance: {}, Fog Shape: {}, Bounds Extension: {}, Occlusion Culling: {}, Angle Culling: {}, Fog Culling: {}, Height Culling: {}, Tiles: {}",
+            self.camera_pos.to_array(),
+            self.search_distance,
+            self.fog_shape,
+            self.bounds_extension,
+            self.use_occlusion_culling,
+            self.use_angle_culling,
+            self.use_fog_culling,
+            self.use_height_culling,
+            self.tiles.len(),
+        )?;
+
+        for tile_capture in &self.tiles {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "Tile - Origin Section Coords: {:?}, Test Results: {:#018b}",
+                tile_capture.origin_section_coords, tile_capture.test_results,
+            )?;
+
+            let incoming_sections =
+                |direction| tile_capture.incoming_dir_section_sets[to_index(direction)];
+            let stages = [
+                ("After Frustum", tile_capture.after_frustum),
+                ("After Fog", tile_capture.after_fog),
+                ("After Height", tile_capture.after_height),
+                ("Angle Mask X", tile_capture.angle_masks[X]),
+                ("Angle Mask Y", tile_capture.angle_masks[Y]),
+                ("Angle Mask Z", tile_capture.angle_masks[Z]),
+                ("After Traversal", tile_capture.after_traversal),
+                ("Incoming -X", incoming_sections(NEG_X)),
+                ("Incoming -Y", incoming_sections(NEG_Y)),
+                ("Incoming -Z", incoming_sections(NEG_Z)),
+                ("Incoming +X", incoming_sections(POS_X)),
+                ("Incoming +Y", incoming_sections(POS_Y)),
+                ("Incoming +Z", incoming_sections(POS_Z)),
+                ("Without Angle Culling", tile_capture.without_angle_culling),
+            ];
+
+            for (name, sections) in stages {
+                writeln!(f, "{name}:")?;
+
+                let mut tile_output = String::new();
+                tile::write_tile(&mut tile_output, &ffi::sections_from_ffi(sections))?;
+                f.write_str(&tile_output)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::context::CombinedTestResults;
+    use crate::simd::prelude::*;
+
+    #[test]
+    fn capture_format_test() {
+        let mut tile_capture =
+            FFITileCapture::new(i32x3::from_xyz(8, -4, 16), CombinedTestResults::ALL_INSIDE);
+        tile_capture.after_traversal = ffi::sections_to_ffi(tile::SECTIONS_FILLED);
+
+        let capture = SearchCapture {
+            camera_pos: Simd::splat(0.5),
+            search_distance: 64.0,
+            fog_shape: FogShape::Spherical,
+            bounds_extension: 1.0,
+            use_occlusion_culling: true,
+            use_angle_culling: false,
+            use_fog_culling: true,
+            use_height_culling: true,
+            tiles: vec![tile_capture],
+        };
+
+        let output = capture.to_string();
+
+        assert!(output.contains("Fog Shape: spherical"));
+        assert!(output.contains("Angle Culling: false"));
+        assert!(output.contains("Origin Section Coords: [8, -4, 16]"));
+        // 14 stages per tile, 8 layers per stage
+        assert_eq!(output.matches("↓Y").count(), 14 * 8);
+        assert!(output.contains("Incoming +Z:"));
+        assert!(output.contains("Without Angle Culling:"));
+        assert!(output.contains("11111111 Z7"));
+    }
+}
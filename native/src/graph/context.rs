@@ -1,17 +1,24 @@
-use core_simd::simd::prelude::*;
-use std_float::StdFloat;
-
-use super::coords::RelativeBoundingBox;
+use super::coords::{RelativeBoundingBox, SectionGeometry};
+use super::tile::fog::FogShape;
 use super::tile::frustum::Frustum;
+use crate::api::SearchOptions;
 use crate::graph::*;
 
 // TODO: move camera into its own struct
+#[derive(Clone)]
 pub struct GraphSearchContext {
     pub frustum: Frustum,
 
+    // the graph's geometry, with the bounds extension from the search options
+    pub geometry: SectionGeometry,
+
     pub global_section_offset: i32x3,
 
+    pub use_fog_culling: bool,
+    pub fog_shape: FogShape,
     pub fog_distance: f32,
+    // limited by the vertical render distance in cubic chunks mode
+    pub vertical_fog_distance: f32,
 
     // the camera coords (in blocks) relative to the local origin, which is the (0, 0, 0) point of
     // the graph. the representation here is slightly different than the representation in
@@ -26,7 +33,10 @@ pub struct GraphSearchContext {
     pub direction_step_counts: Simd<u8, DIRECTION_COUNT>,
 
     pub use_occlusion_culling: bool,
+    pub use_angle_culling: bool,
+    pub use_height_culling: bool,
 
+    // all 1s when outward direction masks are disabled
     pub outward_direction_masks: [u8x64; DIRECTION_COUNT],
 }
 
@@ -35,49 +45,104 @@ impl GraphSearchContext {
         coord_space: &GraphCoordSpace,
         frustum_planes: [f32x4; 6],
         global_camera_pos: f64x3,
-        search_distance: f32,
-        use_occlusion_culling: bool,
+        options: &SearchOptions,
     ) -> Self {
-        assert!(
-            search_distance >= 0.0,
-            "Search distance must not be negative - Search Distance: {search_distance}"
-        );
+        Self::try_new(coord_space, frustum_planes, global_camera_pos, options)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// The search distance in `options` limits how far away sections can be on
+    /// the X and Z axes, and the vertical search distance limits how far away
+    /// they can be on the Y axis. The vertical search distance can be as large
+    /// as the graph's height or width, whichever is larger.
+    pub fn try_new(
+        coord_space: &GraphCoordSpace,
+        frustum_planes: [f32x4; 6],
+        global_camera_pos: f64x3,
+        options: &SearchOptions,
+    ) -> Result<Self, GraphError> {
+        let search_distance = options.search_distance;
+        let vertical_search_distance = options.vertical_search_distance;
+
+        let geometry = match options.bounds_extension {
+            Some(bounds_extension) => {
+                SectionGeometry::new(coord_space.geometry.section_length(), bounds_extension)?
+            }
+            None => coord_space.geometry,
+        };
+        let section_shift = geometry.section_shift() as i32;
+        let tile_shift = geometry.tile_shift() as i32;
 
         let max_search_distance =
-            coord_space.xz_length_tiles as f32 * LocalTileCoords::LENGTH_IN_BLOCKS as f32;
-        assert!(
-            search_distance <= max_search_distance,
-            "Search distance exceeds maximum for graph - Search Distance: {search_distance}, Maximum: {max_search_distance}"
-        );
+            coord_space.xz_length_tiles as f32 * geometry.tile_length() as f32;
+        // written this way so NaN is rejected
+        if !(search_distance >= 0.0 && search_distance <= max_search_distance) {
+            return Err(GraphError::InvalidSearchDistance {
+                search_distance,
+                max_search_distance,
+            });
+        }
 
-        let frustum = Frustum::new(frustum_planes);
+        // the camera can be above or below the world, so the vertical search distance
+        // isn't limited to the height of the graph
+        let max_vertical_search_distance =
+            coord_space.y_length_tiles.max(coord_space.xz_length_tiles) as f32
+                * geometry.tile_length() as f32;
+        if !(vertical_search_distance >= 0.0
+            && vertical_search_distance <= max_vertical_search_distance)
+        {
+            return Err(GraphError::InvalidSearchDistance {
+                search_distance: vertical_search_distance,
+                max_search_distance: max_vertical_search_distance,
+            });
+        }
+
+        // in cubic chunks mode, the Y axis wraps instead, so we can't search further
+        // than the vertical render distance without processing tiles twice
+        let vertical_fog_distance = match coord_space.vertical_render_distance {
+            Some(vertical_render_distance) => vertical_search_distance
+                .min(vertical_render_distance as f32 * geometry.section_length_f32()),
+            None => vertical_search_distance,
+        };
 
+        let frustum = Frustum::new(frustum_planes, geometry);
+
+        // This rejects non-finite positions, along with any particularly stupid camera
+        // positions that can't be converted to integer block coordinates.
         let global_camera_pos_floor = global_camera_pos.floor();
+        if !(global_camera_pos_floor.simd_ge(Simd::splat(i32::MIN as f64))
+            & global_camera_pos_floor.simd_le(Simd::splat(i32::MAX as f64)))
+        .all()
+        {
+            return Err(GraphError::InvalidCameraPosition {
+                camera_pos: global_camera_pos.to_array(),
+            });
+        }
+
         // see the comment in CameraTransform.java for why we reduce the precision
         const PRECISION_MODIFIER: f32x3 = Simd::splat(128.0);
         let camera_pos_frac = ((global_camera_pos - global_camera_pos_floor).cast::<f32>()
             + PRECISION_MODIFIER)
             - PRECISION_MODIFIER;
 
-        // Safety: We check if the conversion was lossless directly after the operation.
-        // This should catch any particularly stupid camera positions.
+        // Safety: The position was checked to be finite and within the range of an i32
+        // above.
         let global_camera_pos_int = unsafe { global_camera_pos_floor.to_int_unchecked::<i32>() };
-        assert_eq!(
-            global_camera_pos_int.cast::<f64>(),
-            global_camera_pos_floor,
-            "Camera position out of bounds: {global_camera_pos:?}",
-        );
 
         let local_camera_pos_int = coord_space.block_to_local_coords(global_camera_pos_int);
-        let global_section_offset = (global_camera_pos_int - local_camera_pos_int) >> 4;
+        let global_section_offset = (global_camera_pos_int - local_camera_pos_int) >> section_shift;
         let local_camera_pos = local_camera_pos_int.cast::<f64>() + camera_pos_frac.cast::<f64>();
 
-        let mut iter_start_tile_coords = (local_camera_pos_int >> 7).cast::<i8>();
+        let mut iter_start_tile_coords = (local_camera_pos_int >> tile_shift).cast::<i8>();
 
-        let global_top_block_y = ((coord_space.world_top_section_y as i32 + 1) << 4) - 1;
-        let global_bottom_block_y = (coord_space.world_bottom_section_y as i32) << 4;
+        let global_top_block_y =
+            ((coord_space.world_top_section_y as i32 + 1) << section_shift) - 1;
+        let global_bottom_block_y = (coord_space.world_bottom_section_y as i32) << section_shift;
 
-        let camera_area = if global_camera_pos_int[Y] > global_top_block_y {
+        // worlds in cubic chunks mode have no top or bottom
+        let camera_area = if coord_space.wraps_y() {
+            CameraArea::Inside
+        } else if global_camera_pos_int[Y] > global_top_block_y {
             iter_start_tile_coords[Y] = coord_space.y_length_tiles as i8;
             CameraArea::Above
         } else if global_camera_pos_int[Y] < global_bottom_block_y {
@@ -87,25 +152,28 @@ impl GraphSearchContext {
             CameraArea::Inside
         };
 
-        let local_top_block_y = (global_top_block_y - global_bottom_block_y) as u16;
+        let local_top_block_y = global_top_block_y - global_bottom_block_y;
+        let clamp_y = !coord_space.wraps_y();
+        let step_distance =
+            f32x3::from_xyz(search_distance, vertical_fog_distance, search_distance).cast::<f64>();
 
         let positive_step_counts = {
-            let mut iter_end_block = (local_camera_pos + Simd::splat(search_distance as f64))
-                .floor()
-                .cast::<u16>();
-            iter_end_block[Y] = iter_end_block[Y].clamp(0, local_top_block_y);
-            let iter_end_tile = iter_end_block >> 7;
-            (iter_end_tile.cast::<i16>() - iter_start_tile_coords.cast::<i16>())
+            let mut iter_end_block = (local_camera_pos + step_distance).floor().cast::<i32>();
+            if clamp_y {
+                iter_end_block[Y] = iter_end_block[Y].clamp(0, local_top_block_y);
+            }
+            let iter_end_tile = iter_end_block >> tile_shift;
+            (iter_end_tile - iter_start_tile_coords.cast::<i32>())
                 .max(Simd::splat(0))
                 .cast::<u8>()
         };
         let negative_step_counts = {
-            let mut iter_end_block = (local_camera_pos - Simd::splat(search_distance as f64))
-                .floor()
-                .cast::<i16>();
-            iter_end_block[Y] = iter_end_block[Y].clamp(0, local_top_block_y as i16);
-            let iter_end_tile = iter_end_block >> 7;
-            (iter_start_tile_coords.cast::<i16>() - iter_end_tile)
+            let mut iter_end_block = (local_camera_pos - step_distance).floor().cast::<i32>();
+            if clamp_y {
+                iter_end_block[Y] = iter_end_block[Y].clamp(0, local_top_block_y);
+            }
+            let iter_end_tile = iter_end_block >> tile_shift;
+            (iter_start_tile_coords.cast::<i32>() - iter_end_tile)
                 .max(Simd::splat(0))
                 .cast::<u8>()
         };
@@ -116,26 +184,37 @@ impl GraphSearchContext {
             [0, 1, 2, 3, 4, 5,],
         );
 
-        let camera_section_in_tile = (local_camera_pos_int >> 4).cast::<u8>() & Simd::splat(0b111);
+        let camera_section_in_tile =
+            (local_camera_pos_int >> section_shift).cast::<u8>() & Simd::splat(0b111);
 
-        Self {
+        let outward_direction_masks = if options.use_outward_direction_masks {
+            tile::traversal::gen_outward_direction_masks(camera_section_in_tile)
+        } else {
+            [tile::SECTIONS_FILLED; DIRECTION_COUNT]
+        };
+
+        Ok(Self {
             frustum,
+            geometry,
             global_section_offset,
+            use_fog_culling: options.use_fog_culling,
+            fog_shape: options.fog_shape,
             fog_distance: search_distance,
+            vertical_fog_distance,
             camera_pos_int: local_camera_pos_int,
             camera_pos_frac,
             camera_area,
             camera_section_in_tile,
             iter_start_tile_coords: LocalTileCoords(iter_start_tile_coords),
             direction_step_counts,
-            use_occlusion_culling,
-            outward_direction_masks: tile::traversal::gen_outward_direction_masks(
-                camera_section_in_tile,
-            ),
-        }
+            use_occlusion_culling: options.use_occlusion_culling,
+            use_angle_culling: options.use_angle_culling,
+            use_height_culling: options.use_height_culling,
+            outward_direction_masks,
+        })
     }
 
-    pub fn test_tile(
+    pub fn test_tile<L: Level>(
         &self,
         coord_space: &GraphCoordSpace,
         coords: LocalTileCoords,
@@ -146,20 +225,29 @@ impl GraphSearchContext {
 
         let bb = RelativeBoundingBox::new_extended(
             relative_pos,
-            relative_pos + Simd::splat(LocalTileCoords::LENGTH_IN_BLOCKS as f32),
+            relative_pos + Simd::splat(self.geometry.tile_length() as f32),
+            self.geometry.padded_bounds_extension(),
         );
 
-        self.frustum.test_box(bb, &mut results);
+        self.frustum.test_box::<L>(bb, &mut results);
 
         if results == CombinedTestResults::OUTSIDE {
             // early exit
             return results;
         }
-        tile::fog::test_box(bb, self.fog_distance, &mut results);
-
-        if results == CombinedTestResults::OUTSIDE {
-            // early exit
-            return results;
+        if self.use_fog_culling {
+            tile::fog::test_box(
+                bb,
+                self.fog_shape,
+                self.fog_distance,
+                self.vertical_fog_distance,
+                &mut results,
+            );
+
+            if results == CombinedTestResults::OUTSIDE {
+                // early exit
+                return results;
+            }
         }
 
         if do_height_checks {
@@ -169,14 +257,56 @@ impl GraphSearchContext {
         results
     }
 
+    /// Returns the global coordinates of the section at the origin of the tile.
+    pub fn tile_origin_section_coords(&self, coords: LocalTileCoords) -> i32x3 {
+        let local_section_coords = coords.0.cast::<i32>() << 3;
+        self.global_section_offset + local_section_coords
+    }
+
+    pub fn global_camera_pos(&self) -> f64x3 {
+        let global_camera_pos_int = (self.global_section_offset
+            << self.geometry.section_shift() as i32)
+            + self.camera_pos_int;
+        global_camera_pos_int.cast::<f64>() + self.camera_pos_frac.cast::<f64>()
+    }
+
+    /// Returns the global coordinates of the section that the camera is in.
+    pub fn camera_section_coords(&self) -> i32x3 {
+        self.global_section_offset + (self.camera_pos_int >> self.geometry.section_shift() as i32)
+    }
+
     pub fn relative_tile_pos(&self, coords: LocalTileCoords) -> f32x3 {
-        let pos_int = coords.to_local_block_coords() - self.camera_pos_int;
+        let pos_int = coords.to_local_block_coords(self.geometry) - self.camera_pos_int;
         pos_int.cast::<f32>() - self.camera_pos_frac
     }
 
+    /// Returns the position of the section's negative corner relative to the
+    /// camera. This is only precise enough for diagnostics and tests.
+    pub fn relative_section_pos(&self, section_coords: i32x3) -> f32x3 {
+        let section_pos = (section_coords << self.geometry.section_shift() as i32).cast::<f64>();
+        (section_pos - self.global_camera_pos()).cast::<f32>()
+    }
+
+    /// Returns the bounding box of the section with the same extension that the
+    /// culling stages use.
+    pub fn section_bounding_box(&self, section_coords: i32x3) -> RelativeBoundingBox {
+        let relative_pos = self.relative_section_pos(section_coords);
+        RelativeBoundingBox::new_extended(
+            relative_pos,
+            relative_pos + Simd::splat(self.geometry.section_length_f32()),
+            self.geometry.padded_bounds_extension(),
+        )
+    }
+
     #[inline(never)]
-    pub fn voxelize_fog_cylinder(&self, relative_tile_pos: f32x3, visible_sections: &mut u8x64) {
-        *visible_sections &= tile::fog::voxelize_cylinder(relative_tile_pos, self.fog_distance);
+    pub fn voxelize_fog<L: Level>(&self, relative_tile_pos: f32x3, visible_sections: &mut u8x64) {
+        *visible_sections &= tile::fog::voxelize::<L>(
+            self.fog_shape,
+            relative_tile_pos,
+            self.fog_distance,
+            self.vertical_fog_distance,
+            self.geometry,
+        );
     }
 }
 
@@ -184,7 +314,7 @@ impl GraphSearchContext {
 // Each test is represented by a single bit in this bit set. For each test:
 // 1-bit = Partially inside, partially outside
 // 0-bit = Inside
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub struct CombinedTestResults(u16);
 
 impl CombinedTestResults {
@@ -210,6 +340,10 @@ impl CombinedTestResults {
     pub fn get_intersecting_planes(self) -> u8 {
         (self.0 & Self::FRUSTUM_PLANE_BITS) as u8
     }
+
+    pub fn to_bits(self) -> u16 {
+        self.0
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -1,9 +1,104 @@
 use std::ops::Index;
 
-use core_simd::simd::prelude::*;
-
+use super::error::GraphError;
 use super::{direction, i32x3, i8x3, u8x3, Coords3};
 use crate::math::*;
+use crate::simd::prelude::*;
+
+/// The size of a section in blocks, and how far the bounding box of each
+/// section is extended to fit block models that stick out of it. Tiles are
+/// always 8 sections long on each axis, so this also sets the size of a tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SectionGeometry {
+    section_length_shift: u8,
+    bounds_extension: f32,
+}
+
+impl SectionGeometry {
+    /// Minecraft's 16-block sections, extended by 1 block to account for large
+    /// block models.
+    pub const MINECRAFT: Self = Self {
+        section_length_shift: 4,
+        bounds_extension: 1.0,
+    };
+
+    // Sections longer than this would make the local block coordinates of the
+    // largest graphs overflow an i32 when they're shifted.
+    const MAX_SECTION_LENGTH_SHIFT: u8 = 8;
+
+    /// `section_length` is the length of each side of a section in blocks,
+    /// which must be a power of 2 no larger than 256. `bounds_extension` is the
+    /// distance in blocks that each section's bounding box is extended by on
+    /// every side, which must be between 0 and `section_length`.
+    pub fn new(section_length: u32, bounds_extension: f32) -> Result<Self, GraphError> {
+        let section_length_shift = section_length.trailing_zeros();
+
+        // written this way so NaN is rejected
+        if !section_length.is_power_of_two()
+            || section_length_shift > Self::MAX_SECTION_LENGTH_SHIFT as u32
+            || !(bounds_extension >= 0.0 && bounds_extension <= section_length as f32)
+        {
+            return Err(GraphError::InvalidSectionGeometry {
+                section_length,
+                bounds_extension,
+            });
+        }
+
+        Ok(Self {
+            section_length_shift: section_length_shift as u8,
+            bounds_extension,
+        })
+    }
+
+    /// The length of each side of a section in blocks.
+    pub fn section_length(self) -> u32 {
+        1 << self.section_length_shift
+    }
+
+    /// The distance in blocks that each section's bounding box is extended by,
+    /// not including the padding for float imprecision.
+    pub fn bounds_extension(self) -> f32 {
+        self.bounds_extension
+    }
+
+    /// The amount to shift block coordinates right by to get section
+    /// coordinates.
+    pub(crate) fn section_shift(self) -> u8 {
+        self.section_length_shift
+    }
+
+    /// The amount to shift block coordinates right by to get tile coordinates.
+    pub(crate) fn tile_shift(self) -> u8 {
+        self.section_length_shift + 3
+    }
+
+    pub(crate) fn section_length_f32(self) -> f32 {
+        self.section_length() as f32
+    }
+
+    pub(crate) fn tile_length(self) -> u32 {
+        self.section_length() * LocalTileCoords::LENGTH_IN_SECTIONS as u32
+    }
+
+    // The bounds extension used by the culling stages, with 1/128th of a section
+    // added to account for float imprecision. For 16-block sections, this is
+    // 0.125 blocks.
+    pub(crate) fn padded_bounds_extension(self) -> f32 {
+        self.bounds_extension + (self.section_length_f32() / 128.0)
+    }
+
+    // The largest area that we allow float imprecision to add, which is twice the
+    // padding.
+    pub(crate) fn max_bounds_extension(self) -> f32 {
+        self.bounds_extension + (self.section_length_f32() / 64.0)
+    }
+}
+
+impl Default for SectionGeometry {
+    fn default() -> Self {
+        Self::MINECRAFT
+    }
+}
 
 pub struct GraphCoordSpace {
     // WARNING: if this is 128, there will be conversion problems when out of bounds above the
@@ -11,8 +106,16 @@ pub struct GraphCoordSpace {
     pub y_length_tiles: u8,
     pub xz_length_tiles: u8,
 
+    // both 0 in cubic chunks mode, where the world has no height limit
     pub world_bottom_section_y: i8,
     pub world_top_section_y: i8,
+
+    /// The number of sections that the graph extends above and below the
+    /// camera in cubic chunks mode, where the Y axis wraps around like the X
+    /// and Z axes. `None` for worlds with a fixed height.
+    pub vertical_render_distance: Option<u8>,
+
+    pub geometry: SectionGeometry,
 }
 
 impl GraphCoordSpace {
@@ -24,18 +127,29 @@ impl GraphCoordSpace {
         xz_length_tiles: u8,
         world_bottom_section_y: i8,
         world_top_section_y: i8,
+        vertical_render_distance: Option<u8>,
+        geometry: SectionGeometry,
     ) -> Self {
         Self {
             y_length_tiles,
             xz_length_tiles,
             world_bottom_section_y,
             world_top_section_y,
+            vertical_render_distance,
+            geometry,
         }
     }
 
+    /// Whether the Y axis wraps around, which is only the case in cubic chunks
+    /// mode.
+    pub fn wraps_y(&self) -> bool {
+        self.vertical_render_distance.is_some()
+    }
+
     // Index is packed in YZX ordering
     pub fn pack_index(&self, coords: LocalTileCoords) -> LocalTileIndex {
-        // We don't want to wrap the coordinates on the Y axis, so we do a bounds check.
+        // Unless we're in cubic chunks mode, we don't want to wrap the coordinates on
+        // the Y axis, so we do a bounds check.
         #[cfg(debug_assertions)]
         assert!(
             self.tile_coords_in_bounds(coords),
@@ -54,17 +168,32 @@ impl GraphCoordSpace {
                 .checked_rem_euclid(self.xz_length_tiles as i16)
                 .unwrap_unchecked() as u16
         };
+        let y = if self.wraps_y() {
+            unsafe {
+                (coords[Y] as i16)
+                    .checked_rem_euclid(self.y_length_tiles as i16)
+                    .unwrap_unchecked() as u16
+            }
+        } else {
+            coords[Y] as u16
+        };
 
         LocalTileIndex(
-            (((coords[Y] as u16 * self.xz_length_tiles as u16) + z_wrapped)
-                * self.xz_length_tiles as u16)
+            (((y * self.xz_length_tiles as u16) + z_wrapped) * self.xz_length_tiles as u16)
                 + x_wrapped,
         )
     }
 
+    /// The Y coordinate of the highest tile that contains sections inside of
+    /// the world. This can be lower than the top of the graph, because the
+    /// graph is always atleast 2 tiles tall.
+    pub fn world_top_tile_y(&self) -> i8 {
+        ((self.world_top_section_y as i16 - self.world_bottom_section_y as i16) >> 3) as i8
+    }
+
     pub fn tile_coords_in_bounds(&self, coords: LocalTileCoords) -> bool {
         let y = coords[Y] as i16;
-        (y >= 0) & (y < self.y_length_tiles as i16)
+        self.wraps_y() | ((y >= 0) & (y < self.y_length_tiles as i16))
     }
 
     /// Calculates the tile coordinates in the graph and the section coordinates
@@ -79,9 +208,13 @@ impl GraphCoordSpace {
         let wrapped_xz = scaled_coords
             .rem_euclid(Simd::splat(self.xz_length_tiles as i32))
             .cast::<i8>();
-        // exclude Y axis from wrapping
+        // exclude Y axis from wrapping, unless we're in cubic chunks mode
         let mut tile_coords = wrapped_xz;
-        tile_coords[Y] = scaled_coords[Y] as i8;
+        tile_coords[Y] = if self.wraps_y() {
+            scaled_coords[Y].rem_euclid(self.y_length_tiles as i32) as i8
+        } else {
+            scaled_coords[Y] as i8
+        };
 
         let section_coords_in_tile = shifted_coords.cast::<u8>() & Simd::splat(0b111);
 
@@ -91,11 +224,17 @@ impl GraphCoordSpace {
     /// Converts global block coordinates to local block coordinates
     pub fn block_to_local_coords(&self, block_coords: i32x3) -> i32x3 {
         let wrapped_xz = block_coords.rem_euclid(Simd::splat(
-            self.xz_length_tiles as i32 * LocalTileCoords::LENGTH_IN_BLOCKS as i32,
+            self.xz_length_tiles as i32 * self.geometry.tile_length() as i32,
         ));
 
-        let world_bottom_block_y = (self.world_bottom_section_y as i32) << 4;
-        let shifted_y = block_coords[Y] - world_bottom_block_y;
+        let world_bottom_block_y =
+            (self.world_bottom_section_y as i32) << self.geometry.section_shift();
+        let shifted_y = if self.wraps_y() {
+            block_coords[Y]
+                .rem_euclid(self.y_length_tiles as i32 * self.geometry.tile_length() as i32)
+        } else {
+            block_coords[Y] - world_bottom_block_y
+        };
 
         let mut combined = wrapped_xz;
         combined[Y] = shifted_y;
@@ -108,7 +247,6 @@ impl GraphCoordSpace {
 pub struct LocalTileCoords(pub i8x3);
 
 impl LocalTileCoords {
-    pub const LENGTH_IN_BLOCKS: u8 = 128;
     pub const LENGTH_IN_SECTIONS: u8 = 8;
 
     // TODO: debug assert that Y didn't wrap when doing this
@@ -142,8 +280,8 @@ impl LocalTileCoords {
         Self(self.0 + offset_vec)
     }
 
-    pub fn to_local_block_coords(self) -> i32x3 {
-        self.0.cast::<i32>() << 7
+    pub fn to_local_block_coords(self, geometry: SectionGeometry) -> i32x3 {
+        self.0.cast::<i32>() << geometry.tile_shift() as i32
     }
 }
 
@@ -178,17 +316,10 @@ pub struct RelativeBoundingBox {
 }
 
 impl RelativeBoundingBox {
-    // add 1 block to account for large block models
-    pub const BOUNDING_BOX_EXTENSION_MIN: f32 = 1.0;
-    // add 0.125 blocks to account for float imprecision
-    pub const BOUNDING_BOX_EXTENSION: f32 = Self::BOUNDING_BOX_EXTENSION_MIN + 0.125;
-    // the maximum area that we allow float imprecision to add is 0.25 blocks
-    pub const BOUNDING_BOX_EXTENSION_MAX: f32 = Self::BOUNDING_BOX_EXTENSION_MIN + 0.25;
-
-    pub fn new_extended(min: f32x3, max: f32x3) -> Self {
+    pub fn new_extended(min: f32x3, max: f32x3, extension: f32) -> Self {
         Self {
-            max: max + f32x3::splat(Self::BOUNDING_BOX_EXTENSION),
-            min: min - f32x3::splat(Self::BOUNDING_BOX_EXTENSION),
+            max: max + f32x3::splat(extension),
+            min: min - f32x3::splat(extension),
         }
     }
 
@@ -215,7 +346,14 @@ mod tests {
 
         let graph_total_tiles = y_length_tiles as u32 * (xz_length_tiles as u32).pow(2);
 
-        let coord_space = GraphCoordSpace::new(y_length_tiles as u8, xz_length_tiles as u8, -4, 19);
+        let coord_space = GraphCoordSpace::new(
+            y_length_tiles as u8,
+            xz_length_tiles as u8,
+            -4,
+            19,
+            None,
+            SectionGeometry::MINECRAFT,
+        );
         let mut index_coords_map = HashMap::<LocalTileIndex, LocalTileCoords>::new();
 
         for y in 0..y_length_tiles {
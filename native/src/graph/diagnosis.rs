@@ -0,0 +1,360 @@
+use std::mem;
+
+use super::capture::SearchCapture;
+use super::context::GraphSearchContext;
+use super::coords::GraphCoordSpace;
+use super::direction::*;
+use super::{tile, Graph};
+use crate::ffi::{self, FFITileCapture};
+use crate::math::*;
+
+/// The culling stage that decided whether a section is visible.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SectionDiagnosis {
+    /// No search has been run on the graph yet.
+    NoSearch,
+    /// The section is outside of the frustum. `plane` is the direction of the
+    /// plane the section is furthest outside of.
+    OutsideFrustum { plane: u8 },
+    /// The section is outside of the fog, or beyond the search distance.
+    OutsideFog,
+    /// The section is above or below the world.
+    OutsideWorldHeight,
+    /// The section could only have been reached through paths rejected by angle
+    /// culling. `incoming_dirs` are the directions the traversal may enter the
+    /// section from, and `visible_neighbors` are the subset of those directions
+    /// where the neighboring section is visible.
+    AngleCulled {
+        incoming_dirs: u8,
+        visible_neighbors: u8,
+    },
+    /// The traversal never reached the section.
+    Unreachable {
+        incoming_dirs: u8,
+        visible_neighbors: u8,
+    },
+    /// The section is visible. `reached_from` contains the directions the
+    /// traversal entered the section from, which is empty for the section the
+    /// camera is in, or when occlusion culling is disabled.
+    Visible { reached_from: u8 },
+}
+
+impl SectionDiagnosis {
+    pub const NO_SEARCH: u8 = 0;
+    pub const OUTSIDE_FRUSTUM: u8 = 1;
+    pub const OUTSIDE_FOG: u8 = 2;
+    pub const OUTSIDE_WORLD_HEIGHT: u8 = 3;
+    pub const ANGLE_CULLED: u8 = 4;
+    pub const UNREACHABLE: u8 = 5;
+    pub const VISIBLE: u8 = 6;
+}
+
+impl Graph {
+    /// Determines which culling stage decided the visibility of the section at
+    /// the given global section coordinates, using the camera from the last
+    /// search.
+    ///
+    /// This re-runs the last search with capturing enabled. The results of
+    /// the last search and the last capture are left untouched.
+    pub fn diagnose_section(&mut self, section_coords: i32x3) -> SectionDiagnosis {
+        let Some(context) = self.last_search_context.clone() else {
+            return SectionDiagnosis::NoSearch;
+        };
+
+        let (tile_coords, _) = self.coord_space.section_to_tile_coords(section_coords);
+        if !self.coord_space.tile_coords_in_bounds(tile_coords) {
+            return SectionDiagnosis::OutsideWorldHeight;
+        }
+        // the padding tile above short worlds is in bounds, but the height checks
+        // reject it before the search processes it
+        if !self.coord_space.wraps_y() && tile_coords[Y] > self.coord_space.world_top_tile_y() {
+            return SectionDiagnosis::OutsideWorldHeight;
+        }
+
+        let capture = self.capture_search(&context);
+        let capture = &capture;
+
+        // tiles that weren't captured were never processed, because they're beyond the
+        // search distance
+        let Some((tile_capture, section_index)) =
+            find_tile_capture(capture, &self.coord_space, section_coords)
+        else {
+            return SectionDiagnosis::OutsideFog;
+        };
+
+        let is_set =
+            |sections: [u64; 8]| tile::get_bit(&ffi::sections_from_ffi(sections), section_index);
+
+        if !is_set(tile_capture.after_frustum) {
+            if let Some(plane) = self.furthest_outside_plane(section_coords) {
+                return SectionDiagnosis::OutsideFrustum { plane };
+            }
+
+            // a tile that failed the tile-level tests without being outside the frustum
+            // must have been outside the fog
+            return SectionDiagnosis::OutsideFog;
+        }
+
+        if !is_set(tile_capture.after_fog) {
+            return SectionDiagnosis::OutsideFog;
+        }
+
+        if !is_set(tile_capture.after_height) {
+            return SectionDiagnosis::OutsideWorldHeight;
+        }
+
+        if is_set(tile_capture.after_traversal) {
+            let mut reached_from = 0;
+            for (dir_idx, sections) in tile_capture.incoming_dir_section_sets.iter().enumerate() {
+                if is_set(*sections) {
+                    reached_from |= 1 << dir_idx;
+                }
+            }
+
+            return SectionDiagnosis::Visible { reached_from };
+        }
+
+        let camera_section_coords = context.camera_section_coords();
+        let incoming_dirs = incoming_dirs(section_coords, camera_section_coords);
+
+        let mut visible_neighbors = 0;
+        let mut remaining_dirs = incoming_dirs;
+        while remaining_dirs != 0 {
+            let direction = take_one(&mut remaining_dirs);
+            let neighbor_coords = step_section(section_coords, direction);
+
+            let neighbor_visible = find_tile_capture(capture, &self.coord_space, neighbor_coords)
+                .is_some_and(|(neighbor_capture, neighbor_index)| {
+                    tile::get_bit(
+                        &ffi::sections_from_ffi(neighbor_capture.after_traversal),
+                        neighbor_index,
+                    )
+                });
+
+            if neighbor_visible {
+                visible_neighbors |= direction;
+            }
+        }
+
+        if is_set(tile_capture.without_angle_culling) {
+            SectionDiagnosis::AngleCulled {
+                incoming_dirs,
+                visible_neighbors,
+            }
+        } else {
+            SectionDiagnosis::Unreachable {
+                incoming_dirs,
+                visible_neighbors,
+            }
+        }
+    }
+
+    // Runs a captured search with the given context, restoring the results of
+    // the last search afterwards so they can still be queried.
+    fn capture_search(&mut self, context: &GraphSearchContext) -> SearchCapture {
+        let visible_tiles = mem::take(&mut self.visible_tiles);
+        let empty_lookup = vec![0; self.visible_tile_lookup.len()].into_boxed_slice();
+        let visible_tile_lookup = mem::replace(&mut self.visible_tile_lookup, empty_lookup);
+        let last_capture = self.last_capture.take();
+        let last_search_context = self.last_search_context.take();
+        let capture_next_search = self.capture_next_search;
+
+        self.capture_next_search();
+        self.cull(context);
+
+        let capture = self
+            .last_capture
+            .take()
+            .expect("capture should be present after a captured search");
+
+        self.visible_tiles = visible_tiles;
+        self.visible_tile_lookup = visible_tile_lookup;
+        self.last_capture = last_capture;
+        self.last_search_context = last_search_context;
+        self.capture_next_search = capture_next_search;
+
+        capture
+    }
+
+    fn furthest_outside_plane(&self, section_coords: i32x3) -> Option<u8> {
+        let context = self.last_search_context.as_ref()?;
+
+        let bb = context.section_bounding_box(section_coords);
+        context.frustum.furthest_outside_plane(bb)
+    }
+}
+
+fn find_tile_capture<'a>(
+    capture: &'a SearchCapture,
+    coord_space: &GraphCoordSpace,
+    section_coords: i32x3,
+) -> Option<(&'a FFITileCapture, u16)> {
+    let (_, section_coords_in_tile) = coord_space.section_to_tile_coords(section_coords);
+    let tile_origin = section_coords - section_coords_in_tile.cast::<i32>();
+
+    capture
+        .tiles
+        .iter()
+        .find(|tile_capture| tile_capture.origin_section_coords == tile_origin.to_array())
+        .map(|tile_capture| (tile_capture, tile::section_index(section_coords_in_tile)))
+}
+
+/// The directions that point back towards the camera on each axis, which are
+/// the only directions the traversal can enter a section from.
+fn incoming_dirs(section_coords: i32x3, camera_section_coords: i32x3) -> u8 {
+    let neg_dirs = section_coords.simd_gt(camera_section_coords).to_bitmask() as u8;
+    let pos_dirs = section_coords.simd_lt(camera_section_coords).to_bitmask() as u8;
+
+    neg_dirs | (pos_dirs << 3)
+}
+
+fn step_section(section_coords: i32x3, direction: u8) -> i32x3 {
+    let mut stepped = section_coords;
+    let axis = index_dir_to_axis(to_index(direction));
+
+    if direction & (POS_X | POS_Y | POS_Z) != 0 {
+        stepped[axis] += 1;
+    } else {
+        stepped[axis] -= 1;
+    }
+
+    stepped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{BoundingBox, SearchOptions};
+    use crate::graph::reference;
+    use crate::simd::prelude::*;
+
+    #[test]
+    fn diagnose_section_test() {
+        let mut graph = Graph::new(12, -4, 19);
+
+        for x in -16..16 {
+            for y in -4..20 {
+                for z in -16..16 {
+                    // a wall with no connections at Z=2
+                    let visibility_data = if z == 2 { 0 } else { !0 };
+                    graph.set_section(i32x3::from_xyz(x, y, z), visibility_data);
+                }
+            }
+        }
+
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 4, 1)),
+            SectionDiagnosis::NoSearch
+        );
+
+        // 90 degree FOV looking towards +Z, ordered as NEG_X, NEG_Y, NEG_Z, POS_X,
+        // POS_Y, POS_Z
+        let frustum_planes = [
+            f32x4::from_array([1.0, 0.0, 1.0, 0.0]),
+            f32x4::from_array([0.0, 1.0, 1.0, 0.0]),
+            f32x4::from_array([0.0, 0.0, 1.0, 0.05]),
+            f32x4::from_array([-1.0, 0.0, 1.0, 0.0]),
+            f32x4::from_array([0.0, -1.0, 1.0, 0.0]),
+            f32x4::from_array([0.0, 0.0, -1.0, 1000.0]),
+        ];
+        let context = GraphSearchContext::new(
+            &graph.coord_space,
+            frustum_planes,
+            f64x3::from_xyz(8.5, 72.5, 8.5),
+            &SearchOptions::new(64.0),
+        );
+        graph.cull(&context);
+        let visible_sections = reference::visible_sections(&graph.visible_tiles);
+
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 4, 1)),
+            SectionDiagnosis::Visible {
+                reached_from: NEG_Z
+            }
+        );
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 4, -3)),
+            SectionDiagnosis::OutsideFrustum { plane: NEG_Z }
+        );
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 4, 10)),
+            SectionDiagnosis::OutsideFog
+        );
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 30, 1)),
+            SectionDiagnosis::OutsideWorldHeight
+        );
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 4, 3)),
+            SectionDiagnosis::Unreachable {
+                incoming_dirs: NEG_Z,
+                visible_neighbors: NEG_Z,
+            }
+        );
+
+        // diagnosing sections shouldn't change the results of the last search
+        assert_eq!(
+            reference::visible_sections(&graph.visible_tiles),
+            visible_sections
+        );
+        assert!(graph.last_capture.is_none());
+        let mut results = [false];
+        graph.test_boxes(
+            [BoundingBox {
+                min: [0.0, 64.0, 16.0],
+                max: [1.0, 65.0, 17.0],
+            }]
+            .into_iter(),
+            &mut results,
+        );
+        assert_eq!(results, [true]);
+    }
+
+    #[test]
+    fn diagnose_padding_tile_test() {
+        // the world is only 4 sections tall, so the graph has a padding tile above it
+        let mut graph = Graph::new(12, 0, 3);
+        for x in -4..4 {
+            for y in 0..4 {
+                for z in -4..4 {
+                    graph.set_section(i32x3::from_xyz(x, y, z), !0);
+                }
+            }
+        }
+
+        // every plane accepts everything, so only the fog and height tests reject tiles
+        let context = GraphSearchContext::new(
+            &graph.coord_space,
+            [f32x4::from_array([0.0, 0.0, 0.0, 1.0]); 6],
+            f64x3::from_xyz(8.5, 56.5, 8.5),
+            &SearchOptions::new(128.0),
+        );
+        graph.cull(&context);
+
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 9, 0)),
+            SectionDiagnosis::OutsideWorldHeight
+        );
+        // above the world, but in the same tile as the top of the world
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 6, 0)),
+            SectionDiagnosis::OutsideWorldHeight
+        );
+        assert_eq!(
+            graph.diagnose_section(i32x3::from_xyz(0, 2, -16)),
+            SectionDiagnosis::OutsideFog
+        );
+    }
+
+    #[test]
+    fn incoming_dirs_test() {
+        let camera = i32x3::from_xyz(0, 4, 0);
+
+        assert_eq!(incoming_dirs(camera, camera), 0);
+        assert_eq!(incoming_dirs(i32x3::from_xyz(3, 4, 0), camera), NEG_X);
+        assert_eq!(
+            incoming_dirs(i32x3::from_xyz(-1, 8, -2), camera),
+            POS_X | NEG_Y | POS_Z
+        );
+    }
+}
@@ -12,6 +12,19 @@ pub const POS_Z: u8 = 0b100000;
 pub const ALL_DIRECTIONS: u8 = 0b111111;
 pub const DIRECTION_COUNT: usize = 6;
 
+// Minecraft's Direction ordinals, which are used by Sodium's visibility
+// encoding. Unlike the directions above, these are bit indices rather than
+// direction sets.
+pub const DOWN: u8 = 0;
+pub const UP: u8 = 1;
+pub const NORTH: u8 = 2;
+pub const SOUTH: u8 = 3;
+pub const WEST: u8 = 4;
+pub const EAST: u8 = 5;
+
+// every Minecraft direction, as a set of bits indexed by ordinal
+pub const ALL_ORDINALS: u8 = 0b111111;
+
 pub const fn to_index(direction: u8) -> usize {
     unsafe { NonZero::new_unchecked(direction) }.trailing_zeros() as usize
 }
@@ -0,0 +1,487 @@
+use std::error::Error;
+use std::fmt;
+
+/// An invalid input to the graph. These are returned to the caller instead of
+/// panicking, so it can fall back to another culler rather than crashing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphError {
+    /// A pointer passed across the FFI boundary was null. `name` is the name of
+    /// the parameter.
+    NullPointer { name: &'static str },
+    /// The world's top section is below its bottom section.
+    InvalidWorldHeight {
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+    },
+    /// The graph would be too large to index with its coordinate types.
+    GraphTooLarge {
+        y_length_tiles: u16,
+        xz_length_tiles: u16,
+    },
+    /// The section is above or below the tiles stored in the graph.
+    SectionOutOfBounds {
+        section_coords: [i32; 3],
+        graph_height_tiles: u8,
+    },
+    /// The search distance is negative, not a number, or larger than the graph.
+    InvalidSearchDistance {
+        search_distance: f32,
+        max_search_distance: f32,
+    },
+    /// The camera position is not finite, or too far away to be represented
+    /// with integer block coordinates.
+    InvalidCameraPosition { camera_pos: [f64; 3] },
+    /// A graph handle passed across the FFI boundary doesn't refer to a live
+    /// graph, either because the graph was deleted or because it was never
+    /// created.
+    InvalidHandle { handle: u64 },
+    /// The section length isn't a power of 2 that the graph supports, or the
+    /// bounding box extension is negative, not a number, or longer than a
+    /// section.
+    InvalidSectionGeometry {
+        section_length: u32,
+        bounds_extension: f32,
+    },
+    /// A fog shape passed across the FFI boundary doesn't match any of the
+    /// `FogShape` variants.
+    InvalidFogShape { fog_shape: u8 },
+    /// An argument is invalid for a reason other than being null, like a string
+    /// passed across the FFI boundary that isn't valid UTF-8, or a SIMD level
+    /// that the CPU doesn't support. `name` is the name of the parameter.
+    InvalidArgument {
+        name: &'static str,
+        reason: &'static str,
+    },
+    /// No search has been captured for the graph yet.
+    NoCapture,
+    /// Reading or writing a file failed.
+    Io { message: String },
+}
+
+impl GraphError {
+    pub const OK: u8 = 0;
+    pub const NULL_POINTER: u8 = 1;
+    pub const INVALID_WORLD_HEIGHT: u8 = 2;
+    pub const GRAPH_TOO_LARGE: u8 = 3;
+    pub const SECTION_OUT_OF_BOUNDS: u8 = 4;
+    pub const INVALID_SEARCH_DISTANCE: u8 = 5;
+    pub const INVALID_CAMERA_POSITION: u8 = 6;
+    pub const INVALID_HANDLE: u8 = 7;
+    pub const INVALID_SECTION_GEOMETRY: u8 = 8;
+    pub const INVALID_FOG_SHAPE: u8 = 9;
+    pub const INVALID_ARGUMENT: u8 = 10;
+    pub const NO_CAPTURE: u8 = 11;
+    pub const IO: u8 = 12;
+
+    /// The status code returned across the FFI boundary for this error.
+    pub fn status(&self) -> u8 {
+        match self {
+            GraphError::NullPointer { .. } => Self::NULL_POINTER,
+            GraphError::InvalidWorldHeight { .. } => Self::INVALID_WORLD_HEIGHT,
+            GraphError::GraphTooLarge { .. } => Self::GRAPH_TOO_LARGE,
+            GraphError::SectionOutOfBounds { .. } => Self::SECTION_OUT_OF_BOUNDS,
+            GraphError::InvalidSearchDistance { .. } => Self::INVALID_SEARCH_DISTANCE,
+            GraphError::InvalidCameraPosition { .. } => Self::INVALID_CAMERA_POSITION,
+            GraphError::InvalidHandle { .. } => Self::INVALID_HANDLE,
+            GraphError::InvalidSectionGeometry { .. } => Self::INVALID_SECTION_GEOMETRY,
+            GraphError::InvalidFogShape { .. } => Self::INVALID_FOG_SHAPE,
+            GraphError::InvalidArgument { .. } => Self::INVALID_ARGUMENT,
+            GraphError::NoCapture => Self::NO_CAPTURE,
+            GraphError::Io { .. } => Self::IO,
+        }
+    }
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::NullPointer { name } => write!(f, "Pointer must not be null - Parameter: {name}"),
+            GraphError::InvalidWorldHeight {
+                world_bottom_section_y,
+                world_top_section_y,
+            } => write!(
+                f,
+                "Invalid world height - Bottom Section: {world_bottom_section_y}, Top Section: {world_top_section_y}"
+            ),
+            GraphError::GraphTooLarge {
+                y_length_tiles,
+                xz_length_tiles,
+            } => write!(
+                f,
+                "Graph size is too large - Y Length (tiles): {y_length_tiles}, XZ Length (tiles): {xz_length_tiles}"
+            ),
+            GraphError::SectionOutOfBounds {
+                section_coords,
+                graph_height_tiles,
+            } => write!(
+                f,
+                "Section Y coordinate out of bounds - Section Coords: {section_coords:?}, Graph Height (tiles): {graph_height_tiles}"
+            ),
+            GraphError::InvalidSearchDistance {
+                search_distance,
+                max_search_distance,
+            } => write!(
+                f,
+                "Search distance must be between 0 and the maximum for the graph - Search Distance: {search_distance}, Maximum: {max_search_distance}"
+            ),
+            GraphError::InvalidCameraPosition { camera_pos } => {
+                write!(f, "Camera position out of bounds: {camera_pos:?}")
+            }
+            GraphError::InvalidHandle { handle } => {
+                write!(f, "Graph handle is invalid or was deleted - Handle: {handle:#x}")
+            }
+            GraphError::InvalidSectionGeometry {
+                section_length,
+                bounds_extension,
+            } => write!(
+                f,
+                "Invalid section geometry - Section Length: {section_length}, Bounds Extension: {bounds_extension}"
+            ),
+            GraphError::InvalidFogShape { fog_shape } => {
+                write!(f, "Invalid fog shape: {fog_shape}")
+            }
+            GraphError::InvalidArgument { name, reason } => {
+                write!(f, "Invalid argument - Parameter: {name}, Reason: {reason}")
+            }
+            GraphError::NoCapture => write!(f, "No search has been captured for the graph"),
+            GraphError::Io { message } => write!(f, "I/O error: {message}"),
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SearchOptions;
+    use crate::ffi::*;
+    use crate::graph::context::GraphSearchContext;
+    use crate::graph::coords::SectionGeometry;
+    use crate::graph::tile::fog::FogShape;
+    use crate::graph::Graph;
+    use crate::math::*;
+    use crate::simd::prelude::*;
+
+    const FRUSTUM_PLANES: [f32x4; 6] = [Simd::from_array([0.0, 0.0, 0.0, 1.0]); 6];
+
+    fn try_search_context(
+        graph: &Graph,
+        camera_pos: f64x3,
+        search_distance: f32,
+        vertical_search_distance: f32,
+    ) -> Result<GraphSearchContext, GraphError> {
+        GraphSearchContext::try_new(
+            &graph.coord_space,
+            FRUSTUM_PLANES,
+            camera_pos,
+            &SearchOptions {
+                vertical_search_distance,
+                ..SearchOptions::new(search_distance)
+            },
+        )
+    }
+
+    #[test]
+    fn graph_errors_test() {
+        assert!(matches!(
+            Graph::try_new(12, 4, 3),
+            Err(GraphError::InvalidWorldHeight { .. })
+        ));
+        // too many tiles on the XZ axes
+        assert!(matches!(
+            Graph::try_new(255, -4, 19),
+            Err(GraphError::GraphTooLarge { .. })
+        ));
+        // each axis fits, but the total tile count doesn't
+        assert!(matches!(
+            Graph::try_new(200, -128, 127),
+            Err(GraphError::GraphTooLarge { .. })
+        ));
+
+        // not a power of 2, too long, and bounds extensions that are negative, NaN, or
+        // longer than a section
+        for (section_length, bounds_extension) in [
+            (24, 1.0),
+            (0, 0.0),
+            (512, 1.0),
+            (16, -1.0),
+            (16, f32::NAN),
+            (16, 17.0),
+        ] {
+            assert!(matches!(
+                SectionGeometry::new(section_length, bounds_extension),
+                Err(GraphError::InvalidSectionGeometry { .. })
+            ));
+        }
+        assert_eq!(
+            SectionGeometry::new(16, 1.0),
+            Ok(SectionGeometry::MINECRAFT)
+        );
+
+        let mut graph = Graph::try_new(2, -4, 19).unwrap();
+        assert_eq!(
+            graph.try_set_section(i32x3::from_xyz(0, 60, 0), !0),
+            Err(GraphError::SectionOutOfBounds {
+                section_coords: [0, 60, 0],
+                graph_height_tiles: 3,
+            })
+        );
+        assert_eq!(graph.try_set_section(i32x3::from_xyz(0, 19, 0), !0), Ok(()));
+
+        let camera_pos = f64x3::from_xyz(8.5, 64.5, 8.5);
+        for search_distance in [-1.0, f32::NAN, 1000.0] {
+            assert!(matches!(
+                try_search_context(&graph, camera_pos, search_distance, 32.0),
+                Err(GraphError::InvalidSearchDistance { .. })
+            ));
+            assert!(matches!(
+                try_search_context(&graph, camera_pos, 32.0, search_distance),
+                Err(GraphError::InvalidSearchDistance { .. })
+            ));
+        }
+        // the vertical search distance can go past the height of the world, as long as
+        // it doesn't go past the width of the graph
+        assert!(try_search_context(&graph, camera_pos, 32.0, 300.0).is_ok());
+
+        for camera_pos in [
+            f64x3::from_xyz(f64::NAN, 64.5, 8.5),
+            f64x3::from_xyz(8.5, f64::INFINITY, 8.5),
+            f64x3::from_xyz(8.5, 64.5, 1e12),
+        ] {
+            assert!(matches!(
+                try_search_context(&graph, camera_pos, 32.0, 32.0),
+                Err(GraphError::InvalidCameraPosition { .. })
+            ));
+        }
+
+        assert!(try_search_context(&graph, camera_pos, 32.0, 32.0).is_ok());
+    }
+
+    fn last_error_message() -> String {
+        let mut message = FFISlice {
+            data_ptr: std::ptr::null(),
+            count: 0,
+        };
+
+        unsafe {
+            hypercull_error_get_message(&mut message);
+            let message = std::slice::from_raw_parts(message.data_ptr, message.count);
+            String::from_utf8(message.to_vec()).unwrap()
+        }
+    }
+
+    #[test]
+    fn ffi_error_message_test() {
+        let _lock = lock_ffi_tests();
+        unsafe {
+            let mut graph_handle = 0;
+            let status = hypercull_graph_create(&mut graph_handle, 12, 4, 3);
+            assert_eq!(status, GraphError::INVALID_WORLD_HEIGHT);
+            assert_eq!(graph_handle, 0);
+            assert_eq!(
+                last_error_message(),
+                "Invalid world height - Bottom Section: 4, Top Section: 3"
+            );
+        }
+
+        unsafe {
+            let mut graph_handle = 0;
+            let status =
+                hypercull_graph_create_with_geometry(&mut graph_handle, 12, -4, 19, 48, 1.0);
+            assert_eq!(status, GraphError::INVALID_SECTION_GEOMETRY);
+            assert_eq!(graph_handle, 0);
+            assert_eq!(
+                last_error_message(),
+                "Invalid section geometry - Section Length: 48, Bounds Extension: 1"
+            );
+        }
+
+        unsafe {
+            let mut graph_handle = 0;
+            let status = hypercull_graph_create_cubic(&mut graph_handle, 12, 255, 16, 1.0);
+            assert_eq!(status, GraphError::GRAPH_TOO_LARGE);
+            assert_eq!(graph_handle, 0);
+            assert_eq!(
+                last_error_message(),
+                "Graph size is too large - Y Length (tiles): 65, XZ Length (tiles): 4"
+            );
+        }
+
+        unsafe {
+            let mut graph_handle = 0;
+            assert_eq!(
+                hypercull_graph_create(&mut graph_handle, 2, -4, 19),
+                GraphError::OK
+            );
+
+            let camera = FFICamera {
+                frustum_planes: [[0.0, 0.0, 0.0, 1.0]; 6],
+                pos: [8.5, 64.5, 8.5],
+            };
+            let mut visible_tiles = FFISlice {
+                data_ptr: std::ptr::null(),
+                count: 0,
+            };
+            let status = hypercull_graph_search_with_fog_shape(
+                &mut visible_tiles,
+                graph_handle,
+                &camera,
+                32.0,
+                true,
+                3,
+            );
+            assert_eq!(status, GraphError::INVALID_FOG_SHAPE);
+            assert_eq!(last_error_message(), "Invalid fog shape: 3");
+
+            let status = hypercull_graph_search_with_options(
+                &mut visible_tiles,
+                graph_handle,
+                &camera,
+                std::ptr::null(),
+            );
+            assert_eq!(status, GraphError::NULL_POINTER);
+            assert_eq!(
+                last_error_message(),
+                "Pointer must not be null - Parameter: options_ptr"
+            );
+
+            // the bounds extension can't be longer than a section, like when creating a
+            // graph
+            let options = FFISearchOptions {
+                bounds_extension: 20.0,
+                ..FFISearchOptions::new(32.0, true, FogShape::Cylindrical as u8)
+            };
+            let status = hypercull_graph_search_with_options(
+                &mut visible_tiles,
+                graph_handle,
+                &camera,
+                &options,
+            );
+            assert_eq!(status, GraphError::INVALID_SECTION_GEOMETRY);
+            assert_eq!(
+                last_error_message(),
+                "Invalid section geometry - Section Length: 16, Bounds Extension: 20"
+            );
+            assert!(visible_tiles.data_ptr.is_null());
+
+            let path = "capture.txt";
+            let status = hypercull_graph_write_capture(graph_handle, std::ptr::null(), 4);
+            assert_eq!(status, GraphError::NULL_POINTER);
+            assert_eq!(
+                last_error_message(),
+                "Pointer must not be null - Parameter: path_ptr"
+            );
+
+            let invalid_utf8 = [0xFF_u8, 0xFE];
+            let status = hypercull_graph_write_capture(
+                graph_handle,
+                invalid_utf8.as_ptr(),
+                invalid_utf8.len(),
+            );
+            assert_eq!(status, GraphError::INVALID_ARGUMENT);
+            assert_eq!(
+                last_error_message(),
+                "Invalid argument - Parameter: path_ptr, Reason: path is not valid UTF-8"
+            );
+
+            let status = hypercull_graph_write_capture(graph_handle, path.as_ptr(), usize::MAX);
+            assert_eq!(status, GraphError::INVALID_ARGUMENT);
+
+            let status = hypercull_trace_start(std::ptr::null(), 4);
+            assert_eq!(status, GraphError::NULL_POINTER);
+            assert_eq!(
+                last_error_message(),
+                "Pointer must not be null - Parameter: path_ptr"
+            );
+
+            // the directory doesn't exist, so the trace file can't be created
+            let missing_path = std::env::temp_dir().join("hypercull-missing-dir/trace.bin");
+            let missing_path = missing_path.to_str().unwrap();
+            let status = hypercull_trace_start(missing_path.as_ptr(), missing_path.len());
+            assert_eq!(status, GraphError::IO);
+            assert!(last_error_message().starts_with("I/O error: "));
+
+            let status = hypercull_graph_write_capture(graph_handle, path.as_ptr(), path.len());
+            assert_eq!(status, GraphError::NO_CAPTURE);
+            assert_eq!(
+                last_error_message(),
+                "No search has been captured for the graph"
+            );
+
+            let mut capture = FFISlice::from(&[][..]);
+            let status = hypercull_graph_get_capture(&mut capture, graph_handle);
+            assert_eq!(status, GraphError::NO_CAPTURE);
+            assert_eq!(
+                last_error_message(),
+                "No search has been captured for the graph"
+            );
+
+            let mut results = [false; 1];
+            let status =
+                hypercull_graph_test_boxes(results.as_mut_ptr(), graph_handle, std::ptr::null(), 1);
+            assert_eq!(status, GraphError::NULL_POINTER);
+            assert_eq!(
+                last_error_message(),
+                "Pointer must not be null - Parameter: boxes_ptr"
+            );
+
+            assert_eq!(hypercull_graph_delete(graph_handle), GraphError::OK);
+        }
+
+        let status = hypercull_graph_set_section(0, 0, 0, 0, !0);
+        assert_eq!(status, GraphError::INVALID_HANDLE);
+    }
+
+    #[test]
+    fn ffi_null_return_value_ptr_test() {
+        let _lock = lock_ffi_tests();
+        const MESSAGE: &str = "Pointer must not be null - Parameter: return_value_ptr";
+
+        unsafe {
+            let statuses = [
+                hypercull_get_layout(std::ptr::null_mut()),
+                hypercull_get_build_info(std::ptr::null_mut()),
+                hypercull_graph_create(std::ptr::null_mut(), 2, -4, 19),
+                hypercull_graph_create_with_geometry(std::ptr::null_mut(), 2, -4, 19, 16, 1.0),
+                hypercull_graph_create_cubic(std::ptr::null_mut(), 2, 2, 16, 1.0),
+            ];
+            for status in statuses {
+                assert_eq!(status, GraphError::NULL_POINTER);
+                assert_eq!(last_error_message(), MESSAGE);
+            }
+
+            let mut graph_handle = 0;
+            assert_eq!(
+                hypercull_graph_create(&mut graph_handle, 2, -4, 19),
+                GraphError::OK
+            );
+
+            let camera = FFICamera {
+                frustum_planes: [[0.0, 0.0, 0.0, 1.0]; 6],
+                pos: [8.5, 64.5, 8.5],
+            };
+            let options = FFISearchOptions::new(32.0, true, FogShape::Cylindrical as u8);
+            let statuses = [
+                hypercull_graph_search_with_options(
+                    std::ptr::null_mut(),
+                    graph_handle,
+                    &camera,
+                    &options,
+                ),
+                hypercull_graph_get_simd_level(std::ptr::null_mut(), graph_handle),
+                hypercull_graph_diagnose_section(std::ptr::null_mut(), graph_handle, 0, 4, 0),
+                hypercull_graph_get_capture(std::ptr::null_mut(), graph_handle),
+            ];
+            for status in statuses {
+                assert_eq!(status, GraphError::NULL_POINTER);
+                assert_eq!(last_error_message(), MESSAGE);
+            }
+
+            // there's nowhere to report this error, so it's ignored
+            hypercull_error_get_message(std::ptr::null_mut());
+
+            assert_eq!(hypercull_graph_delete(graph_handle), GraphError::OK);
+        }
+    }
+}
@@ -1,18 +1,28 @@
+use capture::SearchCapture;
 use context::{CameraArea, CombinedTestResults, GraphSearchContext};
-use coords::{GraphCoordSpace, LocalTileIndex};
-use core_simd::simd::prelude::*;
+use coords::{GraphCoordSpace, LocalTileIndex, SectionGeometry};
 use direction::*;
+use error::GraphError;
 use tile::Tile;
 use visibility::*;
 
 use self::coords::LocalTileCoords;
 use crate::bitset::{self, BitSet};
-use crate::ffi::FFITile;
+use crate::ffi::{self, FFITile, FFITileCapture};
 use crate::math::*;
+use crate::simd::prelude::*;
 
+pub mod capture;
 pub mod context;
 pub mod coords;
+pub mod diagnosis;
 pub mod direction;
+pub mod error;
+pub mod query;
+#[cfg(any(test, feature = "cli"))]
+pub mod reference;
+#[cfg(test)]
+pub mod test_world;
 pub mod tile;
 pub mod visibility;
 
@@ -27,7 +37,7 @@ macro_rules! iterate_dirs {
                 $context,
                 $context.iter_start_tile_coords,
                 DIRS_SLICE,
-                Self::process_tile::<INCOMING_DIRS, TRAVERSAL_DIRS>,
+                Self::process_tile::<INCOMING_DIRS, TRAVERSAL_DIRS, L>,
             );
         }
     }};
@@ -54,20 +64,116 @@ pub struct Graph {
     oob_above_incoming_sections: u8x64,
 
     pub visible_tiles: Vec<FFITile>,
+    // for each tile in the graph, the index of its entry in visible_tiles plus 1, or 0 if none of
+    // its sections were visible in the last search
+    visible_tile_lookup: Box<[u32]>,
+
+    capture_next_search: bool,
+    active_capture: Option<SearchCapture>,
+    pub last_capture: Option<SearchCapture>,
+
+    last_search_context: Option<GraphSearchContext>,
+
+    simd_level: SimdLevel,
 }
 
 impl Graph {
     pub fn new(render_distance: u8, world_bottom_section_y: i8, world_top_section_y: i8) -> Self {
-        // Same as Minecraft's ClientChunkCache.calculateStorageRange
-        let storage_distance = render_distance.max(2) + 3;
+        Self::try_new(render_distance, world_bottom_section_y, world_top_section_y)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    pub fn try_new(
+        render_distance: u8,
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+    ) -> Result<Self, GraphError> {
+        Self::try_new_with_geometry(
+            render_distance,
+            world_bottom_section_y,
+            world_top_section_y,
+            SectionGeometry::MINECRAFT,
+        )
+    }
+
+    /// The render distance and world height are in sections, so a larger
+    /// section length covers more blocks with the same number of tiles.
+    pub fn try_new_with_geometry(
+        render_distance: u8,
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+        geometry: SectionGeometry,
+    ) -> Result<Self, GraphError> {
+        if world_top_section_y < world_bottom_section_y {
+            return Err(GraphError::InvalidWorldHeight {
+                world_bottom_section_y,
+                world_top_section_y,
+            });
+        }
+
         let y_length_sections =
             (world_top_section_y as i16 - world_bottom_section_y as i16 + 1) as u16;
-        let xz_length_sections = (storage_distance as u16 * 2) + 1;
 
-        assert!(y_length_sections > 0 && xz_length_sections > 0,
-            "Invalid graph size. RD: {render_distance}, Bottom Section: {world_bottom_section_y}, Top Section: {world_top_section_y}"
-        );
+        let section_height_in_top_tile =
+            y_length_sections % LocalTileCoords::LENGTH_IN_SECTIONS as u16;
+        // worlds that are 8 sections or shorter have a padding tile above them, which
+        // has to be excluded by the height checks
+        let has_padding_tile = y_length_sections <= LocalTileCoords::LENGTH_IN_SECTIONS as u16;
+        let do_height_checks = section_height_in_top_tile != 0 || has_padding_tile;
+
+        let mut graph = Self::allocate(
+            y_length_sections,
+            Self::storage_length_sections(render_distance),
+            world_bottom_section_y,
+            world_top_section_y,
+            None,
+            geometry,
+        )?;
+        graph.do_height_checks = do_height_checks;
+        if section_height_in_top_tile != 0 {
+            graph.top_tile_visibility_mask =
+                tile::height::gen_top_tile_visibility_mask(section_height_in_top_tile);
+        }
+        graph.oob_above_incoming_sections =
+            tile::height::gen_oob_above_incoming_sections(section_height_in_top_tile);
+        Ok(graph)
+    }
+
+    /// Creates a graph for cubic chunks style worlds, which have no height
+    /// limit. The Y axis wraps around like the X and Z axes, and the graph
+    /// extends `vertical_render_distance` sections above and below the
+    /// camera.
+    pub fn try_new_cubic(
+        render_distance: u8,
+        vertical_render_distance: u8,
+        geometry: SectionGeometry,
+    ) -> Result<Self, GraphError> {
+        // the world has no top or bottom, so there's nothing for the height checks or
+        // out-of-bounds edges to do
+        Self::allocate(
+            Self::storage_length_sections(vertical_render_distance),
+            Self::storage_length_sections(render_distance),
+            0,
+            0,
+            Some(vertical_render_distance),
+            geometry,
+        )
+    }
 
+    // Same as Minecraft's ClientChunkCache.calculateStorageRange
+    fn storage_length_sections(render_distance: u8) -> u16 {
+        let storage_distance = render_distance.max(2) as u16 + 3;
+        (storage_distance * 2) + 1
+    }
+
+    fn allocate(
+        y_length_sections: u16,
+        xz_length_sections: u16,
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+        vertical_render_distance: Option<u8>,
+        geometry: SectionGeometry,
+    ) -> Result<Self, GraphError> {
         // the minimum size of the graph is 2x2x2 tiles, so we can guarantee that each
         // tile will only be processed once. if any axis were allowed to have a
         // size of 1, when the graph search wraps past the edge of the graph, we
@@ -81,10 +187,15 @@ impl Graph {
         // when going out-of-bounds, and u16 indices.
         const MAX_AXIS_LENGTH: u16 = 64;
         const MAX_TOTAL_TILES: usize = u16::MAX as usize + 1;
-        assert!(
-            y_length_tiles <= MAX_AXIS_LENGTH && xz_length_tiles <= MAX_AXIS_LENGTH && graph_total_tiles <= MAX_TOTAL_TILES,
-            "Graph size is too large. Y Length (tiles): {y_length_tiles}, XZ Length (tiles): {xz_length_tiles}"
-        );
+        if y_length_tiles > MAX_AXIS_LENGTH
+            || xz_length_tiles > MAX_AXIS_LENGTH
+            || graph_total_tiles > MAX_TOTAL_TILES
+        {
+            return Err(GraphError::GraphTooLarge {
+                y_length_tiles,
+                xz_length_tiles,
+            });
+        }
 
         let tiles = unsafe {
             let mut tiles_uninit = Box::<[Tile]>::new_uninit_slice(graph_total_tiles);
@@ -96,39 +207,79 @@ impl Graph {
             Tiles(tiles_uninit.assume_init())
         };
 
-        let section_height_in_top_tile =
-            y_length_sections % LocalTileCoords::LENGTH_IN_SECTIONS as u16;
-        let do_height_checks = section_height_in_top_tile != 0;
-        let top_tile_visibility_mask = if do_height_checks {
-            tile::height::gen_top_tile_visibility_mask(section_height_in_top_tile)
-        } else {
-            Simd::splat(!0)
-        };
-
-        Self {
+        Ok(Self {
             tiles,
             coord_space: GraphCoordSpace::new(
                 y_length_tiles as u8,
                 xz_length_tiles as u8,
                 world_bottom_section_y,
                 world_top_section_y,
+                vertical_render_distance,
+                geometry,
             ),
-            do_height_checks,
-            top_tile_visibility_mask,
+            do_height_checks: false,
+            top_tile_visibility_mask: tile::SECTIONS_FILLED,
             visible_tiles: Vec::with_capacity(128),
-            oob_above_incoming_sections: tile::height::gen_oob_above_incoming_sections(
-                section_height_in_top_tile,
-            ),
-        }
+            visible_tile_lookup: vec![0; graph_total_tiles].into_boxed_slice(),
+            oob_above_incoming_sections: tile::SECTIONS_EMPTY,
+            capture_next_search: false,
+            active_capture: None,
+            last_capture: None,
+            last_search_context: None,
+            simd_level: SimdLevel::detect(),
+        })
     }
 
     pub fn cull(&mut self, context: &GraphSearchContext) {
         self.clear();
 
-        self.iterate_tiles(context);
+        if self.capture_next_search {
+            self.capture_next_search = false;
+            self.active_capture = Some(SearchCapture::new(context));
+        }
+
+        with_level!(self.simd_level, L => self.iterate_tiles::<L>(context));
+
+        if let Some(capture) = self.active_capture.take() {
+            self.last_capture = Some(capture);
+        }
+
+        self.last_search_context = Some(context.clone());
+    }
+
+    /// Records the result of every culling stage for each tile processed in the
+    /// next search. The results can be retrieved from `last_capture` once the
+    /// search completes.
+    pub fn capture_next_search(&mut self) {
+        self.capture_next_search = true;
+    }
+
+    /// Returns the set of target features that the culling kernels run with.
+    pub fn simd_level(&self) -> SimdLevel {
+        self.simd_level
+    }
+
+    /// Overrides the detected SIMD level, which is mostly useful for comparing
+    /// levels in tests and benchmarks.
+    ///
+    /// # Panics
+    /// If the CPU doesn't support `level`.
+    pub fn set_simd_level(&mut self, level: SimdLevel) {
+        assert!(
+            level.is_supported(),
+            "SIMD level {level} is not supported by this CPU"
+        );
+        self.simd_level = level;
     }
 
     pub fn clear(&mut self) {
+        for visible_tile in &self.visible_tiles {
+            let (tile_coords, _) = self
+                .coord_space
+                .section_to_tile_coords(Simd::from_array(visible_tile.origin_section_coords));
+            let index = self.coord_space.pack_index(tile_coords);
+            self.visible_tile_lookup[index.to_usize()] = 0;
+        }
         self.visible_tiles.clear();
 
         #[cfg(debug_assertions)]
@@ -137,10 +288,10 @@ impl Graph {
         }
     }
 
-    fn iterate_tiles(&mut self, context: &GraphSearchContext) {
+    fn iterate_tiles<L: Level>(&mut self, context: &GraphSearchContext) {
         // Center
         if Self::should_process::<0>(context.camera_area) {
-            self.process_tile::<0, ALL_DIRECTIONS>(
+            self.process_tile::<0, ALL_DIRECTIONS, L>(
                 context,
                 self.coord_space.pack_index(context.iter_start_tile_coords),
                 context.iter_start_tile_coords,
@@ -219,25 +370,44 @@ impl Graph {
 
     // the inlining of this function was a bit too aggressive
     #[inline(never)]
-    fn process_tile<const INCOMING_DIRS: u8, const TRAVERSAL_DIRS: u8>(
+    fn process_tile<const INCOMING_DIRS: u8, const TRAVERSAL_DIRS: u8, L: Level>(
         &mut self,
         context: &GraphSearchContext,
         index: LocalTileIndex,
         coords: LocalTileCoords,
     ) {
-        #[cfg(debug_assertions)]
-        println!("Current Tile - Coords: {:?} Index: {:?}", coords.0, index.0);
+        // SAFETY: Kernels are only instantiated with levels that the CPU supports.
+        unsafe {
+            L::dispatch(|| {
+                self.process_tile_body::<INCOMING_DIRS, TRAVERSAL_DIRS, L>(context, index, coords)
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn process_tile_body<const INCOMING_DIRS: u8, const TRAVERSAL_DIRS: u8, L: Level>(
+        &mut self,
+        context: &GraphSearchContext,
+        index: LocalTileIndex,
+        coords: LocalTileCoords,
+    ) {
+        log::trace!("Current Tile - Coords: {:?} Index: {:?}", coords.0, index.0);
 
         // try to quickly determine whether we need to actually traverse the tile using
         // the frustum, fog, etc
         let relative_tile_pos = context.relative_tile_pos(coords);
-        let test_result = context.test_tile(
+        let test_result = context.test_tile::<L>(
             &self.coord_space,
             coords,
             relative_tile_pos,
             self.do_height_checks,
         );
 
+        let mut tile_capture = self
+            .active_capture
+            .is_some()
+            .then(|| FFITileCapture::new(context.tile_origin_section_coords(coords), test_result));
+
         // tile needs to be re-borrowed multiple times in this method due to borrow
         // checker rules. these should get optimized out.
         let mut tile = self.tiles.get_mut(index);
@@ -251,6 +421,7 @@ impl Graph {
         if test_result == CombinedTestResults::OUTSIDE {
             // early exit
             tile.set_empty();
+            self.push_tile_capture(tile_capture);
             return;
         }
         // All sections are visible initially, and each culling method masks it
@@ -258,21 +429,39 @@ impl Graph {
 
         let intersecting_planes = test_result.get_intersecting_planes();
         if intersecting_planes != 0 {
-            context.frustum.voxelize_planes(
+            context.frustum.voxelize_planes::<L>(
                 intersecting_planes,
                 relative_tile_pos,
                 &mut visible_sections,
             );
         }
 
+        if let Some(tile_capture) = &mut tile_capture {
+            tile_capture.after_frustum = ffi::sections_to_ffi(visible_sections);
+        }
+
         if test_result.is_partial::<{ CombinedTestResults::FOG_BIT }>() {
-            context.voxelize_fog_cylinder(relative_tile_pos, &mut visible_sections);
+            context.voxelize_fog::<L>(relative_tile_pos, &mut visible_sections);
         }
 
-        if test_result.is_partial::<{ CombinedTestResults::HEIGHT_BIT }>() {
+        if let Some(tile_capture) = &mut tile_capture {
+            tile_capture.after_fog = ffi::sections_to_ffi(visible_sections);
+        }
+
+        // tiles above the world are still skipped when height culling is disabled,
+        // because their neighbors above aren't stored
+        if context.use_height_culling
+            && test_result.is_partial::<{ CombinedTestResults::HEIGHT_BIT }>()
+        {
             visible_sections &= self.top_tile_visibility_mask;
         }
 
+        if let Some(tile_capture) = &mut tile_capture {
+            tile_capture.after_height = ffi::sections_to_ffi(visible_sections);
+            tile_capture.after_traversal = tile_capture.after_height;
+            tile_capture.without_angle_culling = tile_capture.after_height;
+        }
+
         if context.use_occlusion_culling {
             let mut traverse_start_sections = tile::SECTIONS_EMPTY;
             let mut incoming_dir_section_sets = [tile::SECTIONS_EMPTY; DIRECTION_COUNT];
@@ -287,7 +476,7 @@ impl Graph {
                 tile.setup_center_tile(section_index);
             } else {
                 // tile goes out of scope here so we can observe neighboring tiles
-                self.get_incoming_edges::<INCOMING_DIRS>(
+                self.get_incoming_edges::<INCOMING_DIRS, L>(
                     coords,
                     context.camera_area,
                     visible_sections,
@@ -301,6 +490,11 @@ impl Graph {
                 if traverse_start_sections == tile::SECTIONS_EMPTY {
                     // early exit
                     tile.set_empty();
+                    if let Some(tile_capture) = &mut tile_capture {
+                        tile_capture.after_traversal = [0; 8];
+                        tile_capture.without_angle_culling = [0; 8];
+                    }
+                    self.push_tile_capture(tile_capture);
                     return;
                 }
             }
@@ -309,19 +503,46 @@ impl Graph {
             // traversed in this tile. because of this, we know atleast part of
             // it is visible.
 
-            let angle_visibility_masks = tile::angle::gen_visibility_masks(relative_tile_pos);
+            let angle_visibility_masks = if context.use_angle_culling {
+                tile::angle::gen_visibility_masks::<L>(relative_tile_pos, self.coord_space.geometry)
+            } else {
+                [tile::SECTIONS_FILLED; 3]
+            };
+
+            if let Some(tile_capture) = &mut tile_capture {
+                tile_capture.angle_masks = angle_visibility_masks.map(ffi::sections_to_ffi);
+
+                // traverse a copy of the tile without angle culling, so diagnostics can tell
+                // whether angle culling is the reason a section isn't visible
+                let mut unculled_incoming_dir_section_sets = incoming_dir_section_sets;
+                let mut unculled_visible_sections = visible_sections;
+                tile.clone().traverse::<TRAVERSAL_DIRS, L>(
+                    traverse_start_sections,
+                    &mut unculled_incoming_dir_section_sets,
+                    &context.outward_direction_masks,
+                    &[tile::SECTIONS_FILLED; 3],
+                    &mut unculled_visible_sections,
+                );
+                tile_capture.without_angle_culling =
+                    ffi::sections_to_ffi(unculled_visible_sections);
+            }
 
             #[cfg(debug_assertions)]
             let old_visible_sections = visible_sections;
 
-            tile.traverse::<TRAVERSAL_DIRS>(
+            tile.traverse::<TRAVERSAL_DIRS, L>(
                 traverse_start_sections,
-                incoming_dir_section_sets,
+                &mut incoming_dir_section_sets,
                 &context.outward_direction_masks,
                 &angle_visibility_masks,
                 &mut visible_sections,
             );
 
+            if let Some(tile_capture) = &mut tile_capture {
+                tile_capture.incoming_dir_section_sets =
+                    incoming_dir_section_sets.map(ffi::sections_to_ffi);
+            }
+
             #[cfg(debug_assertions)]
             {
                 assert_eq!(
@@ -340,17 +561,31 @@ impl Graph {
             }
         }
 
+        if let Some(tile_capture) = &mut tile_capture {
+            tile_capture.after_traversal = ffi::sections_to_ffi(visible_sections);
+        }
+        self.push_tile_capture(tile_capture);
+
         if visible_sections != tile::SECTIONS_EMPTY {
-            let local_section_coords = coords.0.cast::<i32>() << 3;
-            let global_section_coords = context.global_section_offset + local_section_coords;
+            // there can be up to 65536 tiles in the graph, so the index plus 1 doesn't always
+            // fit in a u16
+            self.visible_tile_lookup[index.to_usize()] = self.visible_tiles.len() as u32 + 1;
+            self.visible_tiles.push(FFITile::new(
+                context.tile_origin_section_coords(coords),
+                visible_sections,
+            ));
+        }
+    }
 
-            self.visible_tiles
-                .push(FFITile::new(global_section_coords, visible_sections));
+    fn push_tile_capture(&mut self, tile_capture: Option<FFITileCapture>) {
+        if let (Some(capture), Some(tile_capture)) = (&mut self.active_capture, tile_capture) {
+            capture.tiles.push(tile_capture);
         }
     }
 
     // TODO: consider not using const generics for this
-    fn get_incoming_edges<const INCOMING_DIRS: u8>(
+    #[inline(always)]
+    fn get_incoming_edges<const INCOMING_DIRS: u8, L: Level>(
         &self,
         coords: LocalTileCoords,
         camera_area: CameraArea,
@@ -360,61 +595,64 @@ impl Graph {
     ) {
         if bitset::contains_u8(INCOMING_DIRS, NEG_X) {
             let incoming_edge =
-                self.get_incoming_edge::<NEG_X>(coords, camera_area) & visibility_mask;
+                self.get_incoming_edge::<NEG_X, L>(coords, camera_area) & visibility_mask;
             *traverse_start_sections |= incoming_edge;
             incoming_dir_section_sets[to_index(NEG_X)] = incoming_edge;
         }
 
         if bitset::contains_u8(INCOMING_DIRS, NEG_Y) {
             let incoming_edge =
-                self.get_incoming_edge::<NEG_Y>(coords, camera_area) & visibility_mask;
+                self.get_incoming_edge::<NEG_Y, L>(coords, camera_area) & visibility_mask;
             *traverse_start_sections |= incoming_edge;
             incoming_dir_section_sets[to_index(NEG_Y)] = incoming_edge;
         }
 
         if bitset::contains_u8(INCOMING_DIRS, NEG_Z) {
             let incoming_edge =
-                self.get_incoming_edge::<NEG_Z>(coords, camera_area) & visibility_mask;
+                self.get_incoming_edge::<NEG_Z, L>(coords, camera_area) & visibility_mask;
             *traverse_start_sections |= incoming_edge;
             incoming_dir_section_sets[to_index(NEG_Z)] = incoming_edge;
         }
 
         if bitset::contains_u8(INCOMING_DIRS, POS_X) {
             let incoming_edge =
-                self.get_incoming_edge::<POS_X>(coords, camera_area) & visibility_mask;
+                self.get_incoming_edge::<POS_X, L>(coords, camera_area) & visibility_mask;
             *traverse_start_sections |= incoming_edge;
             incoming_dir_section_sets[to_index(POS_X)] = incoming_edge;
         }
 
         if bitset::contains_u8(INCOMING_DIRS, POS_Y) {
             let incoming_edge =
-                self.get_incoming_edge::<POS_Y>(coords, camera_area) & visibility_mask;
+                self.get_incoming_edge::<POS_Y, L>(coords, camera_area) & visibility_mask;
             *traverse_start_sections |= incoming_edge;
             incoming_dir_section_sets[to_index(POS_Y)] = incoming_edge;
         }
 
         if bitset::contains_u8(INCOMING_DIRS, POS_Z) {
             let incoming_edge =
-                self.get_incoming_edge::<POS_Z>(coords, camera_area) & visibility_mask;
+                self.get_incoming_edge::<POS_Z, L>(coords, camera_area) & visibility_mask;
             *traverse_start_sections |= incoming_edge;
             incoming_dir_section_sets[to_index(POS_Z)] = incoming_edge;
         }
     }
 
-    fn get_incoming_edge<const DIRECTION: u8>(
+    #[inline(always)]
+    fn get_incoming_edge<const DIRECTION: u8, L: Level>(
         &self,
         coords: LocalTileCoords,
         camera_area: CameraArea,
     ) -> u8x64 {
-        // deal with fetching edge from out-of-bounds
-        let top_tile_y = (self.coord_space.y_length_tiles - 1) as i8;
-        if DIRECTION == POS_Y && coords[Y] == top_tile_y {
+        // deal with fetching edge from out-of-bounds. in cubic chunks mode, the Y axis
+        // wraps, so there's no out-of-bounds.
+        let top_tile_y = self.coord_space.world_top_tile_y();
+        let wraps_y = self.coord_space.wraps_y();
+        if DIRECTION == POS_Y && coords[Y] == top_tile_y && !wraps_y {
             if camera_area == CameraArea::Above {
                 return self.oob_above_incoming_sections;
             } else {
                 return tile::SECTIONS_EMPTY;
             }
-        } else if DIRECTION == NEG_Y && coords[Y] == 0 {
+        } else if DIRECTION == NEG_Y && coords[Y] == 0 && !wraps_y {
             if camera_area == CameraArea::Below {
                 return tile::OUT_OF_BOUNDS_BELOW_INCOMING_SECTIONS;
             } else {
@@ -431,31 +669,39 @@ impl Graph {
 
         match DIRECTION {
             NEG_X => tile::traversal::edge_pos_to_neg_x(neighbor_outgoing_sections),
-            NEG_Y => tile::traversal::edge_pos_to_neg_y(neighbor_outgoing_sections),
-            NEG_Z => tile::traversal::edge_pos_to_neg_z(neighbor_outgoing_sections),
+            NEG_Y => tile::traversal::edge_pos_to_neg_y::<L>(neighbor_outgoing_sections),
+            NEG_Z => tile::traversal::edge_pos_to_neg_z::<L>(neighbor_outgoing_sections),
             POS_X => tile::traversal::edge_neg_to_pos_x(neighbor_outgoing_sections),
-            POS_Y => tile::traversal::edge_neg_to_pos_y(neighbor_outgoing_sections),
-            POS_Z => tile::traversal::edge_neg_to_pos_z(neighbor_outgoing_sections),
+            POS_Y => tile::traversal::edge_neg_to_pos_y::<L>(neighbor_outgoing_sections),
+            POS_Z => tile::traversal::edge_neg_to_pos_z::<L>(neighbor_outgoing_sections),
             _ => unreachable!(),
         }
     }
 
     pub fn set_section(&mut self, section_coords: i32x3, visibility_data: u64) {
+        self.try_set_section(section_coords, visibility_data)
+            .unwrap_or_else(|error| panic!("{error}"));
+    }
+
+    pub fn try_set_section(
+        &mut self,
+        section_coords: i32x3,
+        visibility_data: u64,
+    ) -> Result<(), GraphError> {
         let (tile_coords, section_coords_in_tile) =
             self.coord_space.section_to_tile_coords(section_coords);
 
-        assert!(
-            self.coord_space.tile_coords_in_bounds(tile_coords),
-            "Tile Y coordinate out of bounds - Y: {}, Graph Height: {}",
-            tile_coords[Y],
-            self.coord_space.y_length_tiles,
-        );
+        if !self.coord_space.tile_coords_in_bounds(tile_coords) {
+            return Err(GraphError::SectionOutOfBounds {
+                section_coords: section_coords.to_array(),
+                graph_height_tiles: self.coord_space.y_length_tiles,
+            });
+        }
 
         let tile_index = self.coord_space.pack_index(tile_coords);
         let section_index = tile::section_index(section_coords_in_tile);
 
-        #[cfg(debug_assertions)]
-        println!(
+        log::trace!(
             "Set Section - Section Coords: {:?}, Tile Coords: {:?}, Tile Index: {:?}, Section Index: {:?}, Vis: {}",
             section_coords, tile_coords.0, tile_index.0, section_index, visibility_data
         );
@@ -469,5 +715,356 @@ impl Graph {
                 visibility_data.get_bit(bit_idx),
             );
         }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::f32::consts::FRAC_PI_2;
+
+    use rand::prelude::*;
+
+    use super::coords::RelativeBoundingBox;
+    use super::reference::visible_sections;
+    use super::tile::fog::FogShape;
+    use super::*;
+    use crate::api::SearchOptions;
+    use crate::TESTS_RANDOM_SEED;
+
+    const SEARCH_COUNT: usize = 48;
+
+    struct Scenario {
+        render_distance: u8,
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+        // cubic chunks mode if present
+        vertical_render_distance: Option<u8>,
+        geometry: SectionGeometry,
+        connection_chance: f64,
+        frustum_planes: [f32x4; 6],
+        camera_pos: f64x3,
+        // every culling stage is enabled
+        options: SearchOptions,
+    }
+
+    impl Scenario {
+        fn random(rand: &mut StdRng) -> Self {
+            let render_distance = rand.random_range(2..=12);
+            let world_bottom_section_y = rand.random_range(-8..=0);
+            let world_top_section_y = world_bottom_section_y + rand.random_range(0..40);
+            let vertical_render_distance =
+                rand.random_bool(0.25).then(|| rand.random_range(1..=12));
+            let connection_chance = rand.random_range(0.3..=1.0);
+            let geometry = *[
+                SectionGeometry::MINECRAFT,
+                SectionGeometry::new(32, 2.0).unwrap(),
+                SectionGeometry::new(8, 0.0).unwrap(),
+            ]
+            .choose(rand)
+            .unwrap();
+            let section_length = geometry.section_length() as f64;
+
+            // cameras are placed anywhere from below the bottom of the world to above the
+            // top
+            let bottom_block_y = (world_bottom_section_y as f64) * section_length;
+            let top_block_y = (world_top_section_y as f64 + 1.0) * section_length;
+            let camera_y = if vertical_render_distance.is_some() {
+                rand.random_range(-100_000.0..100_000.0)
+            } else {
+                rand.random_range((bottom_block_y - 150.0)..(top_block_y + 150.0))
+            };
+            let camera_pos = f64x3::from_xyz(
+                rand.random_range(-100_000.0..100_000.0),
+                camera_y,
+                rand.random_range(-100_000.0..100_000.0),
+            );
+
+            let max_search_distance = render_distance as f32 * section_length as f32;
+            let search_distance = rand.random_range(0.0..=max_search_distance);
+            let vertical_search_distance = if rand.random_bool(0.5) {
+                search_distance
+            } else {
+                rand.random_range(0.0..=max_search_distance)
+            };
+            let options = SearchOptions {
+                vertical_search_distance,
+                fog_shape: *FogShape::ALL.choose(rand).unwrap(),
+                bounds_extension: rand
+                    .random_bool(0.25)
+                    .then(|| rand.random_range(0.0..=section_length as f32)),
+                ..SearchOptions::new(search_distance)
+            };
+
+            Self {
+                render_distance,
+                world_bottom_section_y,
+                world_top_section_y,
+                vertical_render_distance,
+                geometry,
+                connection_chance,
+                frustum_planes: random_frustum_planes(rand),
+                camera_pos,
+                options,
+            }
+        }
+
+        fn build_graph(&self, rand: &mut StdRng) -> Graph {
+            let mut graph = match self.vertical_render_distance {
+                Some(vertical_render_distance) => Graph::try_new_cubic(
+                    self.render_distance,
+                    vertical_render_distance,
+                    self.geometry,
+                ),
+                None => Graph::try_new_with_geometry(
+                    self.render_distance,
+                    self.world_bottom_section_y,
+                    self.world_top_section_y,
+                    self.geometry,
+                ),
+            }
+            .unwrap();
+            let xz_length_sections = graph.coord_space.xz_length_tiles as i32
+                * LocalTileCoords::LENGTH_IN_SECTIONS as i32;
+            // cubic graphs wrap on the Y axis, so filling a single graph height covers
+            // every section
+            let y_sections = if graph.coord_space.wraps_y() {
+                0..=(graph.coord_space.y_length_tiles as i32
+                    * LocalTileCoords::LENGTH_IN_SECTIONS as i32
+                    - 1)
+            } else {
+                self.world_bottom_section_y as i32..=self.world_top_section_y as i32
+            };
+
+            for x in 0..xz_length_sections {
+                for y in y_sections.clone() {
+                    for z in 0..xz_length_sections {
+                        let mut visibility_data = 0;
+                        for bit_idx in ARRAY_TO_BIT_IDX {
+                            if rand.random_bool(self.connection_chance) {
+                                visibility_data |= 1 << bit_idx;
+                            }
+                        }
+                        graph.set_section(i32x3::from_xyz(x, y, z), visibility_data);
+                    }
+                }
+            }
+
+            graph
+        }
+
+        fn context(
+            &self,
+            graph: &Graph,
+            camera_pos: f64x3,
+            options: &SearchOptions,
+        ) -> GraphSearchContext {
+            GraphSearchContext::new(&graph.coord_space, self.frustum_planes, camera_pos, options)
+        }
+    }
+
+    /// Creates the planes of a perspective frustum with a random orientation
+    /// and field of view.
+    pub(super) fn random_frustum_planes(rand: &mut StdRng) -> [f32x4; 6] {
+        tile::frustum::perspective_planes(
+            rand.random_range(0.0..(FRAC_PI_2 * 4.0)),
+            rand.random_range(-FRAC_PI_2..FRAC_PI_2),
+            rand.random_range(0.4..2.4_f32),
+            rand.random_range(0.5..2.5_f32),
+            10_000.0,
+        )
+    }
+
+    fn section_bounding_box(
+        context: &GraphSearchContext,
+        section: [i32; 3],
+    ) -> RelativeBoundingBox {
+        let relative_pos = context.relative_section_pos(i32x3::from_array(section));
+
+        RelativeBoundingBox::new_extended(
+            relative_pos,
+            relative_pos + Simd::splat(context.geometry.section_length_f32()),
+            context.geometry.max_bounds_extension(),
+        )
+    }
+
+    fn assert_search_invariants(graph: &Graph, context: &GraphSearchContext) {
+        let mut tile_origins = HashSet::new();
+        for visible_tile in &graph.visible_tiles {
+            assert!(
+                tile_origins.insert(visible_tile.origin_section_coords),
+                "Tile at {:?} was added to the visible tiles more than once",
+                visible_tile.origin_section_coords,
+            );
+        }
+
+        let fog_distance = context.fog_distance;
+        let vertical_fog_distance = context.vertical_fog_distance;
+        for section in visible_sections(&graph.visible_tiles) {
+            assert!(
+                graph.coord_space.wraps_y()
+                    || !context.use_height_culling
+                    || (section[Y] >= graph.coord_space.world_bottom_section_y as i32
+                        && section[Y] <= graph.coord_space.world_top_section_y as i32),
+                "Section {section:?} is outside of the world"
+            );
+
+            let bb = section_bounding_box(context, section);
+            assert_eq!(
+                context.frustum.furthest_outside_plane(bb),
+                None,
+                "Section {section:?} is outside of the frustum"
+            );
+
+            let closest = Simd::splat(0.0).simd_max(bb.min).simd_min(bb.max);
+            assert!(
+                !context.use_fog_culling
+                    || context.fog_shape.distance(closest) <= fog_distance
+                    && closest[Y].abs() <= vertical_fog_distance,
+                "Section {section:?} is outside of the fog - Closest Point: {closest:?}, Fog Distance: {fog_distance}, Vertical Fog Distance: {vertical_fog_distance}"
+            );
+        }
+    }
+
+    #[test]
+    fn cull_properties_test() {
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+        let levels: Vec<_> = SimdLevel::supported().collect();
+
+        for search_idx in 0..SEARCH_COUNT {
+            let scenario = Scenario::random(&mut rand);
+            let mut graph = scenario.build_graph(&mut rand);
+            // the scenarios are spread over every level that the CPU supports
+            graph.set_simd_level(levels[search_idx % levels.len()]);
+
+            graph.cull(&scenario.context(&graph, scenario.camera_pos, &scenario.options));
+            assert_search_invariants(&graph, graph.last_search_context.as_ref().unwrap());
+            let occlusion_culled = visible_sections(&graph.visible_tiles);
+
+            let options = SearchOptions {
+                use_occlusion_culling: false,
+                ..scenario.options
+            };
+            graph.cull(&scenario.context(&graph, scenario.camera_pos, &options));
+            assert_search_invariants(&graph, graph.last_search_context.as_ref().unwrap());
+            let not_occlusion_culled = visible_sections(&graph.visible_tiles);
+
+            // turning off occlusion culling should only ever add sections
+            assert!(
+                occlusion_culled.is_subset(&not_occlusion_culled),
+                "Sections only visible with occlusion culling: {:?}",
+                occlusion_culled
+                    .difference(&not_occlusion_culled)
+                    .collect::<Vec<_>>()
+            );
+
+            // the same goes for turning off any of the other stages
+            for options in [
+                SearchOptions {
+                    use_angle_culling: false,
+                    ..scenario.options
+                },
+                SearchOptions {
+                    use_outward_direction_masks: false,
+                    ..scenario.options
+                },
+                SearchOptions {
+                    use_fog_culling: false,
+                    ..scenario.options
+                },
+                SearchOptions {
+                    use_height_culling: false,
+                    ..scenario.options
+                },
+            ] {
+                graph.cull(&scenario.context(&graph, scenario.camera_pos, &options));
+                assert_search_invariants(&graph, graph.last_search_context.as_ref().unwrap());
+                let stage_disabled = visible_sections(&graph.visible_tiles);
+
+                assert!(
+                    occlusion_culled.is_subset(&stage_disabled),
+                    "Sections only visible with all stages enabled: {:?}, Options: {options:?}",
+                    occlusion_culled
+                        .difference(&stage_disabled)
+                        .collect::<Vec<_>>()
+                );
+            }
+
+            // moving the camera by a multiple of the graph's size on the X and Z axes
+            // (and the Y axis in cubic chunks mode) lands on the same tiles, so it should
+            // see the same sections, offset by the same amount
+            let xz_length_sections = graph.coord_space.xz_length_tiles as i32
+                * LocalTileCoords::LENGTH_IN_SECTIONS as i32;
+            let y_wrap_offset_sections = if graph.coord_space.wraps_y() {
+                graph.coord_space.y_length_tiles as i32
+                    * LocalTileCoords::LENGTH_IN_SECTIONS as i32
+                    * rand.random_range(-4..=4)
+            } else {
+                0
+            };
+            let wrap_offset_sections = i32x3::from_xyz(
+                xz_length_sections * rand.random_range(-4..=4),
+                y_wrap_offset_sections,
+                xz_length_sections * rand.random_range(-4..=4),
+            );
+            let wrapped_camera_pos = scenario.camera_pos
+                + (wrap_offset_sections << graph.coord_space.geometry.section_shift() as i32)
+                    .cast::<f64>();
+
+            graph.cull(&scenario.context(&graph, wrapped_camera_pos, &scenario.options));
+            let wrapped: HashSet<[i32; 3]> = visible_sections(&graph.visible_tiles)
+                .into_iter()
+                .map(|section| (i32x3::from_array(section) - wrap_offset_sections).to_array())
+                .collect();
+
+            assert_eq!(occlusion_culled, wrapped);
+        }
+    }
+
+    #[test]
+    fn short_world_padding_tile_test() {
+        // graphs are always at least 2 tiles tall, so worlds of 8 sections or shorter
+        // have a padding tile above them that must never be visible
+        for world_height_sections in 1..=10 {
+            let world_bottom_section_y = -4;
+            let world_top_section_y = world_bottom_section_y + world_height_sections - 1;
+            let mut graph = Graph::new(2, world_bottom_section_y, world_top_section_y);
+
+            let xz_length_sections = graph.coord_space.xz_length_tiles as i32
+                * LocalTileCoords::LENGTH_IN_SECTIONS as i32;
+            for x in 0..xz_length_sections {
+                for y in world_bottom_section_y as i32..=world_top_section_y as i32 {
+                    for z in 0..xz_length_sections {
+                        graph.set_section(i32x3::from_xyz(x, y, z), !0);
+                    }
+                }
+            }
+
+            // a camera in the middle of the world and one above it, both seeing everything
+            let middle_block_y =
+                (world_bottom_section_y as f64 + world_height_sections as f64 / 2.0) * 16.0;
+            let top_block_y = (world_top_section_y as f64 + 1.0) * 16.0;
+            for camera_y in [middle_block_y, top_block_y + 20.0] {
+                let context = GraphSearchContext::new(
+                    &graph.coord_space,
+                    [f32x4::from_array([0.0, 0.0, 0.0, 1.0]); 6],
+                    f64x3::from_xyz(8.5, camera_y, 8.5),
+                    &SearchOptions::new(64.0),
+                );
+                graph.cull(&context);
+
+                let visible_sections = visible_sections(&graph.visible_tiles);
+                assert!(!visible_sections.is_empty());
+                for section in visible_sections {
+                    assert!(
+                        section[Y] >= world_bottom_section_y as i32
+                            && section[Y] <= world_top_section_y as i32,
+                        "Section {section:?} is outside of the world - World Height: {world_height_sections}, Camera Y: {camera_y}"
+                    );
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,332 @@
+use std::array;
+
+use super::coords::LocalTileCoords;
+use super::Graph;
+use crate::api::BoundingBox;
+use crate::ffi::FFITile;
+use crate::graph::context::GraphSearchContext;
+use crate::math::*;
+use crate::simd::prelude::*;
+
+// The number of boxes tested against the frustum at once
+const BATCH_LENGTH: usize = 8;
+
+impl Graph {
+    /// Writes whether each box is visible to the matching element of
+    /// `results`, using the results of the last search. A box is visible when
+    /// it's inside the frustum from the last search, and overlaps a section
+    /// that the search found visible. No boxes are visible before the first
+    /// search.
+    ///
+    /// Boxes with a minimum corner greater than their maximum corner, or with
+    /// coordinates that are NaN, are never visible.
+    pub fn test_boxes(
+        &self,
+        boxes: impl ExactSizeIterator<Item = BoundingBox>,
+        results: &mut [bool],
+    ) {
+        assert_eq!(
+            boxes.len(),
+            results.len(),
+            "the number of boxes doesn't match the number of results"
+        );
+
+        let Some(context) = &self.last_search_context else {
+            results.fill(false);
+            return;
+        };
+
+        with_level!(self.simd_level, L => {
+            // SAFETY: The graph's level is always supported by the CPU.
+            unsafe { L::dispatch(|| self.test_boxes_body::<L>(context, boxes, results)) }
+        });
+    }
+
+    #[inline(always)]
+    fn test_boxes_body<L: Level>(
+        &self,
+        context: &GraphSearchContext,
+        mut boxes: impl Iterator<Item = BoundingBox>,
+        results: &mut [bool],
+    ) {
+        let camera_pos = context.global_camera_pos();
+
+        for results_batch in results.chunks_mut(BATCH_LENGTH) {
+            // the lanes past the end of the last batch are left as empty boxes at the
+            // camera, and their results are ignored
+            let mut batch = [(f64x3::splat(0.0), f64x3::splat(0.0)); BATCH_LENGTH];
+            for (lane, bb) in batch.iter_mut().zip(&mut boxes) {
+                *lane = (Simd::from_array(bb.min), Simd::from_array(bb.max));
+            }
+
+            let relative_min = array::from_fn(|axis| {
+                Simd::from_array(batch.map(|(min, _)| (min[axis] - camera_pos[axis]) as f32))
+            });
+            let relative_max = array::from_fn(|axis| {
+                Simd::from_array(batch.map(|(_, max)| (max[axis] - camera_pos[axis]) as f32))
+            });
+            let outside = context
+                .frustum
+                .test_boxes_outside::<L>(relative_min, relative_max);
+
+            for (lane, result) in results_batch.iter_mut().enumerate() {
+                let (min, max) = batch[lane];
+
+                // written this way so NaN is rejected
+                *result = outside & (1 << lane) == 0
+                    && min.simd_le(max).all()
+                    && self.overlaps_visible_section(context, min, max);
+            }
+        }
+    }
+
+    fn overlaps_visible_section(
+        &self,
+        context: &GraphSearchContext,
+        min: f64x3,
+        max: f64x3,
+    ) -> bool {
+        let coord_space = &self.coord_space;
+        let section_shift = context.geometry.section_shift() as i32;
+
+        // Visible sections are never further from the camera than the length of the
+        // graph, so the range is clamped to keep huge boxes from taking forever. This
+        // also keeps the coordinates from overflowing when they're shifted.
+        let max_length_sections =
+            (coord_space.xz_length_tiles.max(coord_space.y_length_tiles) as i32 + 1)
+                * LocalTileCoords::LENGTH_IN_SECTIONS as i32;
+        let camera_section_coords = context.camera_section_coords();
+        let clamp_min = camera_section_coords - Simd::splat(max_length_sections);
+        let clamp_max = camera_section_coords + Simd::splat(max_length_sections);
+
+        let min_section =
+            (min.floor().cast::<i32>() >> section_shift).simd_clamp(clamp_min, clamp_max);
+        let max_section =
+            (max.floor().cast::<i32>() >> section_shift).simd_clamp(clamp_min, clamp_max);
+
+        // tiles are aligned to the bottom of the world on the Y axis
+        let grid_offset = i32x3::from_xyz(0, coord_space.world_bottom_section_y as i32, 0);
+        let min_tile = (min_section - grid_offset) >> 3;
+        let max_tile = (max_section - grid_offset) >> 3;
+
+        for tile_y in min_tile[Y]..=max_tile[Y] {
+            for tile_z in min_tile[Z]..=max_tile[Z] {
+                for tile_x in min_tile[X]..=max_tile[X] {
+                    let origin_section_coords =
+                        (i32x3::from_xyz(tile_x, tile_y, tile_z) << 3) + grid_offset;
+
+                    let Some(visible_tile) = self.visible_tile(origin_section_coords) else {
+                        continue;
+                    };
+
+                    // the bounds of the box within the tile, which are always between 0
+                    // and 7
+                    let min_in_tile = (min_section - origin_section_coords)
+                        .simd_max(Simd::splat(0))
+                        .cast::<u32>();
+                    let max_in_tile = (max_section - origin_section_coords)
+                        .simd_min(Simd::splat(7))
+                        .cast::<u32>();
+
+                    let row_mask = (0xFF_u64 >> (7 - max_in_tile[X])) & (0xFF << min_in_tile[X]);
+                    let mut layer_mask = 0;
+                    for z in min_in_tile[Z]..=max_in_tile[Z] {
+                        layer_mask |= row_mask << (z << 3);
+                    }
+
+                    let layers = &visible_tile.visible_sections
+                        [min_in_tile[Y] as usize..=max_in_tile[Y] as usize];
+                    if layers.iter().any(|&layer| layer & layer_mask != 0) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    // Returns the tile from the last search with the given origin, if any of its
+    // sections were visible.
+    fn visible_tile(&self, origin_section_coords: i32x3) -> Option<&FFITile> {
+        let (tile_coords, _) = self
+            .coord_space
+            .section_to_tile_coords(origin_section_coords);
+        if !self.coord_space.tile_coords_in_bounds(tile_coords) {
+            return None;
+        }
+
+        let index = self.coord_space.pack_index(tile_coords);
+        let visible_tile_index = self.visible_tile_lookup[index.to_usize()].checked_sub(1)?;
+        let visible_tile = &self.visible_tiles[visible_tile_index as usize];
+
+        // the graph wraps around, so the tile might be a different one that's stored in
+        // the same place
+        (visible_tile.origin_section_coords == origin_section_coords.to_array())
+            .then_some(visible_tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::prelude::*;
+
+    use super::*;
+    use crate::api::SearchOptions;
+    use crate::graph::context::CombinedTestResults;
+    use crate::graph::coords::RelativeBoundingBox;
+    use crate::graph::reference;
+    use crate::graph::test_world;
+    use crate::graph::tests::random_frustum_planes;
+    use crate::TESTS_RANDOM_SEED;
+
+    const SEARCH_DISTANCE: f32 = 96.0;
+
+    // The slow version of test_boxes, which checks every section that the box
+    // overlaps.
+    fn is_box_visible_slow(
+        context: &GraphSearchContext,
+        visible_sections: &HashSet<[i32; 3]>,
+        bb: BoundingBox,
+    ) -> bool {
+        let camera_pos = context.global_camera_pos();
+        let min = Simd::from_array(bb.min);
+        let max = Simd::from_array(bb.max);
+
+        let mut results = CombinedTestResults::ALL_INSIDE;
+        context.frustum.test_box::<crate::simd::level::Portable>(
+            RelativeBoundingBox::new((min - camera_pos).cast(), (max - camera_pos).cast()),
+            &mut results,
+        );
+        if results == CombinedTestResults::OUTSIDE {
+            return false;
+        }
+
+        let min_section = min.floor().cast::<i32>() >> 4;
+        let max_section = max.floor().cast::<i32>() >> 4;
+        for y in min_section[Y]..=max_section[Y] {
+            for z in min_section[Z]..=max_section[Z] {
+                for x in min_section[X]..=max_section[X] {
+                    if visible_sections.contains(&[x, y, z]) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    fn random_box(rand: &mut StdRng, camera_pos: [f64; 3]) -> BoundingBox {
+        let min = camera_pos.map(|coord| coord + rand.random_range(-120.0..120.0));
+        // mostly entity-sized boxes, with a few that cover many sections
+        let max_size = if rand.random_bool(0.9) { 4.0 } else { 80.0 };
+        let max = min.map(|coord| coord + rand.random_range(0.0..max_size));
+        BoundingBox { min, max }
+    }
+
+    #[test]
+    fn test_boxes_test() {
+        const ITERATIONS: u32 = 20;
+        const BOXES: usize = 1000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        // random sections are closed off, so occlusion culling hides some of the boxes
+        // that are inside the frustum
+        let mut graph = Graph::new(6, -4, 19);
+        test_world::load_world(&mut graph, &|block: i32x3| {
+            let section_coords = block >> 4;
+            (section_coords[X] * 7 + section_coords[Y] * 13 + section_coords[Z] * 5) % 4 == 0
+        });
+
+        let boxes = [BoundingBox {
+            min: [0.0; 3],
+            max: [1.0; 3],
+        }];
+        let mut results = [true];
+        graph.test_boxes(boxes.iter().copied(), &mut results);
+        assert_eq!(
+            results,
+            [false],
+            "boxes were visible before the first search"
+        );
+
+        let mut visible_count = 0;
+        for _ in 0..ITERATIONS {
+            let camera_pos = [
+                rand.random_range(-64.0..64.0),
+                rand.random_range(-64.0..200.0),
+                rand.random_range(-64.0..64.0),
+            ];
+            let context = GraphSearchContext::new(
+                &graph.coord_space,
+                random_frustum_planes(&mut rand),
+                Simd::from_array(camera_pos),
+                &SearchOptions::new(SEARCH_DISTANCE),
+            );
+            graph.cull(&context);
+
+            let visible_sections = reference::visible_sections(&graph.visible_tiles);
+            let boxes: Vec<_> = (0..BOXES)
+                .map(|_| random_box(&mut rand, camera_pos))
+                .collect();
+            let mut results = vec![false; BOXES];
+            graph.test_boxes(boxes.iter().copied(), &mut results);
+
+            visible_count += results.iter().filter(|&&result| result).count();
+            for (&bb, &result) in boxes.iter().zip(&results) {
+                assert_eq!(
+                    result,
+                    is_box_visible_slow(&context, &visible_sections, bb),
+                    "Box: {bb:?}, Camera Pos: {camera_pos:?}"
+                );
+            }
+        }
+
+        // make sure the boxes covered both cases
+        let total_count = ITERATIONS as usize * BOXES;
+        assert!(
+            visible_count > total_count / 100 && visible_count < total_count / 2,
+            "Visible Boxes: {visible_count}, Total Boxes: {total_count}"
+        );
+    }
+
+    #[test]
+    fn invalid_box_test() {
+        let mut graph = Graph::new(4, -4, 19);
+        test_world::load_world(&mut graph, &|_| false);
+
+        let context = GraphSearchContext::new(
+            &graph.coord_space,
+            [f32x4::from_array([0.0, 0.0, 0.0, 1.0]); 6],
+            Simd::from_xyz(8.5, 72.5, 8.5),
+            &SearchOptions::new(64.0),
+        );
+        graph.cull(&context);
+
+        let boxes = [
+            BoundingBox {
+                min: [0.0, 64.0, 0.0],
+                max: [1.0, 65.0, 1.0],
+            },
+            BoundingBox {
+                min: [1.0, 64.0, 0.0],
+                max: [0.0, 65.0, 1.0],
+            },
+            BoundingBox {
+                min: [f64::NAN, 64.0, 0.0],
+                max: [1.0, 65.0, 1.0],
+            },
+            // much larger than the graph, but still overlapping visible sections
+            BoundingBox {
+                min: [-1e12; 3],
+                max: [1e12; 3],
+            },
+        ];
+        let mut results = [false; 4];
+        graph.test_boxes(boxes.iter().copied(), &mut results);
+        assert_eq!(results, [true, false, false, true]);
+    }
+}
@@ -0,0 +1,574 @@
+//! A straightforward section-at-a-time port of Sodium's `OcclusionCuller`,
+//! used to check the results of the tile-based search.
+//!
+//! Based on this implementation:
+//! https://github.com/CaffeineMC/sodium-fabric/blob/dd25399c139004e863beb8a2195b9d80b847d95c/common/src/main/java/net/caffeinemc/mods/sodium/client/render/chunk/occlusion/OcclusionCuller.java
+
+use std::collections::{HashMap, HashSet};
+
+use super::context::{CameraArea, GraphSearchContext};
+use super::direction::{ALL_ORDINALS, DOWN, EAST, NORTH, SOUTH, UP, WEST};
+use super::visibility::ARRAY_TO_BIT_IDX;
+use super::{tile, Graph};
+use crate::bitset::BitSet;
+use crate::ffi::{self, FFITile};
+use crate::math::*;
+use crate::simd::prelude::*;
+
+const OFFSETS: [[i32; 3]; 6] = [
+    [0, -1, 0],
+    [0, 1, 0],
+    [0, 0, -1],
+    [0, 0, 1],
+    [-1, 0, 0],
+    [1, 0, 0],
+];
+
+const fn opposite(direction: u8) -> u8 {
+    direction ^ 1
+}
+
+/// The sections that were marked visible by only one of the two cullers.
+#[derive(Default, Debug)]
+pub struct CullDifferences {
+    pub only_graph: Vec<[i32; 3]>,
+    pub only_reference: Vec<[i32; 3]>,
+}
+
+impl CullDifferences {
+    pub fn is_empty(&self) -> bool {
+        self.only_graph.is_empty() && self.only_reference.is_empty()
+    }
+}
+
+/// Runs both the tile-based search and the reference search on the same graph
+/// and camera, and reports the sections they disagree on.
+pub fn compare(graph: &mut Graph, context: &GraphSearchContext) -> CullDifferences {
+    graph.cull(context);
+    let graph_visible = visible_sections(&graph.visible_tiles);
+    let reference_visible = find_visible(graph, context);
+
+    let mut differences = CullDifferences {
+        only_graph: graph_visible
+            .difference(&reference_visible)
+            .copied()
+            .collect(),
+        only_reference: reference_visible
+            .difference(&graph_visible)
+            .copied()
+            .collect(),
+    };
+    differences.only_graph.sort();
+    differences.only_reference.sort();
+    differences
+}
+
+/// Expands a list of visible tiles into the global coordinates of each visible
+/// section.
+pub fn visible_sections(visible_tiles: &[FFITile]) -> HashSet<[i32; 3]> {
+    let mut sections = HashSet::new();
+
+    for visible_tile in visible_tiles {
+        let origin = i32x3::from_array(visible_tile.origin_section_coords);
+        let tile_sections = ffi::sections_from_ffi(visible_tile.visible_sections);
+
+        for y in 0..8 {
+            for z in 0..8 {
+                for x in 0..8 {
+                    let coords_in_tile = u8x3::from_xyz(x, y, z);
+
+                    if tile::get_bit(&tile_sections, tile::section_index(coords_in_tile)) {
+                        sections.insert((origin + coords_in_tile.cast::<i32>()).to_array());
+                    }
+                }
+            }
+        }
+    }
+
+    sections
+}
+
+/// Performs a breadth-first search one section at a time, in the same way as
+/// Sodium's `OcclusionCuller`.
+pub fn find_visible(graph: &Graph, context: &GraphSearchContext) -> HashSet<[i32; 3]> {
+    let coord_space = &graph.coord_space;
+    let origin = context.camera_section_coords();
+    let search_distance = context.fog_distance;
+
+    let mut visible = HashSet::new();
+    let mut queue = SearchQueue::default();
+
+    match context.camera_area {
+        CameraArea::Inside => {
+            visible.insert(origin.to_array());
+
+            let outgoing = if context.use_occlusion_culling {
+                // Since the camera is located inside this section, there are no incoming
+                // directions. So we need to instead find any possible paths out of this
+                // section.
+                fold_outgoing_directions(graph.section_visibility_data(origin), ALL_ORDINALS)
+            } else {
+                ALL_ORDINALS
+            };
+
+            queue.visit_neighbors(graph, origin, outgoing);
+        }
+        CameraArea::Above | CameraArea::Below => {
+            let (height, direction) = if context.camera_area == CameraArea::Above {
+                (coord_space.world_top_section_y as i32, UP)
+            } else {
+                (coord_space.world_bottom_section_y as i32, DOWN)
+            };
+            let radius = (search_distance / context.geometry.section_length_f32()).floor() as i32;
+
+            for z in -radius..=radius {
+                for x in -radius..=radius {
+                    let coords = i32x3::from_xyz(origin[X] + x, height, origin[Z] + z);
+
+                    if is_within_frustum(context, coords) {
+                        queue.visit_node(coords.to_array(), direction);
+                    }
+                }
+            }
+        }
+    }
+
+    while !queue.write.is_empty() {
+        let read_queue = std::mem::take(&mut queue.write);
+
+        for coords in read_queue {
+            let section = i32x3::from_array(coords);
+
+            if !is_within_render_distance(context, section) || !is_within_frustum(context, section)
+            {
+                continue;
+            }
+
+            visible.insert(coords);
+
+            let mut connections = if context.use_occlusion_culling {
+                // When using occlusion culling, we can only traverse into neighbors for which
+                // there is a path of visibility through this section. This is determined by
+                // taking all the incoming paths to this section and creating a union of the
+                // outgoing paths from those.
+                fold_outgoing_directions(
+                    graph.section_visibility_data(section),
+                    queue.incoming_directions[&coords],
+                )
+            } else {
+                ALL_ORDINALS
+            };
+
+            // We can only traverse outwards from the center of the graph search, so mask
+            // off any invalid directions.
+            connections &= outward_directions(origin, section);
+
+            queue.visit_neighbors(graph, section, connections);
+        }
+    }
+
+    visible
+}
+
+#[derive(Default)]
+struct SearchQueue {
+    // the incoming directions of each section that has been enqueued during the search
+    incoming_directions: HashMap<[i32; 3], u8>,
+    write: Vec<[i32; 3]>,
+}
+
+impl SearchQueue {
+    fn visit_neighbors(&mut self, graph: &Graph, section: i32x3, outgoing: u8) {
+        for direction in DOWN..=EAST {
+            if !outgoing.get_bit(direction) {
+                continue;
+            }
+
+            let neighbor = section + i32x3::from_array(OFFSETS[direction as usize]);
+
+            // only traverse into neighbors which are actually present
+            if graph.section_in_world(neighbor) {
+                self.visit_node(neighbor.to_array(), opposite(direction));
+            }
+        }
+    }
+
+    fn visit_node(&mut self, coords: [i32; 3], incoming: u8) {
+        let incoming_directions = self.incoming_directions.entry(coords).or_insert_with(|| {
+            // this is the first time we are visiting this section during the search, so it
+            // needs to be enqueued
+            self.write.push(coords);
+            0
+        });
+        incoming_directions.set_bit(incoming);
+    }
+}
+
+fn fold_outgoing_directions(visibility_data: u64, incoming: u8) -> u8 {
+    let mut outgoing = 0;
+
+    for direction in DOWN..=EAST {
+        if incoming.get_bit(direction) {
+            outgoing |= (visibility_data >> (direction * 8)) as u8 & ALL_ORDINALS;
+        }
+    }
+
+    outgoing
+}
+
+fn outward_directions(origin: i32x3, section: i32x3) -> u8 {
+    let mut planes = 0;
+
+    planes.or_bit(WEST, section[X] <= origin[X]);
+    planes.or_bit(EAST, section[X] >= origin[X]);
+    planes.or_bit(DOWN, section[Y] <= origin[Y]);
+    planes.or_bit(UP, section[Y] >= origin[Y]);
+    planes.or_bit(NORTH, section[Z] <= origin[Z]);
+    planes.or_bit(SOUTH, section[Z] >= origin[Z]);
+
+    planes
+}
+
+fn is_within_render_distance(context: &GraphSearchContext, section: i32x3) -> bool {
+    let max_distance = context.fog_distance;
+    let max_vertical_distance = context.vertical_fog_distance;
+
+    let relative_pos = context.relative_section_pos(section);
+
+    // this is the closest point within the bounding box to the camera
+    let closest = Simd::splat(0.0)
+        .simd_max(relative_pos)
+        .simd_min(relative_pos + Simd::splat(context.geometry.section_length_f32()));
+
+    context.fog_shape.distance(closest) < max_distance && closest[Y].abs() < max_vertical_distance
+}
+
+fn is_within_frustum(context: &GraphSearchContext, section: i32x3) -> bool {
+    let bb = context.section_bounding_box(section);
+    context.frustum.furthest_outside_plane(bb).is_none()
+}
+
+impl Graph {
+    fn section_in_world(&self, section_coords: i32x3) -> bool {
+        let (tile_coords, _) = self.coord_space.section_to_tile_coords(section_coords);
+
+        if self.coord_space.wraps_y() {
+            return true;
+        }
+
+        self.coord_space.tile_coords_in_bounds(tile_coords)
+            && section_coords[Y] >= self.coord_space.world_bottom_section_y as i32
+            && section_coords[Y] <= self.coord_space.world_top_section_y as i32
+    }
+
+    /// Reconstructs the visibility data passed to `set_section` for a section,
+    /// with each connection set in both directions.
+    pub(super) fn section_visibility_data(&self, section_coords: i32x3) -> u64 {
+        let (tile_coords, section_coords_in_tile) =
+            self.coord_space.section_to_tile_coords(section_coords);
+        let tile = self.tiles.get(self.coord_space.pack_index(tile_coords));
+        let section_index = tile::section_index(section_coords_in_tile);
+
+        let mut visibility_data = 0_u64;
+        for (array_idx, &bit_idx) in ARRAY_TO_BIT_IDX.iter().enumerate() {
+            if tile::get_bit(&tile.connection_section_sets[array_idx], section_index) {
+                let mirrored_bit_idx = ((bit_idx & 0b111) << 3) | (bit_idx >> 3);
+                visibility_data.set_bit(bit_idx);
+                visibility_data.set_bit(mirrored_bit_idx);
+            }
+        }
+
+        visibility_data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::*;
+
+    use super::*;
+    use crate::api::SearchOptions;
+    use crate::graph::coords::SectionGeometry;
+    use crate::graph::tile::fog::FogShape;
+    use crate::TESTS_RANDOM_SEED;
+
+    // 90 degree FOV looking towards +Z, ordered as NEG_X, NEG_Y, NEG_Z, POS_X,
+    // POS_Y, POS_Z
+    const FRUSTUM_PLANES: [f32x4; 6] = [
+        f32x4::from_array([1.0, 0.0, 1.0, 0.0]),
+        f32x4::from_array([0.0, 1.0, 1.0, 0.0]),
+        f32x4::from_array([0.0, 0.0, 1.0, 0.05]),
+        f32x4::from_array([-1.0, 0.0, 1.0, 0.0]),
+        f32x4::from_array([0.0, -1.0, 1.0, 0.0]),
+        f32x4::from_array([0.0, 0.0, -1.0, 1000.0]),
+    ];
+
+    const SEARCH_DISTANCE: f32 = 200.0;
+
+    fn random_graph(rand: &mut StdRng, connection_chance: f64, geometry: SectionGeometry) -> Graph {
+        let mut graph = Graph::try_new_with_geometry(12, -4, 19, geometry).unwrap();
+        fill_random(&mut graph, rand, connection_chance);
+        graph
+    }
+
+    fn fill_random(graph: &mut Graph, rand: &mut StdRng, connection_chance: f64) {
+        for x in -16..16 {
+            for y in -4..20 {
+                for z in -16..16 {
+                    let mut visibility_data = 0;
+                    for bit_idx in ARRAY_TO_BIT_IDX {
+                        if rand.random_bool(connection_chance) {
+                            visibility_data |= 1 << bit_idx;
+                        }
+                    }
+                    graph.set_section(i32x3::from_xyz(x, y, z), visibility_data);
+                }
+            }
+        }
+    }
+
+    /// The tile-based search is allowed to be conservative around the edge of
+    /// the fog, so sections it marks visible on its own must be close to it.
+    fn assert_near_fog_edge(sections: &[[i32; 3]], context: &GraphSearchContext) {
+        let section_length = context.geometry.section_length_f32();
+        let search_distance = context.fog_distance;
+        let vertical_search_distance = context.vertical_fog_distance;
+        let is_near = |distance: f32, edge: f32| {
+            distance > edge - (section_length * 2.0) && distance < edge + (section_length * 2.0)
+        };
+
+        for &coords in sections {
+            let center = context.relative_section_pos(i32x3::from_array(coords))
+                + Simd::splat(section_length / 2.0);
+            let distance = context.fog_shape.distance(center);
+
+            assert!(
+                is_near(distance, search_distance)
+                    || is_near(center[Y].abs(), vertical_search_distance),
+                "section {coords:?} is only visible to the graph, but isn't near the fog edge",
+            );
+        }
+    }
+
+    #[test]
+    fn compare_open_world_test() {
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+        let mut graph = random_graph(&mut rand, 1.0, SectionGeometry::MINECRAFT);
+
+        for camera_pos in [
+            f64x3::from_xyz(8.5, 72.5, 8.5),
+            f64x3::from_xyz(-40.0, 310.0, 3.0),
+            f64x3::from_xyz(8.5, -100.0, 8.5),
+        ] {
+            for fog_shape in FogShape::ALL {
+                for use_occlusion_culling in [false, true] {
+                    let context = GraphSearchContext::new(
+                        &graph.coord_space,
+                        FRUSTUM_PLANES,
+                        camera_pos,
+                        &SearchOptions {
+                            fog_shape,
+                            use_occlusion_culling,
+                            ..SearchOptions::new(SEARCH_DISTANCE)
+                        },
+                    );
+                    let differences = compare(&mut graph, &context);
+
+                    assert!(
+                        differences.only_reference.is_empty(),
+                        "{fog_shape}: {:?}",
+                        differences.only_reference
+                    );
+                    assert_near_fog_edge(&differences.only_graph, &context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compare_vertical_search_distance_test() {
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+        let mut graph = random_graph(&mut rand, 1.0, SectionGeometry::MINECRAFT);
+        let camera_pos = f64x3::from_xyz(8.5, 72.5, 8.5);
+
+        // vertical search distances both below and above the horizontal one
+        for vertical_search_distance in [8.0, 20.0, 50.0, SEARCH_DISTANCE * 2.0] {
+            for fog_shape in FogShape::ALL {
+                let context = GraphSearchContext::new(
+                    &graph.coord_space,
+                    FRUSTUM_PLANES,
+                    camera_pos,
+                    &SearchOptions {
+                        fog_shape,
+                        vertical_search_distance,
+                        ..SearchOptions::new(SEARCH_DISTANCE)
+                    },
+                );
+                let differences = compare(&mut graph, &context);
+
+                assert!(
+                    differences.only_reference.is_empty(),
+                    "{fog_shape}, {vertical_search_distance}: {:?}",
+                    differences.only_reference
+                );
+                assert_near_fog_edge(&differences.only_graph, &context);
+            }
+        }
+    }
+
+    #[test]
+    fn compare_large_sections_test() {
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+        let geometry = SectionGeometry::new(32, 2.0).unwrap();
+        let mut graph = random_graph(&mut rand, 1.0, geometry);
+
+        // the same positions as compare_open_world_test, scaled to the larger sections
+        for camera_pos in [
+            f64x3::from_xyz(17.0, 145.0, 17.0),
+            f64x3::from_xyz(-80.0, 620.0, 6.0),
+            f64x3::from_xyz(17.0, -200.0, 17.0),
+        ] {
+            for use_occlusion_culling in [false, true] {
+                let context = GraphSearchContext::new(
+                    &graph.coord_space,
+                    FRUSTUM_PLANES,
+                    camera_pos,
+                    &SearchOptions {
+                        use_occlusion_culling,
+                        ..SearchOptions::new(SEARCH_DISTANCE * 2.0)
+                    },
+                );
+                let differences = compare(&mut graph, &context);
+
+                assert!(
+                    differences.only_reference.is_empty(),
+                    "{:?}",
+                    differences.only_reference
+                );
+                assert_near_fog_edge(&differences.only_graph, &context);
+            }
+        }
+    }
+
+    #[test]
+    fn compare_cubic_test() {
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        // the vertical render distance is both below and above the search distance
+        for vertical_render_distance in [6, 16] {
+            let mut graph =
+                Graph::try_new_cubic(12, vertical_render_distance, SectionGeometry::MINECRAFT)
+                    .unwrap();
+            fill_random(&mut graph, &mut rand, 1.0);
+
+            // cubic graphs don't have a top or bottom, so the camera can be anywhere
+            for camera_pos in [
+                f64x3::from_xyz(8.5, 72.5, 8.5),
+                f64x3::from_xyz(-40.0, 3100.0, 3.0),
+                f64x3::from_xyz(8.5, -12000.0, 8.5),
+            ] {
+                for use_occlusion_culling in [false, true] {
+                    let context = GraphSearchContext::new(
+                        &graph.coord_space,
+                        FRUSTUM_PLANES,
+                        camera_pos,
+                        &SearchOptions {
+                            use_occlusion_culling,
+                            ..SearchOptions::new(SEARCH_DISTANCE)
+                        },
+                    );
+                    let differences = compare(&mut graph, &context);
+
+                    assert!(
+                        differences.only_reference.is_empty(),
+                        "{:?}",
+                        differences.only_reference
+                    );
+                    assert_near_fog_edge(&differences.only_graph, &context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compare_random_world_test() {
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for connection_chance in [0.4, 0.7, 0.9] {
+            let mut graph = random_graph(&mut rand, connection_chance, SectionGeometry::MINECRAFT);
+            let camera_pos = f64x3::from_xyz(8.5, 72.5, 8.5);
+
+            // without occlusion culling, the connectivity of the sections shouldn't
+            // matter
+            let context = GraphSearchContext::new(
+                &graph.coord_space,
+                FRUSTUM_PLANES,
+                camera_pos,
+                &SearchOptions {
+                    use_occlusion_culling: false,
+                    ..SearchOptions::new(SEARCH_DISTANCE)
+                },
+            );
+            let differences = compare(&mut graph, &context);
+
+            assert!(differences.only_reference.is_empty());
+            assert_near_fog_edge(&differences.only_graph, &context);
+
+            // the reference search doesn't do angle culling, so it's free to see sections
+            // through paths that the tile-based search rejects as too steep
+            let context = GraphSearchContext::new(
+                &graph.coord_space,
+                FRUSTUM_PLANES,
+                camera_pos,
+                &SearchOptions::new(SEARCH_DISTANCE),
+            );
+            let differences = compare(&mut graph, &context);
+
+            assert_near_fog_edge(&differences.only_graph, &context);
+
+            // so every section that only the reference found should be visible to the
+            // tile-based search once angle culling is disabled
+            let context = GraphSearchContext::new(
+                &graph.coord_space,
+                FRUSTUM_PLANES,
+                camera_pos,
+                &SearchOptions {
+                    use_angle_culling: false,
+                    ..SearchOptions::new(SEARCH_DISTANCE)
+                },
+            );
+            graph.cull(&context);
+            let without_angle_culling = visible_sections(&graph.visible_tiles);
+
+            let unexplained: Vec<_> = differences
+                .only_reference
+                .iter()
+                .filter(|section| !without_angle_culling.contains(*section))
+                .collect();
+            assert!(
+                unexplained.is_empty(),
+                "Sections only the reference found, even without angle culling: {unexplained:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn section_visibility_data_test() {
+        let mut graph = Graph::new(4, -4, 19);
+        let coords = i32x3::from_xyz(3, 7, -2);
+
+        // DOWN <-> UP, and NORTH <-> EAST
+        let visibility_data = (1 << (DOWN * 8 + UP)) | (1 << (NORTH * 8 + EAST));
+        graph.set_section(coords, visibility_data);
+
+        let mirrored = (1 << (UP * 8 + DOWN)) | (1 << (EAST * 8 + NORTH));
+        assert_eq!(
+            graph.section_visibility_data(coords),
+            visibility_data | mirrored
+        );
+        assert_eq!(
+            fold_outgoing_directions(visibility_data | mirrored, 1 << UP),
+            1 << DOWN
+        );
+    }
+}
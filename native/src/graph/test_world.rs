@@ -0,0 +1,328 @@
+//! Builds graphs from simple block-level worlds, so tests don't have to set up
+//! section visibility data by hand.
+//!
+//! The connectivity of each section is calculated by flood filling the
+//! transparent blocks in the section, in the same way as Minecraft's
+//! `VisGraph`.
+
+use super::coords::LocalTileCoords;
+use super::direction::{ALL_ORDINALS, DOWN, EAST, NORTH, SOUTH, UP, WEST};
+use super::visibility::connect_faces;
+use super::Graph;
+use crate::bitset::BitSet;
+use crate::math::*;
+use crate::simd::prelude::*;
+
+const SECTION_LENGTH: i32 = 16;
+const SECTION_VOLUME: usize = 16 * 16 * 16;
+
+// Minecraft's VisGraph treats any section with less than this many opaque
+// blocks as fully connected
+const MIN_OPAQUE_BLOCKS: usize = 256;
+
+/// A world made of opaque and transparent blocks.
+pub trait BlockWorld {
+    fn is_opaque(&self, block_coords: i32x3) -> bool;
+}
+
+impl<F: Fn(i32x3) -> bool> BlockWorld for F {
+    fn is_opaque(&self, block_coords: i32x3) -> bool {
+        self(block_coords)
+    }
+}
+
+/// Terrain where every block below the height returned for its column is
+/// opaque.
+pub fn heightmap_terrain(height: impl Fn(i32, i32) -> i32) -> impl BlockWorld {
+    move |block: i32x3| block[Y] < height(block[X], block[Z])
+}
+
+/// Solid ground up to `surface_y`, with caves carved out wherever the noise is
+/// above `threshold`. `threshold` should be between 0.0 and 1.0, where lower
+/// values create more caves.
+pub fn noise_caves(seed: u64, surface_y: i32, threshold: f32) -> impl BlockWorld {
+    move |block: i32x3| block[Y] < surface_y && value_noise(seed, block) <= threshold
+}
+
+/// Solid ground everywhere, except for an empty room covering the blocks from
+/// `min` to `max`, inclusive.
+pub fn box_room(min: i32x3, max: i32x3) -> impl BlockWorld {
+    move |block: i32x3| !(block.simd_ge(min).all() && block.simd_le(max).all())
+}
+
+/// Solid ground everywhere, except for a tunnel with a square cross-section
+/// that runs along `axis` through the whole world. `center` is the block at the
+/// center of the tunnel's cross-section.
+pub fn tunnel(center: i32x3, axis: usize, half_width: i32) -> impl BlockWorld {
+    move |block: i32x3| {
+        let mut distance = (block - center).abs();
+        distance[axis] = 0;
+        distance.simd_gt(Simd::splat(half_width)).any()
+    }
+}
+
+/// Sets every section in the graph to the connectivity calculated from the
+/// world.
+pub fn load_world(graph: &mut Graph, world: &impl BlockWorld) {
+    let xz_length_sections =
+        graph.coord_space.xz_length_tiles as i32 * LocalTileCoords::LENGTH_IN_SECTIONS as i32;
+    let bottom_section_y = graph.coord_space.world_bottom_section_y as i32;
+    let top_section_y = graph.coord_space.world_top_section_y as i32;
+
+    for y in bottom_section_y..=top_section_y {
+        for z in 0..xz_length_sections {
+            for x in 0..xz_length_sections {
+                let section_coords = i32x3::from_xyz(x, y, z);
+                graph.set_section(section_coords, section_visibility(world, section_coords));
+            }
+        }
+    }
+}
+
+/// Calculates the visibility data for a section, in the format expected by
+/// `Graph::set_section`. Each connection is set in both directions, so the
+/// bits used by `ARRAY_TO_BIT_IDX` are always present.
+pub fn section_visibility(world: &impl BlockWorld, section_coords: i32x3) -> u64 {
+    let origin = section_coords * Simd::splat(SECTION_LENGTH);
+
+    let mut opaque = [false; SECTION_VOLUME];
+    let mut opaque_count = 0;
+    for (index, is_opaque) in opaque.iter_mut().enumerate() {
+        *is_opaque = world.is_opaque(origin + unpack_block_index(index));
+        opaque_count += *is_opaque as usize;
+    }
+
+    if opaque_count < MIN_OPAQUE_BLOCKS {
+        return connect_faces(ALL_ORDINALS);
+    } else if opaque_count == SECTION_VOLUME {
+        return 0;
+    }
+
+    let mut visited = opaque;
+    let mut stack = Vec::new();
+    let mut visibility_data = 0;
+
+    for start_index in 0..SECTION_VOLUME {
+        if visited[start_index] {
+            continue;
+        }
+
+        visited[start_index] = true;
+        stack.push(start_index);
+        let mut faces = 0_u8;
+
+        while let Some(index) = stack.pop() {
+            let block = unpack_block_index(index);
+            faces |= touched_faces(block);
+
+            for axis in [X, Y, Z] {
+                for step in [-1, 1] {
+                    let mut neighbor = block;
+                    neighbor[axis] += step;
+
+                    if neighbor[axis] < 0 || neighbor[axis] >= SECTION_LENGTH {
+                        continue;
+                    }
+
+                    let neighbor_index = pack_block_index(neighbor);
+                    if !visited[neighbor_index] {
+                        visited[neighbor_index] = true;
+                        stack.push(neighbor_index);
+                    }
+                }
+            }
+        }
+
+        visibility_data |= connect_faces(faces);
+    }
+
+    visibility_data
+}
+
+fn touched_faces(block: i32x3) -> u8 {
+    let max = SECTION_LENGTH - 1;
+    let mut faces = 0_u8;
+
+    faces.or_bit(WEST, block[X] == 0);
+    faces.or_bit(EAST, block[X] == max);
+    faces.or_bit(DOWN, block[Y] == 0);
+    faces.or_bit(UP, block[Y] == max);
+    faces.or_bit(NORTH, block[Z] == 0);
+    faces.or_bit(SOUTH, block[Z] == max);
+
+    faces
+}
+
+// blocks are packed in YZX order, like Minecraft's VisGraph
+fn pack_block_index(block: i32x3) -> usize {
+    ((block[Y] * SECTION_LENGTH + block[Z]) * SECTION_LENGTH + block[X]) as usize
+}
+
+fn unpack_block_index(index: usize) -> i32x3 {
+    let index = index as i32;
+    i32x3::from_xyz(
+        index % SECTION_LENGTH,
+        index / (SECTION_LENGTH * SECTION_LENGTH),
+        (index / SECTION_LENGTH) % SECTION_LENGTH,
+    )
+}
+
+/// 3D value noise with a lattice spacing of 8 blocks, in the range 0.0 to 1.0.
+fn value_noise(seed: u64, block: i32x3) -> f32 {
+    // shifting right by 3 is like a floored divide by 8
+    let cell = block >> 3;
+    let t = ((block & Simd::splat(0b111)).cast::<f32>() + Simd::splat(0.5)) / Simd::splat(8.0);
+    // smoothstep, so the noise doesn't have visible creases at the lattice points
+    let t = t * t * (Simd::splat(3.0) - Simd::splat(2.0) * t);
+
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let corner = |x: i32, y: i32, z: i32| lattice_value(seed, cell + i32x3::from_xyz(x, y, z));
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), t[X]);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), t[X]);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), t[X]);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), t[X]);
+
+    lerp(lerp(x00, x10, t[Y]), lerp(x01, x11, t[Y]), t[Z])
+}
+
+fn lattice_value(seed: u64, cell: i32x3) -> f32 {
+    let mut hash = seed;
+    for coord in cell.to_array() {
+        hash = (hash ^ coord as u32 as u64).wrapping_mul(0x9e3779b97f4a7c15);
+        hash ^= hash >> 32;
+    }
+
+    (hash >> 40) as f32 / (1_u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::api::SearchOptions;
+    use crate::graph::context::GraphSearchContext;
+    use crate::graph::reference::visible_sections;
+    use crate::graph::visibility::ARRAY_TO_BIT_IDX;
+    use crate::TESTS_RANDOM_SEED;
+
+    const fn connection(from: u8, to: u8) -> u64 {
+        (1 << (from * 8 + to)) | (1 << (to * 8 + from))
+    }
+
+    #[test]
+    fn section_visibility_test() {
+        let origin = i32x3::splat(0);
+
+        assert_eq!(section_visibility(&|_| true, origin), 0);
+        assert_eq!(
+            section_visibility(&|_| false, origin),
+            connect_faces(ALL_ORDINALS)
+        );
+        // too few opaque blocks to block anything, even though the column splits the
+        // section
+        let thin_wall = |block: i32x3| block[X] == 8 && block[Z] < 15;
+        assert_eq!(
+            section_visibility(&thin_wall, origin),
+            connect_faces(ALL_ORDINALS)
+        );
+
+        // ground in the bottom half of the section
+        let ground = heightmap_terrain(|_, _| 8);
+        assert_eq!(
+            section_visibility(&ground, origin),
+            connect_faces(ALL_ORDINALS & !(1 << DOWN))
+        );
+        assert_eq!(
+            section_visibility(&ground, i32x3::from_xyz(0, 1, 0)),
+            connect_faces(ALL_ORDINALS)
+        );
+        assert_eq!(section_visibility(&ground, i32x3::from_xyz(0, -1, 0)), 0);
+
+        let x_tunnel = tunnel(i32x3::from_xyz(5, 7, 9), X, 1);
+        assert_eq!(
+            section_visibility(&x_tunnel, origin),
+            connection(WEST, EAST)
+        );
+        // the tunnel doesn't pass through the neighboring section on the Z axis
+        assert_eq!(section_visibility(&x_tunnel, i32x3::from_xyz(0, 0, 1)), 0);
+
+        // an L-shaped tunnel, which connects the bottom to the south, while a separate
+        // pocket of air only touches the west face
+        let bend = |block: i32x3| {
+            let vertical = block[X] == 4 && block[Z] == 4 && block[Y] <= 4;
+            let horizontal = block[X] == 4 && block[Y] == 4 && block[Z] >= 4;
+            let pocket = block[X] == 0 && block[Y] == 10 && block[Z] == 10;
+            !(vertical || horizontal || pocket)
+        };
+        assert_eq!(section_visibility(&bend, origin), connection(DOWN, SOUTH));
+    }
+
+    #[test]
+    fn bit_layout_test() {
+        let caves = noise_caves(TESTS_RANDOM_SEED, 64, 0.55);
+        let mut graph = Graph::new(2, 0, 4);
+        load_world(&mut graph, &caves);
+
+        let valid_bits = ARRAY_TO_BIT_IDX
+            .iter()
+            .fold(0_u64, |bits, &bit_idx| bits | (1 << bit_idx));
+        let mirrored_valid_bits = ARRAY_TO_BIT_IDX.iter().fold(0_u64, |bits, &bit_idx| {
+            bits | (1 << (((bit_idx & 0b111) << 3) | (bit_idx >> 3)))
+        });
+
+        let mut connection_counts = HashSet::new();
+        for y in 0..=4 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let coords = i32x3::from_xyz(x, y, z);
+                    let visibility_data = section_visibility(&caves, coords);
+
+                    assert_eq!(visibility_data & !(valid_bits | mirrored_valid_bits), 0);
+                    assert_eq!(graph.section_visibility_data(coords), visibility_data);
+                    connection_counts.insert(visibility_data.count_ones());
+                }
+            }
+        }
+
+        // the caves should produce a variety of partially connected sections
+        assert!(connection_counts.len() > 5, "{connection_counts:?}");
+    }
+
+    #[test]
+    fn box_room_cull_test() {
+        // a room covering sections (3, 1, 3) to (4, 2, 5), which is closed off by the
+        // surrounding rock
+        let room = box_room(i32x3::from_xyz(48, 16, 48), i32x3::from_xyz(79, 47, 95));
+        let mut graph = Graph::new(2, 0, 7);
+        load_world(&mut graph, &room);
+
+        // planes that contain everything
+        let frustum_planes = [f32x4::from_array([0.0, 0.0, 0.0, 1.0]); 6];
+        let context = GraphSearchContext::new(
+            &graph.coord_space,
+            frustum_planes,
+            f64x3::from_xyz(60.5, 30.5, 70.5),
+            &SearchOptions::new(32.0),
+        );
+        graph.cull(&context);
+
+        // the room is visible, along with the walls around it
+        let mut expected_sections = HashSet::new();
+        for y in 0..=3 {
+            for z in 2..=6 {
+                for x in 2..=5 {
+                    let outside_room_axes = (!(3..=4).contains(&x)) as u8
+                        + (!(1..=2).contains(&y)) as u8
+                        + (!(3..=5).contains(&z)) as u8;
+                    if outside_room_axes <= 1 {
+                        expected_sections.insert([x, y, z]);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(visible_sections(&graph.visible_tiles), expected_sections);
+    }
+}
@@ -1,21 +1,42 @@
 use super::*;
+use crate::graph::coords::SectionGeometry;
 
 // Code size is bloated when this gets inlined
 #[inline(never)]
-pub fn gen_visibility_masks(relative_tile_pos: f32x3) -> [u8x64; 3] {
-    let offsets = relative_tile_pos.mul_add_fast(Simd::splat(1.0 / 16.0), Simd::splat(0.5));
+pub fn gen_visibility_masks<L: Level>(
+    relative_tile_pos: f32x3,
+    geometry: SectionGeometry,
+) -> [u8x64; 3] {
+    // SAFETY: Kernels are only instantiated with levels that the CPU supports.
+    unsafe { L::dispatch(|| gen_visibility_masks_body::<L>(relative_tile_pos, geometry)) }
+}
+
+#[inline(always)]
+fn gen_visibility_masks_body<L: Level>(
+    relative_tile_pos: f32x3,
+    geometry: SectionGeometry,
+) -> [u8x64; 3] {
+    // the offsets are in sections, and section lengths are powers of 2, so the
+    // reciprocal is exact
+    let offsets = relative_tile_pos.mul_add_fast::<L>(
+        Simd::splat(1.0 / geometry.section_length_f32()),
+        Simd::splat(0.5),
+    );
 
-    let (xy_mask_compressed, yx_mask_compressed) = gen_compressed_mask_pair(offsets[X], offsets[Y]);
-    let xy_mask = expand_xy_mask(xy_mask_compressed);
-    let yx_mask = expand_xy_mask(yx_mask_compressed);
+    let (xy_mask_compressed, yx_mask_compressed) =
+        gen_compressed_mask_pair::<L>(offsets[X], offsets[Y]);
+    let xy_mask = expand_xy_mask::<L>(xy_mask_compressed);
+    let yx_mask = expand_xy_mask::<L>(yx_mask_compressed);
 
-    let (xz_mask_compressed, zx_mask_compressed) = gen_compressed_mask_pair(offsets[X], offsets[Z]);
-    let xz_mask = expand_xz_mask(xz_mask_compressed);
-    let zx_mask = expand_xz_mask(zx_mask_compressed);
+    let (xz_mask_compressed, zx_mask_compressed) =
+        gen_compressed_mask_pair::<L>(offsets[X], offsets[Z]);
+    let xz_mask = expand_xz_mask::<L>(xz_mask_compressed);
+    let zx_mask = expand_xz_mask::<L>(zx_mask_compressed);
 
-    let (zy_mask_compressed, yz_mask_compressed) = gen_compressed_mask_pair(offsets[Z], offsets[Y]);
-    let zy_mask = expand_zy_mask(zy_mask_compressed);
-    let yz_mask = expand_zy_mask(yz_mask_compressed);
+    let (zy_mask_compressed, yz_mask_compressed) =
+        gen_compressed_mask_pair::<L>(offsets[Z], offsets[Y]);
+    let zy_mask = expand_zy_mask::<L>(zy_mask_compressed);
+    let yz_mask = expand_zy_mask::<L>(yz_mask_compressed);
 
     let x_mask = yx_mask & zx_mask;
     let y_mask = xy_mask & zy_mask;
@@ -24,7 +45,8 @@ pub fn gen_visibility_masks(relative_tile_pos: f32x3) -> [u8x64; 3] {
     [x_mask, y_mask, z_mask]
 }
 
-fn gen_compressed_mask_pair(offset_1: f32, offset_2: f32) -> (u8x8, u8x8) {
+#[inline(always)]
+fn gen_compressed_mask_pair<L: Level>(offset_1: f32, offset_2: f32) -> (u8x8, u8x8) {
     let neg_x_offset = Simd::splat(-offset_1);
     let y_offset = Simd::splat(offset_2);
     let ys = Simd::from_array([0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
@@ -36,7 +58,7 @@ fn gen_compressed_mask_pair(offset_1: f32, offset_2: f32) -> (u8x8, u8x8) {
     let upper_bound = line_1.simd_max_fast(line_2);
 
     let (lower_bound_ceil_clamped, upper_bound_floor, lower_bound_mask, upper_bound_mask) =
-        rasterize_rows(lower_bound, upper_bound);
+        rasterize_rows::<L>(lower_bound, upper_bound);
     let combined_mask = lower_bound_mask & upper_bound_mask;
 
     // Get lowest set bit of the mask if the bound falls on an integer.
@@ -54,42 +76,70 @@ fn gen_compressed_mask_pair(offset_1: f32, offset_2: f32) -> (u8x8, u8x8) {
     (combined_mask.cast::<u8>(), reverse_mask.cast::<u8>())
 }
 
+// Only the NEON path depends on the level.
+#[cfg_attr(
+    not(target_arch = "aarch64"),
+    allow(clippy::extra_unused_type_parameters)
+)]
 #[rustfmt::skip]
-fn expand_xy_mask(compressed_mask: u8x8) -> u8x64 {
-    simd_swizzle!(
-        compressed_mask,
-        [
-            0, 0, 0, 0, 0, 0, 0, 0,
-            1, 1, 1, 1, 1, 1, 1, 1,
-            2, 2, 2, 2, 2, 2, 2, 2,
-            3, 3, 3, 3, 3, 3, 3, 3,
-            4, 4, 4, 4, 4, 4, 4, 4,
-            5, 5, 5, 5, 5, 5, 5, 5,
-            6, 6, 6, 6, 6, 6, 6, 6,
-            7, 7, 7, 7, 7, 7, 7, 7,
-        ]
-    )
+#[inline(always)]
+fn expand_xy_mask<L: Level>(compressed_mask: u8x8) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::expand_xy_mask(compressed_mask) },
+        _ => simd_swizzle!(
+            compressed_mask,
+            [
+                0, 0, 0, 0, 0, 0, 0, 0,
+                1, 1, 1, 1, 1, 1, 1, 1,
+                2, 2, 2, 2, 2, 2, 2, 2,
+                3, 3, 3, 3, 3, 3, 3, 3,
+                4, 4, 4, 4, 4, 4, 4, 4,
+                5, 5, 5, 5, 5, 5, 5, 5,
+                6, 6, 6, 6, 6, 6, 6, 6,
+                7, 7, 7, 7, 7, 7, 7, 7,
+            ]
+        ),
+    }
 }
 
+// Only the NEON path depends on the level.
+#[cfg_attr(
+    not(target_arch = "aarch64"),
+    allow(clippy::extra_unused_type_parameters)
+)]
 #[rustfmt::skip]
-fn expand_xz_mask(compressed_mask: u8x8) -> u8x64 {
-    simd_swizzle!(
-        compressed_mask,
-        [
-            0, 1, 2, 3, 4, 5, 6, 7,
-            0, 1, 2, 3, 4, 5, 6, 7,
-            0, 1, 2, 3, 4, 5, 6, 7,
-            0, 1, 2, 3, 4, 5, 6, 7,
-            0, 1, 2, 3, 4, 5, 6, 7,
-            0, 1, 2, 3, 4, 5, 6, 7,
-            0, 1, 2, 3, 4, 5, 6, 7,
-            0, 1, 2, 3, 4, 5, 6, 7,
-        ]
-    )
+#[inline(always)]
+fn expand_xz_mask<L: Level>(compressed_mask: u8x8) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::expand_xz_mask(compressed_mask) },
+        _ => simd_swizzle!(
+            compressed_mask,
+            [
+                0, 1, 2, 3, 4, 5, 6, 7,
+                0, 1, 2, 3, 4, 5, 6, 7,
+                0, 1, 2, 3, 4, 5, 6, 7,
+                0, 1, 2, 3, 4, 5, 6, 7,
+                0, 1, 2, 3, 4, 5, 6, 7,
+                0, 1, 2, 3, 4, 5, 6, 7,
+                0, 1, 2, 3, 4, 5, 6, 7,
+                0, 1, 2, 3, 4, 5, 6, 7,
+            ]
+        ),
+    }
 }
 
+// Only the NEON path depends on the level.
+#[cfg_attr(
+    not(target_arch = "aarch64"),
+    allow(clippy::extra_unused_type_parameters)
+)]
 #[rustfmt::skip]
-fn expand_zy_mask(compressed_mask: u8x8) -> u8x64 {
+#[inline(always)]
+fn expand_zy_mask<L: Level>(compressed_mask: u8x8) -> u8x64 {
     const MASK: u8x64 = Simd::from_array([
         0b1, 0b10, 0b100, 0b1000, 0b10000, 0b100000, 0b1000000, 0b10000000,
         0b1, 0b10, 0b100, 0b1000, 0b10000, 0b100000, 0b1000000, 0b10000000,
@@ -100,19 +150,24 @@ fn expand_zy_mask(compressed_mask: u8x8) -> u8x64 {
         0b1, 0b10, 0b100, 0b1000, 0b10000, 0b100000, 0b1000000, 0b10000000,
         0b1, 0b10, 0b100, 0b1000, 0b10000, 0b100000, 0b1000000, 0b10000000,
     ]);
-    (simd_swizzle!(
-        compressed_mask,
-        [
-            0, 0, 0, 0, 0, 0, 0, 0,
-            1, 1, 1, 1, 1, 1, 1, 1,
-            2, 2, 2, 2, 2, 2, 2, 2,
-            3, 3, 3, 3, 3, 3, 3, 3,
-            4, 4, 4, 4, 4, 4, 4, 4,
-            5, 5, 5, 5, 5, 5, 5, 5,
-            6, 6, 6, 6, 6, 6, 6, 6,
-            7, 7, 7, 7, 7, 7, 7, 7,
-        ]
-    ) & MASK).simd_eq(MASK).to_int().cast()
+    match () {
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::expand_zy_mask(compressed_mask) },
+        _ => (simd_swizzle!(
+            compressed_mask,
+            [
+                0, 0, 0, 0, 0, 0, 0, 0,
+                1, 1, 1, 1, 1, 1, 1, 1,
+                2, 2, 2, 2, 2, 2, 2, 2,
+                3, 3, 3, 3, 3, 3, 3, 3,
+                4, 4, 4, 4, 4, 4, 4, 4,
+                5, 5, 5, 5, 5, 5, 5, 5,
+                6, 6, 6, 6, 6, 6, 6, 6,
+                7, 7, 7, 7, 7, 7, 7, 7,
+            ]
+        ) & MASK).simd_eq(MASK).to_int().cast(),
+    }
 }
 
 #[cfg(test)]
@@ -120,9 +175,10 @@ mod tests {
     use rand::prelude::*;
 
     use super::*;
+    use crate::simd::level::Portable;
     use crate::TESTS_RANDOM_SEED;
 
-    fn gen_visibility_masks_slow(relative_tile_pos: f32x3) -> [u8x64; 3] {
+    fn gen_visibility_masks_slow(relative_tile_pos: f32x3, section_length: f32) -> [u8x64; 3] {
         let mut x_mask = SECTIONS_FILLED;
         let mut y_mask = SECTIONS_FILLED;
         let mut z_mask = SECTIONS_FILLED;
@@ -133,8 +189,8 @@ mod tests {
                     let section_coords = Simd::from_xyz(x, y, z);
                     let section_index = section_index(section_coords);
                     let relative_section_center = relative_tile_pos
-                        + Simd::splat(8.0)
-                        + (section_coords.cast::<f32>() * Simd::splat(16.0));
+                        + Simd::splat(section_length / 2.0)
+                        + (section_coords.cast::<f32>() * Simd::splat(section_length));
 
                     let distances = relative_section_center.abs();
 
@@ -158,40 +214,91 @@ mod tests {
     fn angle_visibility_masks_test() {
         const ITERATIONS: u32 = 10000;
         let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+        let levels: Vec<_> = SimdLevel::supported().collect();
+
+        let geometries = [
+            SectionGeometry::MINECRAFT,
+            SectionGeometry::new(32, 2.0).unwrap(),
+            SectionGeometry::new(4, 0.0).unwrap(),
+        ];
+
+        for iteration in 0..ITERATIONS {
+            let geometry = geometries[iteration as usize % geometries.len()];
+            // keep the positions the same number of sections away
+            let scale = geometry.section_length_f32() / 16.0;
 
-        for _ in 0..ITERATIONS {
             let relative_tile_pos = Simd::from_xyz(
                 // (rand.random_range(-20_i8..20_i8) as f32) * 16.0,
                 // (rand.random_range(-20_i8..20_i8) as f32) * 16.0,
                 // (rand.random_range(-20_i8..20_i8) as f32) * 16.0,
-                rand.random_range(-300.0_f32..300.0_f32),
-                rand.random_range(-300.0_f32..300.0_f32),
-                rand.random_range(-300.0_f32..300.0_f32),
+                rand.random_range(-300.0_f32..300.0_f32) * scale,
+                rand.random_range(-300.0_f32..300.0_f32) * scale,
+                rand.random_range(-300.0_f32..300.0_f32) * scale,
             );
 
-            let test_masks = gen_visibility_masks(relative_tile_pos);
-            let sane_masks = gen_visibility_masks_slow(relative_tile_pos);
-
-            if sane_masks != test_masks {
-                println!("Sane X Mask");
-                print_tile(&sane_masks[X]);
-                println!();
-                println!("Sane Y Mask");
-                print_tile(&sane_masks[Y]);
-                println!();
-                println!("Sane Z Mask");
-                print_tile(&sane_masks[Z]);
-                println!();
-                println!("Test X Mask");
-                print_tile(&test_masks[X]);
-                println!();
-                println!("Test Y Mask");
-                print_tile(&test_masks[X]);
-                println!();
-                println!("Test Z Mask");
-                print_tile(&test_masks[X]);
-                println!();
-                panic!("sane != test, Relative Tile Coords: {relative_tile_pos:?}");
+            let sane_masks =
+                gen_visibility_masks_slow(relative_tile_pos, geometry.section_length_f32());
+
+            for &level in &levels {
+                let test_masks =
+                    with_level!(level, L => gen_visibility_masks::<L>(relative_tile_pos, geometry));
+
+                if sane_masks != test_masks {
+                    println!("Sane X Mask");
+                    print_tile(&sane_masks[X]);
+                    println!();
+                    println!("Sane Y Mask");
+                    print_tile(&sane_masks[Y]);
+                    println!();
+                    println!("Sane Z Mask");
+                    print_tile(&sane_masks[Z]);
+                    println!();
+                    println!("Test X Mask");
+                    print_tile(&test_masks[X]);
+                    println!();
+                    println!("Test Y Mask");
+                    print_tile(&test_masks[X]);
+                    println!();
+                    println!("Test Z Mask");
+                    print_tile(&test_masks[X]);
+                    println!();
+                    panic!(
+                        "sane != test, Level: {level}, Geometry: {geometry:?}, Relative Tile Coords: {relative_tile_pos:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    fn expand_masks<L: Level>(compressed_mask: u8x8) -> [u8x64; 3] {
+        [
+            expand_xy_mask::<L>(compressed_mask),
+            expand_xz_mask::<L>(compressed_mask),
+            expand_zy_mask::<L>(compressed_mask),
+        ]
+    }
+
+    #[test]
+    fn expand_masks_level_equivalence_test() {
+        const ITERATIONS: u32 = 10000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for _ in 0..ITERATIONS {
+            let mut compressed_mask = u8x8::splat(0);
+            rand.fill_bytes(compressed_mask.as_mut_array());
+
+            let portable_masks = expand_masks::<Portable>(compressed_mask);
+
+            for level in SimdLevel::supported() {
+                // SAFETY: The level is supported by the CPU.
+                let level_masks = with_level!(level, L => unsafe {
+                    L::dispatch(|| expand_masks::<L>(compressed_mask))
+                });
+
+                assert_eq!(
+                    portable_masks, level_masks,
+                    "portable != {level}, Compressed Mask: {compressed_mask:?}"
+                );
             }
         }
     }
@@ -0,0 +1,91 @@
+//! AVX-512 versions of the tile operations that LLVM can't turn into single
+//! instructions on its own. A tile is exactly one 512-bit register, so each of
+//! these replaces a pair of 256-bit operations and the shuffles that stitch
+//! them back together.
+//!
+//! Each function here must return bit-identical results to the generic code
+//! it replaces. Callers pick them with [`Level::AVX512`].
+
+use std::arch::x86_64::*;
+
+use super::*;
+
+// Each 64-bit lane holds one X-Z plane of the tile, with the rows on the Z axis
+// in byte order. Moving on the Z axis is a shift within those lanes, and moving
+// on the Y axis moves whole lanes, which valignq does across the register.
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn shift_neg_z(sections: u8x64) -> u8x64 {
+    _mm512_srli_epi64::<8>(sections.into()).into()
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn shift_pos_z(sections: u8x64) -> u8x64 {
+    _mm512_slli_epi64::<8>(sections.into()).into()
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn shift_neg_y(sections: u8x64) -> u8x64 {
+    _mm512_alignr_epi64::<1>(_mm512_setzero_si512(), sections.into()).into()
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn shift_pos_y(sections: u8x64) -> u8x64 {
+    _mm512_alignr_epi64::<7>(sections.into(), _mm512_setzero_si512()).into()
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn edge_neg_to_pos_z(sections: u8x64) -> u8x64 {
+    _mm512_slli_epi64::<56>(sections.into()).into()
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn edge_pos_to_neg_z(sections: u8x64) -> u8x64 {
+    _mm512_srli_epi64::<56>(sections.into()).into()
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn edge_neg_to_pos_y(sections: u8x64) -> u8x64 {
+    _mm512_alignr_epi64::<1>(sections.into(), _mm512_setzero_si512()).into()
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn edge_pos_to_neg_y(sections: u8x64) -> u8x64 {
+    _mm512_alignr_epi64::<7>(_mm512_setzero_si512(), sections.into()).into()
+}
+
+/// ORs `added` into `sections`, and returns whether that set any new bits.
+/// This tests the bits that were added rather than comparing the whole
+/// register, which saves the reduction of a 64 lane compare.
+#[target_feature(enable = "avx512f")]
+#[inline]
+pub fn or_changed(sections: &mut u8x64, added: u8x64) -> bool {
+    let previous: __m512i = (*sections).into();
+    let added: __m512i = added.into();
+
+    *sections = _mm512_or_si512(previous, added).into();
+
+    let new_bits = _mm512_andnot_si512(previous, added);
+    _mm512_test_epi64_mask(new_bits, new_bits) != 0
+}
+
+/// Turns each intercept into a mask with a lookup table, the same way as the
+/// AVX2 version in `frustum`, but in a single shuffle. Negative intercepts
+/// become 0 because vpshufb writes a 0 for any index with the top bit set.
+#[target_feature(enable = "avx512f,avx512bw")]
+#[inline]
+pub fn intercepts_to_masks(tile_x_intercepts: u8x64) -> u8x64 {
+    let mask_table = _mm512_set1_epi64(i64::from_le_bytes([
+        0b1, 0b11, 0b111, 0b1111, 0b11111, 0b111111, 0b1111111, 0b11111111,
+    ]));
+
+    _mm512_shuffle_epi8(mask_table, tile_x_intercepts.into()).into()
+}
@@ -1,9 +1,69 @@
+use std::fmt;
+
 use super::*;
-use crate::graph::coords::RelativeBoundingBox;
+use crate::graph::coords::{RelativeBoundingBox, SectionGeometry};
+
+/// The shape of the area around the camera that sections have to be inside of
+/// to be visible. Every shape is also limited to the vertical fog distance
+/// above and below the camera.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FogShape {
+    /// A cylinder around the camera's Y axis, like Sodium's fog.
+    #[default]
+    Cylindrical = 0,
+    /// A sphere around the camera, like vanilla's environmental fog.
+    Spherical = 1,
+    /// A square around the camera on the X and Z axes, like the square of
+    /// chunks that the server keeps loaded.
+    Square = 2,
+}
+
+impl FogShape {
+    pub const ALL: [Self; 3] = [Self::Cylindrical, Self::Spherical, Self::Square];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|shape| *shape as u8 == value)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cylindrical => "cylindrical",
+            Self::Spherical => "spherical",
+            Self::Square => "square",
+        }
+    }
+
+    /// Returns the distance from the camera to a point relative to it, which
+    /// is compared against the fog distance. The vertical fog distance is
+    /// checked separately.
+    pub fn distance(self, point: f32x3) -> f32 {
+        match self {
+            Self::Cylindrical => point[X].hypot(point[Z]),
+            Self::Spherical => (point * point).reduce_sum().sqrt(),
+            Self::Square => point[X].abs().max(point[Z].abs()),
+        }
+    }
+}
+
+impl fmt::Display for FogShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
 
 // based on this algorithm
 // https://github.com/CaffeineMC/sodium-fabric/blob/dd25399c139004e863beb8a2195b9d80b847d95c/common/src/main/java/net/caffeinemc/mods/sodium/client/render/chunk/occlusion/OcclusionCuller.java#L153
-pub fn test_box(bb: RelativeBoundingBox, fog_distance: f32, results: &mut CombinedTestResults) {
+//
+// the vertical fog distance is separate so the graph can have a shorter vertical
+// search distance in cubic chunks mode.
+pub fn test_box(
+    bb: RelativeBoundingBox,
+    fog_shape: FogShape,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    results: &mut CombinedTestResults,
+) {
     // find closest to (0,0) because the bounding box coordinates are relative to
     // the camera
     let closest_in_chunk = f32x3::splat(0.0).simd_clamp_fast(bb.min, bb.max);
@@ -16,16 +76,22 @@ pub fn test_box(bb: RelativeBoundingBox, fog_distance: f32, results: &mut Combin
     let xz_distances = simd_swizzle!(closest_in_chunk, furthest_in_chunk, [X, Z, X + 3, Z + 3]);
     let xz_distances_squared = xz_distances * xz_distances;
 
-    // add Xs and Zs
-    let combined_distances_squared =
-        simd_swizzle!(xz_distances_squared, [0, 2]) + simd_swizzle!(xz_distances_squared, [1, 3]);
-
     let y_distances = simd_swizzle!(closest_in_chunk, furthest_in_chunk, [Y, Y + 3]);
     let y_distances_abs = y_distances.abs();
 
+    let x_distances_squared = simd_swizzle!(xz_distances_squared, [0, 2]);
+    let z_distances_squared = simd_swizzle!(xz_distances_squared, [1, 3]);
+    let combined_distances_squared = match fog_shape {
+        FogShape::Cylindrical => x_distances_squared + z_distances_squared,
+        FogShape::Spherical => {
+            x_distances_squared + z_distances_squared + (y_distances * y_distances)
+        }
+        FogShape::Square => x_distances_squared.simd_max(z_distances_squared),
+    };
+
     let outside_fog_mask = combined_distances_squared
         .simd_ge(Simd::splat(fog_distance * fog_distance))
-        | y_distances_abs.simd_ge(Simd::splat(fog_distance));
+        | y_distances_abs.simd_ge(Simd::splat(vertical_fog_distance));
 
     if outside_fog_mask.test(0) {
         // early exit
@@ -36,55 +102,248 @@ pub fn test_box(bb: RelativeBoundingBox, fog_distance: f32, results: &mut Combin
     results.set_partial::<{ CombinedTestResults::FOG_BIT }>(outside_fog_mask.test(1));
 }
 
-pub fn voxelize_cylinder(relative_tile_pos: f32x3, fog_distance: f32) -> u8x64 {
-    const BB_EXTENSION: f32 = RelativeBoundingBox::BOUNDING_BOX_EXTENSION;
-    const BB_EXTENSION_SCALED: f32 = BB_EXTENSION / 16.0;
+/// Returns the sections of the tile that are inside of the fog.
+pub fn voxelize<L: Level>(
+    fog_shape: FogShape,
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    match fog_shape {
+        FogShape::Cylindrical => voxelize_cylinder::<L>(
+            relative_tile_pos,
+            fog_distance,
+            vertical_fog_distance,
+            geometry,
+        ),
+        FogShape::Spherical => voxelize_sphere::<L>(
+            relative_tile_pos,
+            fog_distance,
+            vertical_fog_distance,
+            geometry,
+        ),
+        FogShape::Square => voxelize_square::<L>(
+            relative_tile_pos,
+            fog_distance,
+            vertical_fog_distance,
+            geometry,
+        ),
+    }
+}
+
+pub fn voxelize_cylinder<L: Level>(
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    // SAFETY: Kernels are only instantiated with levels that the CPU supports.
+    unsafe {
+        L::dispatch(|| {
+            voxelize_cylinder_body::<L>(
+                relative_tile_pos,
+                fog_distance,
+                vertical_fog_distance,
+                geometry,
+            )
+        })
+    }
+}
 
-    let section_zs = (f32x8::from_array([0.0, 16.0, 32.0, 48.0, 64.0, 80.0, 96.0, 112.0])
-        - Simd::splat(BB_EXTENSION))
-        + Simd::splat(relative_tile_pos[Z]);
+pub fn voxelize_sphere<L: Level>(
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    // SAFETY: Kernels are only instantiated with levels that the CPU supports.
+    unsafe {
+        L::dispatch(|| {
+            voxelize_sphere_body::<L>(
+                relative_tile_pos,
+                fog_distance,
+                vertical_fog_distance,
+                geometry,
+            )
+        })
+    }
+}
+
+pub fn voxelize_square<L: Level>(
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    // SAFETY: Kernels are only instantiated with levels that the CPU supports.
+    unsafe {
+        L::dispatch(|| {
+            voxelize_square_body::<L>(
+                relative_tile_pos,
+                fog_distance,
+                vertical_fog_distance,
+                geometry,
+            )
+        })
+    }
+}
 
-    let distance_zs = Simd::splat(0.0)
-        .simd_max_fast(section_zs)
-        .simd_min_fast(section_zs + Simd::splat(16.0 + (BB_EXTENSION * 2.0)));
+#[inline(always)]
+fn voxelize_cylinder_body<L: Level>(
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    let distance_zs = closest_row_distances(relative_tile_pos[Z], geometry);
 
     let c_squared =
-        distance_zs.mul_add_fast(-distance_zs, Simd::splat(fog_distance * fog_distance));
+        distance_zs.mul_add_fast::<L>(-distance_zs, Simd::splat(fog_distance * fog_distance));
     let c = c_squared.sqrt();
 
-    let upper_bound = (c - Simd::splat(relative_tile_pos[X]))
-        .mul_add_fast(Simd::splat(1.0 / 16.0), Simd::splat(BB_EXTENSION_SCALED));
-    let lower_bound = (c + Simd::splat(relative_tile_pos[X])).mul_add_fast(
-        Simd::splat(-1.0 / 16.0),
-        Simd::splat(-1.0 - BB_EXTENSION_SCALED),
+    let zx_mask = u64x8::splat(rasterize_layer::<L>(
+        relative_tile_pos[X],
+        c,
+        c_squared,
+        geometry,
+    ))
+    .to_ne_bytes();
+
+    zx_mask & vertical_mask::<L>(relative_tile_pos[Y], vertical_fog_distance, geometry)
+}
+
+#[inline(always)]
+fn voxelize_sphere_body<L: Level>(
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    let distance_zs = closest_row_distances(relative_tile_pos[Z], geometry);
+    let distance_ys = closest_row_distances(relative_tile_pos[Y], geometry);
+    let y_c_squared =
+        distance_ys.mul_add_fast::<L>(-distance_ys, Simd::splat(fog_distance * fog_distance));
+
+    // each Y layer is a slice of the sphere, which is a circle with a smaller radius
+    let mut layers = [0_u64; 8];
+    for (y, layer) in layers.iter_mut().enumerate() {
+        let c_squared = distance_zs.mul_add_fast::<L>(-distance_zs, Simd::splat(y_c_squared[y]));
+        let c = c_squared.sqrt();
+
+        *layer = rasterize_layer::<L>(relative_tile_pos[X], c, c_squared, geometry);
+    }
+    let zx_mask = u64x8::from_array(layers).to_ne_bytes();
+
+    zx_mask & vertical_mask::<L>(relative_tile_pos[Y], vertical_fog_distance, geometry)
+}
+
+#[inline(always)]
+fn voxelize_square_body<L: Level>(
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    let distance_zs = closest_row_distances(relative_tile_pos[Z], geometry);
+
+    // the rows are only used for their sign here, because every row that's inside
+    // of the square has the same width
+    let c_squared =
+        distance_zs.mul_add_fast::<L>(-distance_zs, Simd::splat(fog_distance * fog_distance));
+    let c = Simd::splat(fog_distance);
+
+    let zx_mask = u64x8::splat(rasterize_layer::<L>(
+        relative_tile_pos[X],
+        c,
+        c_squared,
+        geometry,
+    ))
+    .to_ne_bytes();
+
+    zx_mask & vertical_mask::<L>(relative_tile_pos[Y], vertical_fog_distance, geometry)
+}
+
+/// Returns the distance from the camera to the closest point of each row of
+/// extended sections along an axis, given the position of the tile on that
+/// axis.
+#[inline(always)]
+fn closest_row_distances(relative_tile_pos: f32, geometry: SectionGeometry) -> f32x8 {
+    let section_length = geometry.section_length_f32();
+    let bb_extension = geometry.padded_bounds_extension();
+
+    let section_positions = ((f32x8::from_array([0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0])
+        * Simd::splat(section_length))
+        - Simd::splat(bb_extension))
+        + Simd::splat(relative_tile_pos);
+
+    Simd::splat(0.0)
+        .simd_max_fast(section_positions)
+        .simd_min_fast(section_positions + Simd::splat(section_length + (bb_extension * 2.0)))
+}
+
+/// Rasterizes a single Y layer of the tile, where each Z row is visible within
+/// `c` blocks of the camera on the X axis. Rows where `c_squared` is negative
+/// are entirely outside of the fog.
+#[inline(always)]
+fn rasterize_layer<L: Level>(
+    relative_tile_pos_x: f32,
+    c: f32x8,
+    c_squared: f32x8,
+    geometry: SectionGeometry,
+) -> u64 {
+    let section_length = geometry.section_length_f32();
+    // section lengths are powers of 2, so this is exact
+    let section_length_recip = 1.0 / section_length;
+    let bb_extension_scaled = geometry.padded_bounds_extension() * section_length_recip;
+
+    let upper_bound = (c - Simd::splat(relative_tile_pos_x)).mul_add_fast::<L>(
+        Simd::splat(section_length_recip),
+        Simd::splat(bb_extension_scaled),
+    );
+    let lower_bound = (c + Simd::splat(relative_tile_pos_x)).mul_add_fast::<L>(
+        Simd::splat(-section_length_recip),
+        Simd::splat(-1.0 - bb_extension_scaled),
     );
 
-    let (.., lower_bound_mask, upper_bound_mask) = rasterize_rows(lower_bound, upper_bound);
+    let (.., lower_bound_mask, upper_bound_mask) = rasterize_rows::<L>(lower_bound, upper_bound);
     let out_of_bounds_mask = c_squared.is_sign_positive_fast().to_int().cast::<u32>();
     let combined_mask = (lower_bound_mask & upper_bound_mask & out_of_bounds_mask).cast::<u8>();
 
-    let zx_mask = u64x8::splat(u64::from_ne_bytes(combined_mask.to_array())).to_ne_bytes();
+    u64::from_ne_bytes(combined_mask.to_array())
+}
+
+/// Returns the Y layers of the tile that are within the vertical fog distance,
+/// expanded to every section in each layer.
+#[inline(always)]
+fn vertical_mask<L: Level>(
+    relative_tile_pos_y: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    // section lengths are powers of 2, so this is exact
+    let section_length_recip = 1.0 / geometry.section_length_f32();
+    let bb_extension_scaled = geometry.padded_bounds_extension() * section_length_recip;
 
     let y_lower_bound_mask = (0xFF_u32
         << unsafe {
-            (-fog_distance - relative_tile_pos[Y])
-                .mul_add_fast(1.0 / 16.0, -BB_EXTENSION_SCALED)
+            (-vertical_fog_distance - relative_tile_pos_y)
+                .mul_add_fast::<L>(section_length_recip, -bb_extension_scaled)
                 .floor()
                 .to_int_unchecked::<i32>()
                 .clamp(0, 8)
         }) as u8;
     let y_upper_bound_mask = (0xFF_u32
         >> unsafe {
-            8 - (fog_distance - relative_tile_pos[Y])
-                .mul_add_fast(1.0 / 16.0, BB_EXTENSION_SCALED)
+            8 - (vertical_fog_distance - relative_tile_pos_y)
+                .mul_add_fast::<L>(section_length_recip, bb_extension_scaled)
                 .ceil()
                 .to_int_unchecked::<i32>()
                 .clamp(0, 8)
         }) as u8;
     let y_mask = y_lower_bound_mask & y_upper_bound_mask;
-    let y_mask_expanded = mask64x8::from_bitmask(y_mask as u64).to_int().to_ne_bytes();
 
-    zx_mask & y_mask_expanded
+    mask64x8::from_bitmask(y_mask as u64).to_int().to_ne_bytes()
 }
 
 #[cfg(test)]
@@ -92,11 +351,24 @@ mod tests {
     use rand::prelude::*;
 
     use super::*;
+    use crate::simd::level::Baseline;
     use crate::TESTS_RANDOM_SEED;
 
-    fn voxelize_cylinder_slow(
+    fn is_inside_fog(
+        fog_shape: FogShape,
+        point: f32x3,
+        fog_distance: f32,
+        vertical_fog_distance: f32,
+    ) -> bool {
+        fog_shape.distance(point) < fog_distance && point[Y].abs() < vertical_fog_distance
+    }
+
+    fn voxelize_slow(
+        fog_shape: FogShape,
         relative_tile_pos: f32x3,
         fog_distance: f32,
+        vertical_fog_distance: f32,
+        section_length: f32,
         bounds_extension: f32,
     ) -> u8x64 {
         let mut visible_sections = SECTIONS_EMPTY;
@@ -109,21 +381,24 @@ mod tests {
 
                     let relative_section_pos = section_coords
                         .cast::<f32>()
-                        .mul_add_fast(Simd::splat(16.0), relative_tile_pos);
+                        .mul_add_fast::<Baseline>(Simd::splat(section_length), relative_tile_pos);
                     let bb = RelativeBoundingBox::new(
                         relative_section_pos - Simd::splat(bounds_extension),
-                        relative_section_pos + Simd::splat(16.0 + bounds_extension),
+                        relative_section_pos + Simd::splat(section_length + bounds_extension),
                     );
 
                     let closest_in_chunk = f32x3::splat(0.0).simd_max(bb.min).simd_min(bb.max);
 
-                    let distances_squared = closest_in_chunk * closest_in_chunk;
-
-                    let inside_fog = (distances_squared[X] + distances_squared[Z])
-                        < (fog_distance * fog_distance)
-                        && closest_in_chunk[Y].abs() < fog_distance;
-
-                    modify_bit(&mut visible_sections, section_index, inside_fog);
+                    modify_bit(
+                        &mut visible_sections,
+                        section_index,
+                        is_inside_fog(
+                            fog_shape,
+                            closest_in_chunk,
+                            fog_distance,
+                            vertical_fog_distance,
+                        ),
+                    );
                 }
             }
         }
@@ -131,42 +406,123 @@ mod tests {
         visible_sections
     }
 
+    #[test]
+    fn test_box_test() {
+        const ITERATIONS: u32 = 10000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for iteration in 0..ITERATIONS {
+            let fog_shape = FogShape::ALL[iteration as usize % FogShape::ALL.len()];
+
+            let min = Simd::from_xyz(
+                rand.random_range(-600.0_f32..600.0_f32),
+                rand.random_range(-600.0_f32..600.0_f32),
+                rand.random_range(-600.0_f32..600.0_f32),
+            );
+            let bb = RelativeBoundingBox::new(min, min + Simd::splat(128.0));
+            let fog_distance = rand.random_range(0.0_f32..500.0_f32);
+            let vertical_fog_distance = if rand.random_bool(0.5) {
+                fog_distance
+            } else {
+                rand.random_range(0.0..=fog_distance)
+            };
+
+            let mut results = CombinedTestResults::ALL_INSIDE;
+            test_box(
+                bb,
+                fog_shape,
+                fog_distance,
+                vertical_fog_distance,
+                &mut results,
+            );
+
+            // the closest and furthest points of a box are always on its corners, or at
+            // the camera when it's inside of the box
+            let closest = f32x3::splat(0.0).simd_max(bb.min).simd_min(bb.max);
+            let furthest = bb.min.abs().simd_gt(bb.max.abs()).select(bb.min, bb.max);
+
+            let closest_inside =
+                is_inside_fog(fog_shape, closest, fog_distance, vertical_fog_distance);
+            let furthest_inside =
+                is_inside_fog(fog_shape, furthest, fog_distance, vertical_fog_distance);
+
+            let expected = if !closest_inside {
+                CombinedTestResults::OUTSIDE
+            } else {
+                let mut expected = CombinedTestResults::ALL_INSIDE;
+                expected.set_partial::<{ CombinedTestResults::FOG_BIT }>(!furthest_inside);
+                expected
+            };
+
+            assert_eq!(
+                results, expected,
+                "Fog Shape: {fog_shape}, Bounding Box Min: {min:?}, Fog Distance: {fog_distance}, Vertical Fog Distance: {vertical_fog_distance}",
+            );
+        }
+    }
+
     #[test]
     fn fog_voxelization_test() {
         const ITERATIONS: u32 = 10000;
         let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+        let levels: Vec<_> = SimdLevel::supported().collect();
+
+        let geometries = [
+            SectionGeometry::MINECRAFT,
+            SectionGeometry::new(32, 2.0).unwrap(),
+            SectionGeometry::new(4, 0.0).unwrap(),
+        ];
+
+        for iteration in 0..ITERATIONS {
+            let geometry = geometries[iteration as usize % geometries.len()];
+            let fog_shape =
+                FogShape::ALL[(iteration as usize / geometries.len()) % FogShape::ALL.len()];
+            // keep the positions and distances the same number of sections away
+            let scale = geometry.section_length_f32() / 16.0;
 
-        for _ in 0..ITERATIONS {
             let relative_tile_pos = Simd::from_xyz(
-                // (rand.random_range(-20_i8..20_i8) as f32) * 16.0,
-                // (rand.random_range(-20_i8..20_i8) as f32) * 16.0,
-                // (rand.random_range(-20_i8..20_i8) as f32) * 16.0,
-                rand.random_range(-3000.0_f32..3000.0_f32),
-                rand.random_range(-3000.0_f32..3000.0_f32),
-                rand.random_range(-3000.0_f32..3000.0_f32),
+                rand.random_range(-3000.0_f32..3000.0_f32) * scale,
+                rand.random_range(-3000.0_f32..3000.0_f32) * scale,
+                rand.random_range(-3000.0_f32..3000.0_f32) * scale,
             );
-            let fog_distance = rand.random_range(0.0_f32..900.0_f32);
+            let fog_distance = rand.random_range(0.0_f32..900.0_f32) * scale;
+            // half of the iterations use a shorter vertical distance, like in cubic chunks
+            // mode
+            let vertical_fog_distance = if rand.random_bool(0.5) {
+                fog_distance
+            } else {
+                rand.random_range(0.0..=fog_distance)
+            };
 
-            let sane_visible_sections_min = voxelize_cylinder_slow(
+            let sane_visible_sections_min = voxelize_slow(
+                fog_shape,
                 relative_tile_pos,
                 fog_distance,
-                RelativeBoundingBox::BOUNDING_BOX_EXTENSION_MIN,
+                vertical_fog_distance,
+                geometry.section_length_f32(),
+                geometry.bounds_extension(),
             );
-            let sane_visible_sections_max = voxelize_cylinder_slow(
+            let sane_visible_sections_max = voxelize_slow(
+                fog_shape,
                 relative_tile_pos,
                 fog_distance,
-                RelativeBoundingBox::BOUNDING_BOX_EXTENSION_MAX,
+                vertical_fog_distance,
+                geometry.section_length_f32(),
+                geometry.max_bounds_extension(),
             );
-            let test_visible_sections = voxelize_cylinder(relative_tile_pos, fog_distance);
-
-            if !test_minimum_maximum(
-                &sane_visible_sections_min,
-                &sane_visible_sections_max,
-                &test_visible_sections,
-            ) {
-                panic!(
-                    "Test results don't fit in sane bounds. Relative Tile Coords: {relative_tile_pos:?}, Fog Distance: {fog_distance}",
-                );
+
+            for &level in &levels {
+                let test_visible_sections = with_level!(level, L => voxelize::<L>(fog_shape, relative_tile_pos, fog_distance, vertical_fog_distance, geometry));
+
+                if !test_minimum_maximum(
+                    &sane_visible_sections_min,
+                    &sane_visible_sections_max,
+                    &test_visible_sections,
+                ) {
+                    panic!(
+                        "Test results don't fit in sane bounds. Level: {level}, Fog Shape: {fog_shape}, Geometry: {geometry:?}, Relative Tile Coords: {relative_tile_pos:?}, Fog Distance: {fog_distance}, Vertical Fog Distance: {vertical_fog_distance}",
+                    );
+                }
             }
         }
     }
@@ -1,13 +1,15 @@
 use std::array;
 
 use super::*;
-use crate::graph::coords::RelativeBoundingBox;
+use crate::graph::coords::{RelativeBoundingBox, SectionGeometry};
 
 /// When using this, it is expected that coordinates are relative to the camera
 /// rather than the world origin.
+#[derive(Clone)]
 pub struct Frustum {
     planes: [f32x4; DIRECTION_COUNT],
     axis_bb_offsets: [f32x3; DIRECTION_COUNT],
+    section_length: f32,
 
     // Plane data ordered component-wise rather than plane-wise. The contents are transposed from
     // the normal plane array
@@ -15,9 +17,10 @@ pub struct Frustum {
 }
 
 impl Frustum {
-    pub fn new(planes: [f32x4; 6]) -> Self {
+    pub fn new(planes: [f32x4; 6], geometry: SectionGeometry) -> Self {
+        let section_length = geometry.section_length_f32();
         let axis_bb_offsets = planes.map(|plane| {
-            Self::gen_axis_bb_offsets(plane, RelativeBoundingBox::BOUNDING_BOX_EXTENSION)
+            Self::gen_axis_bb_offsets(plane, section_length, geometry.padded_bounds_extension())
         });
         let planes_cw = array::from_fn(|component_idx| {
             Simd::from_array(planes.map(|plane| plane[component_idx]))
@@ -26,23 +29,73 @@ impl Frustum {
         Frustum {
             planes,
             axis_bb_offsets,
+            section_length,
             planes_cw,
         }
     }
 
-    fn gen_axis_bb_offsets(plane: f32x4, bounds_extension: f32) -> f32x3 {
+    fn gen_axis_bb_offsets(plane: f32x4, section_length: f32, bounds_extension: f32) -> f32x3 {
         plane
             .resize(Default::default())
             .is_sign_negative_fast()
             .select(
                 Simd::splat(-bounds_extension),
-                Simd::splat(16.0 + bounds_extension),
+                Simd::splat(section_length + bounds_extension),
             )
     }
 
     // TODO OPT: get rid of W by normalizing plane_xs, ys, zs.
     //  potentially can exclude near and far plane
-    pub fn test_box(&self, bb: RelativeBoundingBox, results: &mut CombinedTestResults) {
+    pub fn test_box<L: Level>(&self, bb: RelativeBoundingBox, results: &mut CombinedTestResults) {
+        let intersecting_planes = match () {
+            #[cfg(target_arch = "aarch64")]
+            // SAFETY: The level is only allowed to enable NEON if the target has it.
+            _ if L::NEON => unsafe { super::neon::test_planes(&self.planes_cw, bb) },
+            _ => self.test_planes::<L>(bb),
+        };
+
+        match intersecting_planes {
+            Some(intersecting_planes) => results.set_intersecting_planes(intersecting_planes),
+            None => *results = CombinedTestResults::OUTSIDE,
+        }
+    }
+
+    /// Tests 8 boxes at once, with the bounds of each box in one lane of `min`
+    /// and `max`. Returns a bitmask of the boxes that are outside of any
+    /// plane, matching the boxes that `test_box` would mark as `OUTSIDE`.
+    #[inline(always)]
+    pub fn test_boxes_outside<L: Level>(&self, min: [f32x8; 3], max: [f32x8; 3]) -> u8 {
+        let mut outside = Mask::default();
+
+        // unlike test_planes, this vectorizes across the boxes, so the planes are
+        // handled one at a time and the bounds for each axis can be picked up front
+        for plane in &self.planes {
+            let outside_bounds: [f32x8; 3] = array::from_fn(|axis| {
+                if plane[axis].is_sign_negative() {
+                    min[axis]
+                } else {
+                    max[axis]
+                }
+            });
+
+            let outside_length_sq = Simd::splat(plane[X]).mul_add_fast::<L>(
+                outside_bounds[X],
+                Simd::splat(plane[Y]).mul_add_fast::<L>(
+                    outside_bounds[Y],
+                    Simd::splat(plane[Z]) * outside_bounds[Z],
+                ),
+            );
+
+            outside |= (outside_length_sq + Simd::splat(plane[W])).is_sign_negative_fast();
+        }
+
+        outside.to_bitmask() as u8
+    }
+
+    /// Returns `None` if the box is outside of any plane, otherwise the bitmask
+    /// of the planes that it intersects.
+    #[inline(always)]
+    fn test_planes<L: Level>(&self, bb: RelativeBoundingBox) -> Option<u8> {
         // This is faster than doing a float comparison because we can ignore special
         // float values like infinity, and because we can hint to the compiler to use
         // vblendvps on x86.
@@ -62,9 +115,10 @@ impl Frustum {
         let bb_max_z = Simd::splat(bb.max[Z]);
         let outside_bounds_z = is_neg_z.select(bb_min_z, bb_max_z);
 
-        let outside_length_sq = self.planes_cw[X].mul_add_fast(
+        let outside_length_sq = self.planes_cw[X].mul_add_fast::<L>(
             outside_bounds_x,
-            self.planes_cw[Y].mul_add_fast(outside_bounds_y, self.planes_cw[Z] * outside_bounds_z),
+            self.planes_cw[Y]
+                .mul_add_fast::<L>(outside_bounds_y, self.planes_cw[Z] * outside_bounds_z),
         );
 
         // if any outside lengths are less than -w, return OUTSIDE
@@ -82,17 +136,17 @@ impl Frustum {
 
         if any_outside {
             // early exit
-            *results = CombinedTestResults::OUTSIDE;
-            return;
+            return None;
         }
 
         let inside_bounds_x = is_neg_x.select(bb_max_x, bb_min_x);
         let inside_bounds_y = is_neg_y.select(bb_max_y, bb_min_y);
         let inside_bounds_z = is_neg_z.select(bb_max_z, bb_min_z);
 
-        let inside_length_sq = self.planes_cw[X].mul_add_fast(
+        let inside_length_sq = self.planes_cw[X].mul_add_fast::<L>(
             inside_bounds_x,
-            self.planes_cw[Y].mul_add_fast(inside_bounds_y, self.planes_cw[Z] * inside_bounds_z),
+            self.planes_cw[Y]
+                .mul_add_fast::<L>(inside_bounds_y, self.planes_cw[Z] * inside_bounds_z),
         );
 
         let intersecting_planes = ((inside_length_sq + self.planes_cw[W])
@@ -100,29 +154,58 @@ impl Frustum {
             .to_bitmask()
             & 0b111111) as u8;
 
-        results.set_intersecting_planes(intersecting_planes);
+        Some(intersecting_planes)
+    }
+
+    /// Returns the direction of the plane that the box is furthest outside of,
+    /// or `None` if the box is not outside of any plane. This is much
+    /// slower than `test_box`, and is only meant for diagnostics.
+    pub fn furthest_outside_plane(&self, bb: RelativeBoundingBox) -> Option<u8> {
+        let mut furthest_plane = None;
+        let mut furthest_distance = 0.0;
+
+        for (plane_idx, plane) in self.planes.iter().enumerate() {
+            let outside_bounds = plane
+                .resize::<3>(0.0)
+                .is_sign_negative_fast()
+                .select(bb.min, bb.max);
+            let distance = (plane.resize::<3>(0.0) * outside_bounds).reduce_sum() + plane[W];
+
+            if distance < furthest_distance {
+                furthest_distance = distance;
+                furthest_plane = Some(1 << plane_idx);
+            }
+        }
+
+        furthest_plane
     }
 
     // The inlining of this was pretty aggressive. It's not really necessary and
     // likely helps the code cache this way.
     #[inline(never)]
-    pub fn voxelize_planes(
+    pub fn voxelize_planes<L: Level>(
         &self,
         mut planes: u8,
         relative_tile_pos: f32x3,
         visible_sections: &mut u8x64,
     ) {
-        while planes != 0 {
-            let plane_direction = take_one(&mut planes);
-            let plane_idx = to_index(plane_direction);
-
-            let sections_in_plane = voxelize_plane(
-                relative_tile_pos,
-                self.planes[plane_idx],
-                self.axis_bb_offsets[plane_idx],
-            );
+        // SAFETY: Kernels are only instantiated with levels that the CPU supports.
+        unsafe {
+            L::dispatch(|| {
+                while planes != 0 {
+                    let plane_direction = take_one(&mut planes);
+                    let plane_idx = to_index(plane_direction);
+
+                    let sections_in_plane = voxelize_plane::<L>(
+                        relative_tile_pos,
+                        self.planes[plane_idx],
+                        self.axis_bb_offsets[plane_idx],
+                        self.section_length,
+                    );
 
-            *visible_sections &= sections_in_plane;
+                    *visible_sections &= sections_in_plane;
+                }
+            })
         }
     }
 }
@@ -142,38 +225,44 @@ impl Frustum {
 // We vectorize this process with 8 lanes across the Z axis, and we do this
 // operation 8 times for each section on the Y axis. We extract as much work as
 // possible outside of the Y-axis loop, and specific optimzations regarding the
-// mask generation are implemented for x86 machines with AVX2.
-fn voxelize_plane(relative_tile_pos: f32x3, plane: f32x4, axis_bb_offsets: f32x3) -> u8x64 {
-    // These increments are scaled 16x because sections are cubes with side lengths
-    // of 16 blocks.
-    const SECTION_INCREMENTS: f32x8 =
-        Simd::from_array([0.0, 16.0, 32.0, 48.0, 64.0, 80.0, 96.0, 112.0]);
+// mask generation are implemented for x86 machines with AVX2 and AVX-512.
+#[inline(always)]
+fn voxelize_plane<L: Level>(
+    relative_tile_pos: f32x3,
+    plane: f32x4,
+    axis_bb_offsets: f32x3,
+    section_length: f32,
+) -> u8x64 {
+    // These increments are scaled by the length of a section in blocks, because
+    // sections are cubes.
+    let section_increments =
+        f32x8::from_array([0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]) * Simd::splat(section_length);
 
     let tile_bb_origin = relative_tile_pos + axis_bb_offsets;
     let mut section_bb_y_offset = tile_bb_origin[Y];
 
     // To simultaneously find 8 X intercepts at once, we vectorize across the Z
     // axis. These offsets let us find the result at different section Z values.
-    let section_bb_zs = SECTION_INCREMENTS + Simd::splat(tile_bb_origin[Z]);
+    let section_bb_zs = section_increments + Simd::splat(tile_bb_origin[Z]);
 
     // cz + ax + d
-    let partial_intercept_setup = section_bb_zs.mul_add_fast(
+    let partial_intercept_setup = section_bb_zs.mul_add_fast::<L>(
         Simd::splat(plane[Z]),
-        Simd::splat(tile_bb_origin[X].mul_add_fast(plane[X], plane[W])),
+        Simd::splat(tile_bb_origin[X].mul_add_fast::<L>(plane[X], plane[W])),
     );
 
-    // -16a
-    let plane_x_scaled = Simd::splat(plane[X] * -16.0);
+    // -la, where l is the section length
+    let plane_x_scaled = Simd::splat(plane[X] * -section_length);
 
     let tile_x_intercepts_expanded = i32x64::from_slice(
         array::from_fn::<_, 8, _>(|_y| {
-            // (by + (cz + ax + d)) / (-16a)
+            // (by + (cz + ax + d)) / (-la)
             let tile_x_intercepts = Simd::splat(section_bb_y_offset)
-                .mul_add_fast(Simd::splat(plane[Y]), partial_intercept_setup)
+                .mul_add_fast::<L>(Simd::splat(plane[Y]), partial_intercept_setup)
                 / plane_x_scaled;
 
             // Increment Y by length of section in blocks after usage of offsets
-            section_bb_y_offset += 16.0;
+            section_bb_y_offset += section_length;
 
             // SAFETY: We make sure the value going into the conversion is no larger than
             // 7.0. For values under 0.0, we mask out the poison values before using the
@@ -199,37 +288,7 @@ fn voxelize_plane(relative_tile_pos: f32x3, plane: f32x4, axis_bb_offsets: f32x3
         .simd_clamp(Simd::splat(i8::MIN).cast(), Simd::splat(i8::MAX).cast())
         .cast::<u8>();
 
-    #[cfg(target_feature = "avx2")]
-    let tile_x_masks = unsafe {
-        use std::arch::x86_64::*;
-
-        let intercepts_halves: [u8x32; 2] = [
-            tile_x_intercepts.extract::<0, 32>(),
-            tile_x_intercepts.extract::<32, 32>(),
-        ];
-
-        let mask_table = _mm256_set1_epi64x(i64::from_le_bytes([
-            0b1, 0b11, 0b111, 0b1111, 0b11111, 0b111111, 0b1111111, 0b11111111,
-        ]));
-        let shuffled_masks_halves: [u8x32; 2] = intercepts_halves
-            .map(|intercepts| _mm256_shuffle_epi8(mask_table, intercepts.into()).into());
-
-        simd_swizzle!(
-            shuffled_masks_halves[0],
-            shuffled_masks_halves[1],
-            concat_swizzle_pattern::<64>()
-        )
-    };
-
-    #[cfg(not(target_feature = "avx2"))]
-    let tile_x_masks = {
-        let in_bounds_masks = (Simd::splat(0b10) << tile_x_intercepts) - Simd::splat(1);
-        tile_x_intercepts
-            .simd_lt(Simd::splat(8))
-            .to_int()
-            .cast::<u8>()
-            & in_bounds_masks
-    };
+    let tile_x_masks = intercepts_to_masks::<L>(tile_x_intercepts);
 
     // If plane[X] is positive, this will be all 1 bits. if plane[X] is negative,
     // this will be all 0 bits. This is used to reverse the direction of the mask
@@ -239,6 +298,102 @@ fn voxelize_plane(relative_tile_pos: f32x3, plane: f32x4, axis_bb_offsets: f32x3
     tile_x_masks ^ plane_x_positive_mask
 }
 
+// Turns each intercept into a mask with all of the bits up to and including the
+// intercept set. The intercepts must either be between 0 and 7, or have the top
+// bit set, which results in an empty mask.
+#[inline(always)]
+fn intercepts_to_masks<L: Level>(tile_x_intercepts: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { super::avx512::intercepts_to_masks(tile_x_intercepts) },
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX2 if the CPU supports it.
+        _ if L::AVX2 => unsafe { intercepts_to_masks_avx2(tile_x_intercepts) },
+        _ => {
+            let in_bounds_masks = (Simd::splat(0b10) << tile_x_intercepts) - Simd::splat(1);
+            tile_x_intercepts
+                .simd_lt(Simd::splat(8))
+                .to_int()
+                .cast::<u8>()
+                & in_bounds_masks
+        }
+    }
+}
+
+/// Creates the planes of a perspective frustum, with each plane's normal facing
+/// inwards. The angles are in radians and match Minecraft's, so a yaw of 0
+/// faces +Z and a positive pitch faces downwards. This is only used to make
+/// cameras for the tests, the benchmarks and the command-line tool.
+pub fn perspective_planes(
+    yaw: f32,
+    pitch: f32,
+    fov_y: f32,
+    aspect_ratio: f32,
+    far_distance: f32,
+) -> [f32x4; 6] {
+    let half_fov_y = fov_y / 2.0;
+    let half_fov_x = (half_fov_y.tan() * aspect_ratio).atan();
+
+    let forward = f32x3::from_xyz(
+        -yaw.sin() * pitch.cos(),
+        -pitch.sin(),
+        yaw.cos() * pitch.cos(),
+    );
+    let right = f32x3::from_xyz(yaw.cos(), 0.0, yaw.sin());
+    let up = f32x3::from_xyz(
+        yaw.sin() * pitch.sin(),
+        pitch.cos(),
+        -yaw.cos() * pitch.sin(),
+    );
+
+    let plane = |normal: f32x3, w: f32| f32x4::from_array([normal[X], normal[Y], normal[Z], w]);
+    let side = |axis: f32x3, half_fov: f32, sign: f32| {
+        plane(
+            axis * Simd::splat(sign * half_fov.cos()) + forward * Simd::splat(half_fov.sin()),
+            0.0,
+        )
+    };
+
+    [
+        side(right, half_fov_x, 1.0),
+        side(up, half_fov_y, 1.0),
+        plane(forward, 0.05),
+        side(right, half_fov_x, -1.0),
+        side(up, half_fov_y, -1.0),
+        plane(-forward, far_distance),
+    ]
+}
+
+// Turns each intercept into a mask with a lookup table. The negative intercepts
+// don't need to be handled separately, because pshufb writes a 0 for any index
+// with the top bit set.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+#[inline]
+fn intercepts_to_masks_avx2(tile_x_intercepts: u8x64) -> u8x64 {
+    use std::arch::x86_64::*;
+
+    use crate::simd::u8x32;
+
+    let intercepts_halves: [u8x32; 2] = [
+        tile_x_intercepts.extract::<0, 32>(),
+        tile_x_intercepts.extract::<32, 32>(),
+    ];
+
+    let mask_table = _mm256_set1_epi64x(i64::from_le_bytes([
+        0b1, 0b11, 0b111, 0b1111, 0b11111, 0b111111, 0b1111111, 0b11111111,
+    ]));
+    let shuffled_masks_halves: [u8x32; 2] = intercepts_halves
+        .map(|intercepts| _mm256_shuffle_epi8(mask_table, intercepts.into()).into());
+
+    simd_swizzle!(
+        shuffled_masks_halves[0],
+        shuffled_masks_halves[1],
+        concat_swizzle_pattern::<64>()
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use std::f32::consts::TAU;
@@ -246,9 +401,15 @@ mod tests {
     use rand::prelude::*;
 
     use super::*;
+    use crate::simd::level::{Baseline, Portable};
     use crate::TESTS_RANDOM_SEED;
 
-    fn voxelize_plane_slow(relative_tile_pos: f32x3, plane: f32x4, bounds_extension: f32) -> u8x64 {
+    fn voxelize_plane_slow(
+        relative_tile_pos: f32x3,
+        plane: f32x4,
+        section_length: f32,
+        bounds_extension: f32,
+    ) -> u8x64 {
         let mut visible_sections = SECTIONS_EMPTY;
 
         for y in 0..8 {
@@ -259,10 +420,10 @@ mod tests {
 
                     let relative_section_pos = section_coords
                         .cast::<f32>()
-                        .mul_add_fast(Simd::splat(16.0), relative_tile_pos);
+                        .mul_add_fast::<Baseline>(Simd::splat(section_length), relative_tile_pos);
                     let bb = RelativeBoundingBox::new(
                         relative_section_pos - Simd::splat(bounds_extension),
-                        relative_section_pos + Simd::splat(16.0 + bounds_extension),
+                        relative_section_pos + Simd::splat(section_length + bounds_extension),
                     );
 
                     let not_outside = plane[X]
@@ -280,51 +441,206 @@ mod tests {
     }
 
     #[test]
-    fn plane_voxelization_test() {
+    fn intercepts_to_masks_level_equivalence_test() {
         const ITERATIONS: u32 = 10000;
         let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
 
         for _ in 0..ITERATIONS {
+            // the intercepts are either in the tile, or negative
+            let intercepts = u8x64::from_array(array::from_fn(|_| {
+                if rand.random_bool(0.5) {
+                    rand.random_range(0..8)
+                } else {
+                    rand.random_range(128..=255)
+                }
+            }));
+
+            let baseline_masks = intercepts_to_masks::<Baseline>(intercepts);
+
+            for level in SimdLevel::supported() {
+                // SAFETY: The level is supported by the CPU.
+                let level_masks = with_level!(level, L => unsafe {
+                    L::dispatch(|| intercepts_to_masks::<L>(intercepts))
+                });
+
+                assert_eq!(
+                    baseline_masks, level_masks,
+                    "baseline != {level}, Intercepts: {intercepts:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn plane_voxelization_test() {
+        const ITERATIONS: u32 = 10000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+        let levels: Vec<_> = SimdLevel::supported().collect();
+
+        let geometries = [
+            SectionGeometry::MINECRAFT,
+            SectionGeometry::new(32, 2.0).unwrap(),
+            SectionGeometry::new(4, 0.0).unwrap(),
+        ];
+
+        for iteration in 0..ITERATIONS {
+            let geometry = geometries[iteration as usize % geometries.len()];
+            let section_length = geometry.section_length_f32();
+            // keep the positions and distances the same number of sections away
+            let scale = section_length / 16.0;
+
             // generate random plane from random unit vector and random W component.
             // based off of this math stackexchange answer: https://math.stackexchange.com/a/44691
             let theta = rand.random_range(0.0..TAU);
             let z: f32 = rand.random_range(-1.0..1.0);
-            let w: f32 = rand.random_range(-10.0..1000.0);
+            let w: f32 = rand.random_range(-10.0..1000.0) * scale;
 
             let z_modified = (1.0 - (z * z)).sqrt();
             let x = z_modified * theta.cos();
             let y = z_modified * theta.sin();
 
             let plane = Simd::from_array([x, y, z, w]);
-            let plane_bb_offsets =
-                Frustum::gen_axis_bb_offsets(plane, RelativeBoundingBox::BOUNDING_BOX_EXTENSION);
+            let plane_bb_offsets = Frustum::gen_axis_bb_offsets(
+                plane,
+                section_length,
+                geometry.padded_bounds_extension(),
+            );
 
             let relative_tile_pos = Simd::from_xyz(
-                rand.random_range(-3000.0_f32..3000.0_f32),
-                rand.random_range(-3000.0_f32..3000.0_f32),
-                rand.random_range(-3000.0_f32..3000.0_f32),
+                rand.random_range(-3000.0_f32..3000.0_f32) * scale,
+                rand.random_range(-3000.0_f32..3000.0_f32) * scale,
+                rand.random_range(-3000.0_f32..3000.0_f32) * scale,
             );
 
             let sane_visible_sections_min = voxelize_plane_slow(
                 relative_tile_pos,
                 plane,
-                RelativeBoundingBox::BOUNDING_BOX_EXTENSION_MIN,
+                section_length,
+                geometry.bounds_extension(),
             );
             let sane_visible_sections_max = voxelize_plane_slow(
                 relative_tile_pos,
                 plane,
-                RelativeBoundingBox::BOUNDING_BOX_EXTENSION_MAX,
+                section_length,
+                geometry.max_bounds_extension(),
             );
-            let test_visible_sections = voxelize_plane(relative_tile_pos, plane, plane_bb_offsets);
-
-            if !test_minimum_maximum(
-                &sane_visible_sections_min,
-                &sane_visible_sections_max,
-                &test_visible_sections,
-            ) {
-                panic!(
-                    "Test results don't fit in sane bounds. Relative Tile Coords: {relative_tile_pos:?}, Plane: {plane:?}",
+
+            for &level in &levels {
+                // SAFETY: The level is supported by the CPU.
+                let test_visible_sections = with_level!(level, L => unsafe {
+                    L::dispatch(|| {
+                        voxelize_plane::<L>(relative_tile_pos, plane, plane_bb_offsets, section_length)
+                    })
+                });
+
+                if !test_minimum_maximum(
+                    &sane_visible_sections_min,
+                    &sane_visible_sections_max,
+                    &test_visible_sections,
+                ) {
+                    panic!(
+                        "Test results don't fit in sane bounds. Level: {level}, Geometry: {geometry:?}, Relative Tile Coords: {relative_tile_pos:?}, Plane: {plane:?}",
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_box_level_equivalence_test() {
+        const ITERATIONS: u32 = 10000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for _ in 0..ITERATIONS {
+            let frustum = Frustum::new(
+                array::from_fn(|_| {
+                    Simd::from_array([
+                        rand.random_range(-1.0..1.0),
+                        rand.random_range(-1.0..1.0),
+                        rand.random_range(-1.0..1.0),
+                        rand.random_range(-100.0..100.0),
+                    ])
+                }),
+                SectionGeometry::MINECRAFT,
+            );
+            let bb_min = Simd::from_xyz(
+                rand.random_range(-200.0_f32..200.0_f32),
+                rand.random_range(-200.0_f32..200.0_f32),
+                rand.random_range(-200.0_f32..200.0_f32),
+            );
+            let bb = RelativeBoundingBox::new(bb_min, bb_min + Simd::splat(16.0));
+
+            let mut portable_results = CombinedTestResults::ALL_INSIDE;
+            frustum.test_box::<Portable>(bb, &mut portable_results);
+
+            for level in SimdLevel::supported() {
+                let mut level_results = CombinedTestResults::ALL_INSIDE;
+                // SAFETY: The level is supported by the CPU.
+                with_level!(level, L => unsafe {
+                    L::dispatch(|| frustum.test_box::<L>(bb, &mut level_results))
+                });
+
+                assert_eq!(
+                    portable_results, level_results,
+                    "portable != {level}, Box Min: {bb_min:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_boxes_outside_test() {
+        const ITERATIONS: u32 = 2000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for _ in 0..ITERATIONS {
+            let frustum = Frustum::new(
+                array::from_fn(|_| {
+                    Simd::from_array([
+                        rand.random_range(-1.0..1.0),
+                        rand.random_range(-1.0..1.0),
+                        rand.random_range(-1.0..1.0),
+                        rand.random_range(-100.0..100.0),
+                    ])
+                }),
+                SectionGeometry::MINECRAFT,
+            );
+            let boxes: [RelativeBoundingBox; 8] = array::from_fn(|_| {
+                let bb_min = Simd::from_xyz(
+                    rand.random_range(-200.0_f32..200.0_f32),
+                    rand.random_range(-200.0_f32..200.0_f32),
+                    rand.random_range(-200.0_f32..200.0_f32),
+                );
+                let bb_size = Simd::from_xyz(
+                    rand.random_range(0.0_f32..20.0_f32),
+                    rand.random_range(0.0_f32..20.0_f32),
+                    rand.random_range(0.0_f32..20.0_f32),
                 );
+                RelativeBoundingBox::new(bb_min, bb_min + bb_size)
+            });
+            let min = array::from_fn(|axis| Simd::from_array(boxes.map(|bb| bb.min[axis])));
+            let max = array::from_fn(|axis| Simd::from_array(boxes.map(|bb| bb.max[axis])));
+
+            for level in SimdLevel::supported() {
+                // SAFETY: The level is supported by the CPU.
+                with_level!(level, L => unsafe {
+                    L::dispatch(|| {
+                        let outside = frustum.test_boxes_outside::<L>(min, max);
+
+                        for (lane, &bb) in boxes.iter().enumerate() {
+                            let mut results = CombinedTestResults::ALL_INSIDE;
+                            frustum.test_box::<L>(bb, &mut results);
+
+                            assert_eq!(
+                                outside & (1 << lane) != 0,
+                                results == CombinedTestResults::OUTSIDE,
+                                "Level: {level}, Box Min: {:?}, Box Max: {:?}",
+                                bb.min,
+                                bb.max,
+                            );
+                        }
+                    })
+                });
             }
         }
     }
@@ -6,12 +6,11 @@ pub fn test_coords(
     results: &mut CombinedTestResults,
 ) {
     let tile_y = coords[Y];
-    let world_max_y = (coord_space.y_length_tiles - 1) as i8;
+    let world_max_y = coord_space.world_top_tile_y();
 
-    // out of bounds
+    // out of bounds, which happens for the padding tiles above short worlds
     if tile_y > world_max_y {
         // early exit
-        // TODO: should this ever happen?
         *results = CombinedTestResults::OUTSIDE;
         return;
     }
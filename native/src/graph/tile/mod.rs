@@ -1,14 +1,15 @@
 pub mod angle;
+#[cfg(target_arch = "x86_64")]
+mod avx512;
 pub mod fog;
 pub mod frustum;
 pub mod height;
+#[cfg(target_arch = "aarch64")]
+mod neon;
 pub mod traversal;
 
-use core_simd::simd::prelude::*;
-use core_simd::simd::ToBytes;
-use std_float::StdFloat;
+use std::fmt;
 
-use super::visibility::*;
 use super::{connection_index, u8x3, *};
 use crate::bitset;
 use crate::bitset::BitSet;
@@ -63,11 +64,15 @@ pub fn or_bit(sections: &mut u8x64, index: u16, value: bool) {
     byte.or_bit(bit_idx, value);
 }
 
-pub fn rasterize_rows(lower_bound: f32x8, upper_bound: f32x8) -> (f32x8, f32x8, u32x8, u32x8) {
+#[inline(always)]
+pub fn rasterize_rows<L: Level>(
+    lower_bound: f32x8,
+    upper_bound: f32x8,
+) -> (f32x8, f32x8, u32x8, u32x8) {
     let lower_bound_ceil_clamped = lower_bound
-        .ceil()
+        .ceil_fast::<L>()
         .simd_clamp(Simd::splat(0.0), Simd::splat(8.0));
-    let upper_bound_floor = upper_bound.floor();
+    let upper_bound_floor = upper_bound.floor_fast::<L>();
 
     let lower_bound_shifts = unsafe {
         lower_bound_ceil_clamped
@@ -91,24 +96,44 @@ pub fn rasterize_rows(lower_bound: f32x8, upper_bound: f32x8) -> (f32x8, f32x8,
     )
 }
 
-#[cfg(test)]
-pub fn print_tile(sections: &u8x64) {
-    for y in 0..8 {
-        println!("↓Y{y}");
-        for z in 0..8 {
-            for x in 0..8 {
-                print!(
-                    "{}",
-                    if get_bit(sections, section_index(Simd::from_xyz(x, y, z))) {
-                        1_u8
-                    } else {
-                        0_u8
-                    }
-                );
+/// Writes a human-readable representation of the sections in a tile, one Y
+/// layer at a time.
+pub fn write_tile<W: fmt::Write>(writer: &mut W, sections: &u8x64) -> fmt::Result {
+    write_layers(writer, Simd::splat(0), Simd::splat(7), |coords| {
+        if get_bit(sections, section_index(coords.cast::<u8>())) {
+            '1'
+        } else {
+            '0'
+        }
+    })
+}
+
+/// Writes a grid of characters for each section from `min` to `max`, inclusive,
+/// one Y layer at a time. Each row of a layer has a constant Z coordinate.
+pub fn write_layers<W: fmt::Write>(
+    writer: &mut W,
+    min: i32x3,
+    max: i32x3,
+    section_char: impl Fn(i32x3) -> char,
+) -> fmt::Result {
+    for y in min[Y]..=max[Y] {
+        writeln!(writer, "↓Y{y}")?;
+        for z in min[Z]..=max[Z] {
+            for x in min[X]..=max[X] {
+                writer.write_char(section_char(i32x3::from_xyz(x, y, z)))?;
             }
-            println!(" Z{z}");
+            writeln!(writer, " Z{z}")?;
         }
     }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub fn print_tile(sections: &u8x64) {
+    let mut output = String::new();
+    let _ = write_tile(&mut output, sections);
+    print!("{output}");
 }
 
 #[cfg(test)]
@@ -129,7 +154,7 @@ pub fn test_minimum_maximum(
         print_tile(&sections_outside_minimum);
 
         println!("-------------- Minimum");
-        print_tile(&sane_visible_sections_min);
+        print_tile(sane_visible_sections_min);
 
         passed = false;
     }
@@ -139,20 +164,20 @@ pub fn test_minimum_maximum(
         print_tile(&sections_outside_maximum);
 
         println!("-------------- Maximum");
-        print_tile(&sane_visible_sections_max);
+        print_tile(sane_visible_sections_max);
 
         passed = false;
     }
 
     if !passed {
         println!("-------------- Test results");
-        print_tile(&test_visible_sections);
+        print_tile(test_visible_sections);
     }
 
     passed
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Tile {
     // Only changes on section update
     pub connection_section_sets: [u8x64; UNIQUE_CONNECTION_COUNT],
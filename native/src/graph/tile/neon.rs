@@ -0,0 +1,264 @@
+//! NEON versions of the tile operations that the generic code lowers poorly on
+//! aarch64. A tile is split across four 128-bit registers, and LLVM turns the
+//! swizzles that move bytes between them into generic shuffles, while TBL and
+//! EXT can do each of them in one instruction per register.
+//!
+//! Each function here must return bit-identical results to the generic code
+//! it replaces. Callers pick them with [`Level::NEON`].
+
+use std::arch::aarch64::*;
+
+use super::*;
+use crate::graph::coords::RelativeBoundingBox;
+
+// Each 64-bit lane holds one X-Z plane of the tile, with the rows on the Z axis
+// in byte order. Moving on the Z axis is a shift within those lanes, and moving
+// on the Y axis moves whole lanes, which EXT does across a pair of registers.
+
+#[target_feature(enable = "neon")]
+#[inline]
+fn shift_right_u64<const N: i32>(sections: u8x64) -> u8x64 {
+    let sections: uint8x16x4_t = sections.into();
+
+    uint8x16x4_t(
+        vreinterpretq_u8_u64(vshrq_n_u64::<N>(vreinterpretq_u64_u8(sections.0))),
+        vreinterpretq_u8_u64(vshrq_n_u64::<N>(vreinterpretq_u64_u8(sections.1))),
+        vreinterpretq_u8_u64(vshrq_n_u64::<N>(vreinterpretq_u64_u8(sections.2))),
+        vreinterpretq_u8_u64(vshrq_n_u64::<N>(vreinterpretq_u64_u8(sections.3))),
+    )
+    .into()
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+fn shift_left_u64<const N: i32>(sections: u8x64) -> u8x64 {
+    let sections: uint8x16x4_t = sections.into();
+
+    uint8x16x4_t(
+        vreinterpretq_u8_u64(vshlq_n_u64::<N>(vreinterpretq_u64_u8(sections.0))),
+        vreinterpretq_u8_u64(vshlq_n_u64::<N>(vreinterpretq_u64_u8(sections.1))),
+        vreinterpretq_u8_u64(vshlq_n_u64::<N>(vreinterpretq_u64_u8(sections.2))),
+        vreinterpretq_u8_u64(vshlq_n_u64::<N>(vreinterpretq_u64_u8(sections.3))),
+    )
+    .into()
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn shift_neg_z(sections: u8x64) -> u8x64 {
+    shift_right_u64::<8>(sections)
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn shift_pos_z(sections: u8x64) -> u8x64 {
+    shift_left_u64::<8>(sections)
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn shift_neg_y(sections: u8x64) -> u8x64 {
+    let sections: uint8x16x4_t = sections.into();
+    let zero = vdupq_n_u8(0);
+
+    uint8x16x4_t(
+        vextq_u8::<8>(sections.0, sections.1),
+        vextq_u8::<8>(sections.1, sections.2),
+        vextq_u8::<8>(sections.2, sections.3),
+        vextq_u8::<8>(sections.3, zero),
+    )
+    .into()
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn shift_pos_y(sections: u8x64) -> u8x64 {
+    let sections: uint8x16x4_t = sections.into();
+    let zero = vdupq_n_u8(0);
+
+    uint8x16x4_t(
+        vextq_u8::<8>(zero, sections.0),
+        vextq_u8::<8>(sections.0, sections.1),
+        vextq_u8::<8>(sections.1, sections.2),
+        vextq_u8::<8>(sections.2, sections.3),
+    )
+    .into()
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn edge_neg_to_pos_z(sections: u8x64) -> u8x64 {
+    shift_left_u64::<56>(sections)
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn edge_pos_to_neg_z(sections: u8x64) -> u8x64 {
+    shift_right_u64::<56>(sections)
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn edge_neg_to_pos_y(sections: u8x64) -> u8x64 {
+    let sections: uint8x16x4_t = sections.into();
+    let zero = vdupq_n_u8(0);
+
+    uint8x16x4_t(zero, zero, zero, vextq_u8::<8>(zero, sections.0)).into()
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn edge_pos_to_neg_y(sections: u8x64) -> u8x64 {
+    let sections: uint8x16x4_t = sections.into();
+    let zero = vdupq_n_u8(0);
+
+    uint8x16x4_t(vextq_u8::<8>(sections.3, zero), zero, zero, zero).into()
+}
+
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn expand_xz_mask(compressed_mask: u8x8) -> u8x64 {
+    let compressed_mask: uint8x8_t = compressed_mask.into();
+    let rows = vcombine_u8(compressed_mask, compressed_mask);
+
+    uint8x16x4_t(rows, rows, rows, rows).into()
+}
+
+/// Repeats each byte of the mask 8 times with one TBL per register.
+#[rustfmt::skip]
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn expand_xy_mask(compressed_mask: u8x8) -> u8x64 {
+    const INDICES: [[u8; 16]; 4] = [
+        [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1],
+        [2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 3],
+        [4, 4, 4, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5],
+        [6, 6, 6, 6, 6, 6, 6, 6, 7, 7, 7, 7, 7, 7, 7, 7],
+    ];
+
+    let table = vcombine_u8(compressed_mask.into(), vdup_n_u8(0));
+
+    uint8x16x4_t(
+        vqtbl1q_u8(table, Simd::from_array(INDICES[0]).into()),
+        vqtbl1q_u8(table, Simd::from_array(INDICES[1]).into()),
+        vqtbl1q_u8(table, Simd::from_array(INDICES[2]).into()),
+        vqtbl1q_u8(table, Simd::from_array(INDICES[3]).into()),
+    )
+    .into()
+}
+
+/// Expands the mask the same way as [`expand_xy_mask`], then turns bit `i` of
+/// each byte into the whole byte with CMTST, which sets every bit of a byte
+/// when the two inputs share any bit.
+#[rustfmt::skip]
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn expand_zy_mask(compressed_mask: u8x8) -> u8x64 {
+    const BITS: [u8; 16] = [
+        0b1, 0b10, 0b100, 0b1000, 0b10000, 0b100000, 0b1000000, 0b10000000,
+        0b1, 0b10, 0b100, 0b1000, 0b10000, 0b100000, 0b1000000, 0b10000000,
+    ];
+
+    let expanded: uint8x16x4_t = expand_xy_mask(compressed_mask).into();
+    let bits: uint8x16_t = Simd::from_array(BITS).into();
+
+    uint8x16x4_t(
+        vtstq_u8(expanded.0, bits),
+        vtstq_u8(expanded.1, bits),
+        vtstq_u8(expanded.2, bits),
+        vtstq_u8(expanded.3, bits),
+    )
+    .into()
+}
+
+/// Tests a box against the frustum planes the same way as
+/// [`Frustum::test_box`](super::frustum::Frustum::test_box), with the 6 planes
+/// split into a register of 4 and a register of 2 plus padding. Returns `None`
+/// if the box is outside of any plane, otherwise the bitmask of the planes
+/// that it intersects.
+#[target_feature(enable = "neon")]
+#[inline]
+pub fn test_planes(
+    planes_cw: &[Simd<f32, DIRECTION_COUNT>; 4],
+    bb: RelativeBoundingBox,
+) -> Option<u8> {
+    let planes_low: [float32x4_t; 4] = planes_cw.map(|planes| planes.extract::<0, 4>().into());
+    let planes_high: [float32x4_t; 4] =
+        planes_cw.map(|planes| planes.resize::<8>(0.0).extract::<4, 4>().into());
+
+    let is_neg_low = [
+        vcltzq_s32(vreinterpretq_s32_f32(planes_low[X])),
+        vcltzq_s32(vreinterpretq_s32_f32(planes_low[Y])),
+        vcltzq_s32(vreinterpretq_s32_f32(planes_low[Z])),
+    ];
+    let is_neg_high = [
+        vcltzq_s32(vreinterpretq_s32_f32(planes_high[X])),
+        vcltzq_s32(vreinterpretq_s32_f32(planes_high[Y])),
+        vcltzq_s32(vreinterpretq_s32_f32(planes_high[Z])),
+    ];
+
+    let outside_planes = sign_bitmask(
+        corner_distances(planes_low, is_neg_low, bb.min, bb.max),
+        corner_distances(planes_high, is_neg_high, bb.min, bb.max),
+    );
+
+    if outside_planes != 0 {
+        return None;
+    }
+
+    Some(sign_bitmask(
+        corner_distances(planes_low, is_neg_low, bb.max, bb.min),
+        corner_distances(planes_high, is_neg_high, bb.max, bb.min),
+    ))
+}
+
+/// Returns the signed distance of the corner of the box picked by the sign of
+/// each plane component, fused in the same order as the generic code.
+#[target_feature(enable = "neon")]
+#[inline]
+fn corner_distances(
+    planes: [float32x4_t; 4],
+    is_neg: [uint32x4_t; 3],
+    neg_corner: f32x3,
+    pos_corner: f32x3,
+) -> float32x4_t {
+    let corner_x = vbslq_f32(
+        is_neg[X],
+        vdupq_n_f32(neg_corner[X]),
+        vdupq_n_f32(pos_corner[X]),
+    );
+    let corner_y = vbslq_f32(
+        is_neg[Y],
+        vdupq_n_f32(neg_corner[Y]),
+        vdupq_n_f32(pos_corner[Y]),
+    );
+    let corner_z = vbslq_f32(
+        is_neg[Z],
+        vdupq_n_f32(neg_corner[Z]),
+        vdupq_n_f32(pos_corner[Z]),
+    );
+
+    let length = vfmaq_f32(
+        vfmaq_f32(vmulq_f32(planes[Z], corner_z), planes[Y], corner_y),
+        planes[X],
+        corner_x,
+    );
+    vaddq_f32(length, planes[W])
+}
+
+/// Packs the sign bits of the 6 plane lanes into a bitmask, dropping the
+/// padding lanes of the high register.
+#[target_feature(enable = "neon")]
+#[inline]
+fn sign_bitmask(low: float32x4_t, high: float32x4_t) -> u8 {
+    let low_positions: int32x4_t = Simd::from_array([0, 1, 2, 3]).into();
+    let high_positions: int32x4_t = Simd::from_array([4, 5, 6, 7]).into();
+
+    let low_bits = vshlq_u32(vshrq_n_u32::<31>(vreinterpretq_u32_f32(low)), low_positions);
+    let high_bits = vshlq_u32(
+        vshrq_n_u32::<31>(vreinterpretq_u32_f32(high)),
+        high_positions,
+    );
+
+    (vaddvq_u32(vorrq_u32(low_bits, high_bits)) & 0b111111) as u8
+}
@@ -27,10 +27,33 @@ impl Tile {
         }
     }
 
-    pub fn traverse<const TRAVERSAL_DIRS: u8>(
+    pub fn traverse<const TRAVERSAL_DIRS: u8, L: Level>(
         &mut self,
         start_sections: u8x64,
-        mut incoming_dir_section_sets: [u8x64; DIRECTION_COUNT],
+        incoming_dir_section_sets: &mut [u8x64; DIRECTION_COUNT],
+        outward_direction_masks: &[u8x64; DIRECTION_COUNT],
+        angle_visibility_masks: &[u8x64; 3],
+        visible_sections: &mut u8x64,
+    ) {
+        // SAFETY: Kernels are only instantiated with levels that the CPU supports.
+        unsafe {
+            L::dispatch(|| {
+                self.traverse_body::<TRAVERSAL_DIRS, L>(
+                    start_sections,
+                    incoming_dir_section_sets,
+                    outward_direction_masks,
+                    angle_visibility_masks,
+                    visible_sections,
+                )
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn traverse_body<const TRAVERSAL_DIRS: u8, L: Level>(
+        &mut self,
+        start_sections: u8x64,
+        incoming_dir_section_sets: &mut [u8x64; DIRECTION_COUNT],
         outward_direction_masks: &[u8x64; DIRECTION_COUNT],
         angle_visibility_masks: &[u8x64; 3],
         visible_sections: &mut u8x64,
@@ -41,43 +64,43 @@ impl Tile {
         loop {
             let mut incoming_changed = false;
 
-            self.try_traverse_dir::<TRAVERSAL_DIRS, NEG_X>(
-                &mut incoming_dir_section_sets,
+            self.try_traverse_dir::<TRAVERSAL_DIRS, NEG_X, L>(
+                incoming_dir_section_sets,
                 outward_direction_masks,
                 angle_visibility_masks,
                 main_visibility_mask,
                 &mut incoming_changed,
             );
-            self.try_traverse_dir::<TRAVERSAL_DIRS, NEG_Y>(
-                &mut incoming_dir_section_sets,
+            self.try_traverse_dir::<TRAVERSAL_DIRS, NEG_Y, L>(
+                incoming_dir_section_sets,
                 outward_direction_masks,
                 angle_visibility_masks,
                 main_visibility_mask,
                 &mut incoming_changed,
             );
-            self.try_traverse_dir::<TRAVERSAL_DIRS, NEG_Z>(
-                &mut incoming_dir_section_sets,
+            self.try_traverse_dir::<TRAVERSAL_DIRS, NEG_Z, L>(
+                incoming_dir_section_sets,
                 outward_direction_masks,
                 angle_visibility_masks,
                 main_visibility_mask,
                 &mut incoming_changed,
             );
-            self.try_traverse_dir::<TRAVERSAL_DIRS, POS_X>(
-                &mut incoming_dir_section_sets,
+            self.try_traverse_dir::<TRAVERSAL_DIRS, POS_X, L>(
+                incoming_dir_section_sets,
                 outward_direction_masks,
                 angle_visibility_masks,
                 main_visibility_mask,
                 &mut incoming_changed,
             );
-            self.try_traverse_dir::<TRAVERSAL_DIRS, POS_Y>(
-                &mut incoming_dir_section_sets,
+            self.try_traverse_dir::<TRAVERSAL_DIRS, POS_Y, L>(
+                incoming_dir_section_sets,
                 outward_direction_masks,
                 angle_visibility_masks,
                 main_visibility_mask,
                 &mut incoming_changed,
             );
-            self.try_traverse_dir::<TRAVERSAL_DIRS, POS_Z>(
-                &mut incoming_dir_section_sets,
+            self.try_traverse_dir::<TRAVERSAL_DIRS, POS_Z, L>(
+                incoming_dir_section_sets,
                 outward_direction_masks,
                 angle_visibility_masks,
                 main_visibility_mask,
@@ -94,7 +117,8 @@ impl Tile {
             .fold(start_sections, |a, b| a | b);
     }
 
-    fn try_traverse_dir<const TRAVERSAL_DIRS: u8, const OUTGOING_DIR: u8>(
+    #[inline(always)]
+    fn try_traverse_dir<const TRAVERSAL_DIRS: u8, const OUTGOING_DIR: u8, L: Level>(
         &mut self,
         incoming_dir_section_sets: &mut [u8x64; DIRECTION_COUNT],
         outward_direction_masks: &[u8x64; DIRECTION_COUNT],
@@ -116,22 +140,23 @@ impl Tile {
             let outgoing_sections = self.outgoing_dir_section_sets[dir_index];
             let shifted_masked = match OUTGOING_DIR {
                 NEG_X => shift_neg_x(outgoing_sections),
-                NEG_Y => shift_neg_y(outgoing_sections),
-                NEG_Z => shift_neg_z(outgoing_sections),
+                NEG_Y => shift_neg_y::<L>(outgoing_sections),
+                NEG_Z => shift_neg_z::<L>(outgoing_sections),
                 POS_X => shift_pos_x(outgoing_sections),
-                POS_Y => shift_pos_y(outgoing_sections),
-                POS_Z => shift_pos_z(outgoing_sections),
+                POS_Y => shift_pos_y::<L>(outgoing_sections),
+                POS_Z => shift_pos_z::<L>(outgoing_sections),
                 _ => unreachable!(),
             } & main_visibility_mask;
 
             // TODO: does this have to be an OR? I think the answer is yes
-            let previous = incoming_dir_section_sets[opposite_dir_index];
-            incoming_dir_section_sets[opposite_dir_index] |= shifted_masked;
-
-            *incoming_changed |= incoming_dir_section_sets[opposite_dir_index] != previous;
+            *incoming_changed |= or_changed::<L>(
+                &mut incoming_dir_section_sets[opposite_dir_index],
+                shifted_masked,
+            );
         }
     }
 
+    #[inline(always)]
     fn find_outgoing_connections<const TRAVERSAL_DIRS: u8, const OUTGOING_DIR: u8>(
         &mut self,
         incoming_dir_section_sets: &[u8x64; DIRECTION_COUNT],
@@ -164,16 +189,36 @@ impl Tile {
     }
 }
 
+/// ORs `added` into `sections`, and returns whether that set any new bits.
+#[inline(always)]
+fn or_changed<L: Level>(sections: &mut u8x64, added: u8x64) -> bool {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::or_changed(sections, added) },
+        _ => {
+            let previous = *sections;
+            *sections |= added;
+            *sections != previous
+        }
+    }
+}
+
+// The X axis moves are shifts within each byte, which every level already does
+// in a couple of instructions, so they don't have AVX-512 or NEON versions.
+#[inline(always)]
 fn shift_neg_x(sections: u8x64) -> u8x64 {
     sections >> 1
 }
 
+#[inline(always)]
 fn shift_pos_x(sections: u8x64) -> u8x64 {
     sections << 1
 }
 
 #[rustfmt::skip]
-fn shift_neg_z(sections: u8x64) -> u8x64 {
+#[inline(always)]
+fn shift_neg_z<L: Level>(sections: u8x64) -> u8x64 {
     // The u8x64 "sections" vector represents an 8x8x8 array of bits, with each
     // bit representing a render section. It is indexed with the pattern
     // YYYZZZXXX. Because of our indexing scheme, we know that each u8 lane
@@ -209,154 +254,227 @@ fn shift_neg_z(sections: u8x64) -> u8x64 {
     // operation effectively shifts each X-axis row of sections by -1 on the Z
     // axis. The "64" indices seen in this swizzle are used to fill the empty
     // space that the shift left over with zeroes.
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            1,  2,  3,  4,  5,  6,  7,  64,
-            9,  10, 11, 12, 13, 14, 15, 64,
-            17, 18, 19, 20, 21, 22, 23, 64,
-            25, 26, 27, 28, 29, 30, 31, 64,
-            33, 34, 35, 36, 37, 38, 39, 64,
-            41, 42, 43, 44, 45, 46, 47, 64,
-            49, 50, 51, 52, 53, 54, 55, 64,
-            57, 58, 59, 60, 61, 62, 63, 64,
-        ]
-    )
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::shift_neg_z(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::shift_neg_z(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                1,  2,  3,  4,  5,  6,  7,  64,
+                9,  10, 11, 12, 13, 14, 15, 64,
+                17, 18, 19, 20, 21, 22, 23, 64,
+                25, 26, 27, 28, 29, 30, 31, 64,
+                33, 34, 35, 36, 37, 38, 39, 64,
+                41, 42, 43, 44, 45, 46, 47, 64,
+                49, 50, 51, 52, 53, 54, 55, 64,
+                57, 58, 59, 60, 61, 62, 63, 64,
+            ]
+        ),
+    }
 }
 
 #[rustfmt::skip]
-fn shift_pos_z(sections: u8x64) -> u8x64 {
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            64, 0,  1,  2,  3,  4,  5,  6,
-            64, 8,  9,  10, 11, 12, 13, 14,
-            64, 16, 17, 18, 19, 20, 21, 22,
-            64, 24, 25, 26, 27, 28, 29, 30,
-            64, 32, 33, 34, 35, 36, 37, 38,
-            64, 40, 41, 42, 43, 44, 45, 46,
-            64, 48, 49, 50, 51, 52, 53, 54,
-            64, 56, 57, 58, 59, 60, 61, 62,
-        ]
-    )
+#[inline(always)]
+fn shift_pos_z<L: Level>(sections: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::shift_pos_z(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::shift_pos_z(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                64, 0,  1,  2,  3,  4,  5,  6,
+                64, 8,  9,  10, 11, 12, 13, 14,
+                64, 16, 17, 18, 19, 20, 21, 22,
+                64, 24, 25, 26, 27, 28, 29, 30,
+                64, 32, 33, 34, 35, 36, 37, 38,
+                64, 40, 41, 42, 43, 44, 45, 46,
+                64, 48, 49, 50, 51, 52, 53, 54,
+                64, 56, 57, 58, 59, 60, 61, 62,
+            ]
+        ),
+    }
 }
 
 #[rustfmt::skip]
-fn shift_neg_y(sections: u8x64) -> u8x64 {
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            8,  9,  10, 11, 12, 13, 14, 15,
-            16, 17, 18, 19, 20, 21, 22, 23,
-            24, 25, 26, 27, 28, 29, 30, 31,
-            32, 33, 34, 35, 36, 37, 38, 39,
-            40, 41, 42, 43, 44, 45, 46, 47,
-            48, 49, 50, 51, 52, 53, 54, 55,
-            56, 57, 58, 59, 60, 61, 62, 63,
-            64, 65, 66, 67, 68, 69, 70, 71,
-        ]
-    )
+#[inline(always)]
+fn shift_neg_y<L: Level>(sections: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::shift_neg_y(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::shift_neg_y(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                8,  9,  10, 11, 12, 13, 14, 15,
+                16, 17, 18, 19, 20, 21, 22, 23,
+                24, 25, 26, 27, 28, 29, 30, 31,
+                32, 33, 34, 35, 36, 37, 38, 39,
+                40, 41, 42, 43, 44, 45, 46, 47,
+                48, 49, 50, 51, 52, 53, 54, 55,
+                56, 57, 58, 59, 60, 61, 62, 63,
+                64, 65, 66, 67, 68, 69, 70, 71,
+            ]
+        ),
+    }
 }
 
 #[rustfmt::skip]
-fn shift_pos_y(sections: u8x64) -> u8x64 {
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            64, 65, 66, 67, 68, 69, 70, 71,
-            0,  1,  2,  3,  4,  5,  6,  7,
-            8,  9,  10, 11, 12, 13, 14, 15,
-            16, 17, 18, 19, 20, 21, 22, 23,
-            24, 25, 26, 27, 28, 29, 30, 31,
-            32, 33, 34, 35, 36, 37, 38, 39,
-            40, 41, 42, 43, 44, 45, 46, 47,
-            48, 49, 50, 51, 52, 53, 54, 55,
-        ]
-    )
+#[inline(always)]
+fn shift_pos_y<L: Level>(sections: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::shift_pos_y(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::shift_pos_y(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                64, 65, 66, 67, 68, 69, 70, 71,
+                0,  1,  2,  3,  4,  5,  6,  7,
+                8,  9,  10, 11, 12, 13, 14, 15,
+                16, 17, 18, 19, 20, 21, 22, 23,
+                24, 25, 26, 27, 28, 29, 30, 31,
+                32, 33, 34, 35, 36, 37, 38, 39,
+                40, 41, 42, 43, 44, 45, 46, 47,
+                48, 49, 50, 51, 52, 53, 54, 55,
+            ]
+        ),
+    }
 }
 
+#[inline(always)]
 pub fn edge_neg_to_pos_x(sections: u8x64) -> u8x64 {
     sections << 7
 }
 
+#[inline(always)]
 pub fn edge_pos_to_neg_x(sections: u8x64) -> u8x64 {
     sections >> 7
 }
 
 #[rustfmt::skip]
-pub fn edge_neg_to_pos_z(sections: u8x64) -> u8x64 {
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            64, 64, 64, 64, 64, 64, 64, 0,
-            64, 64, 64, 64, 64, 64, 64, 8,
-            64, 64, 64, 64, 64, 64, 64, 16,
-            64, 64, 64, 64, 64, 64, 64, 24,
-            64, 64, 64, 64, 64, 64, 64, 32,
-            64, 64, 64, 64, 64, 64, 64, 40,
-            64, 64, 64, 64, 64, 64, 64, 48,
-            64, 64, 64, 64, 64, 64, 64, 56,
-        ]
-    )
+#[inline(always)]
+pub fn edge_neg_to_pos_z<L: Level>(sections: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::edge_neg_to_pos_z(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::edge_neg_to_pos_z(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                64, 64, 64, 64, 64, 64, 64, 0,
+                64, 64, 64, 64, 64, 64, 64, 8,
+                64, 64, 64, 64, 64, 64, 64, 16,
+                64, 64, 64, 64, 64, 64, 64, 24,
+                64, 64, 64, 64, 64, 64, 64, 32,
+                64, 64, 64, 64, 64, 64, 64, 40,
+                64, 64, 64, 64, 64, 64, 64, 48,
+                64, 64, 64, 64, 64, 64, 64, 56,
+            ]
+        ),
+    }
 }
 
 #[rustfmt::skip]
-pub fn edge_pos_to_neg_z(sections: u8x64) -> u8x64 {
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            7,  64, 64, 64, 64, 64, 64, 64,
-            15, 64, 64, 64, 64, 64, 64, 64,
-            23, 64, 64, 64, 64, 64, 64, 64,
-            31, 64, 64, 64, 64, 64, 64, 64,
-            39, 64, 64, 64, 64, 64, 64, 64,
-            47, 64, 64, 64, 64, 64, 64, 64,
-            55, 64, 64, 64, 64, 64, 64, 64,
-            63, 64, 64, 64, 64, 64, 64, 64,
-        ]
-    )
+#[inline(always)]
+pub fn edge_pos_to_neg_z<L: Level>(sections: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::edge_pos_to_neg_z(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::edge_pos_to_neg_z(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                7,  64, 64, 64, 64, 64, 64, 64,
+                15, 64, 64, 64, 64, 64, 64, 64,
+                23, 64, 64, 64, 64, 64, 64, 64,
+                31, 64, 64, 64, 64, 64, 64, 64,
+                39, 64, 64, 64, 64, 64, 64, 64,
+                47, 64, 64, 64, 64, 64, 64, 64,
+                55, 64, 64, 64, 64, 64, 64, 64,
+                63, 64, 64, 64, 64, 64, 64, 64,
+            ]
+        ),
+    }
 }
 
 #[rustfmt::skip]
-pub fn edge_neg_to_pos_y(sections: u8x64) -> u8x64 {
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            64,  65,  66,  67,  68,  69,  70,  71,
-            72,  73,  74,  75,  76,  77,  78,  79,
-            80,  81,  82,  83,  84,  85,  86,  87,
-            88,  89,  90,  91,  92,  93,  94,  95,
-            96,  97,  98,  99,  100, 101, 102, 103,
-            104, 105, 106, 107, 108, 109, 110, 111,
-            112, 113, 114, 115, 116, 117, 118, 119,
-            0,   1,   2,   3,   4,   5,   6,   7,
-        ]
-    )
+#[inline(always)]
+pub fn edge_neg_to_pos_y<L: Level>(sections: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::edge_neg_to_pos_y(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::edge_neg_to_pos_y(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                64,  65,  66,  67,  68,  69,  70,  71,
+                72,  73,  74,  75,  76,  77,  78,  79,
+                80,  81,  82,  83,  84,  85,  86,  87,
+                88,  89,  90,  91,  92,  93,  94,  95,
+                96,  97,  98,  99,  100, 101, 102, 103,
+                104, 105, 106, 107, 108, 109, 110, 111,
+                112, 113, 114, 115, 116, 117, 118, 119,
+                0,   1,   2,   3,   4,   5,   6,   7,
+            ]
+        ),
+    }
 }
 
 #[rustfmt::skip]
-pub fn edge_pos_to_neg_y(sections: u8x64) -> u8x64 {
-    simd_swizzle!(
-        sections,
-        Simd::splat(0),
-        [
-            56,  57,  58,  59,  60,  61,  62,  63,
-            64,  65,  66,  67,  68,  69,  70,  71,
-            72,  73,  74,  75,  76,  77,  78,  79,
-            80,  81,  82,  83,  84,  85,  86,  87,
-            88,  89,  90,  91,  92,  93,  94,  95,
-            96,  97,  98,  99,  100, 101, 102, 103,
-            104, 105, 106, 107, 108, 109, 110, 111,
-            112, 113, 114, 115, 116, 117, 118, 119,
-        ]
-    )
+#[inline(always)]
+pub fn edge_pos_to_neg_y<L: Level>(sections: u8x64) -> u8x64 {
+    match () {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: The level is only allowed to enable AVX-512 if the CPU supports it.
+        _ if L::AVX512 => unsafe { avx512::edge_pos_to_neg_y(sections) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: The level is only allowed to enable NEON if the target has it.
+        _ if L::NEON => unsafe { neon::edge_pos_to_neg_y(sections) },
+        _ => simd_swizzle!(
+            sections,
+            Simd::splat(0),
+            [
+                56,  57,  58,  59,  60,  61,  62,  63,
+                64,  65,  66,  67,  68,  69,  70,  71,
+                72,  73,  74,  75,  76,  77,  78,  79,
+                80,  81,  82,  83,  84,  85,  86,  87,
+                88,  89,  90,  91,  92,  93,  94,  95,
+                96,  97,  98,  99,  100, 101, 102, 103,
+                104, 105, 106, 107, 108, 109, 110, 111,
+                112, 113, 114, 115, 116, 117, 118, 119,
+            ]
+        ),
+    }
 }
 
 pub fn gen_outward_direction_masks(camera_section_in_tile: u8x3) -> [u8x64; DIRECTION_COUNT] {
@@ -399,9 +517,12 @@ pub fn gen_outward_direction_masks(camera_section_in_tile: u8x3) -> [u8x64; DIRE
 
 #[cfg(test)]
 mod tests {
+    use std::array;
+
     use rand::prelude::*;
 
     use super::*;
+    use crate::simd::level::{Baseline, Portable};
     use crate::TESTS_RANDOM_SEED;
 
     #[test]
@@ -463,7 +584,7 @@ mod tests {
                     }
                 }
 
-                let dst_test_neg_to_pos_y = edge_neg_to_pos_y(src);
+                let dst_test_neg_to_pos_y = edge_neg_to_pos_y::<Baseline>(src);
 
                 assert_eq!(dst_sane_neg_to_pos_y, dst_test_neg_to_pos_y);
             }
@@ -481,7 +602,7 @@ mod tests {
                     }
                 }
 
-                let dst_test_pos_to_neg_y = edge_pos_to_neg_y(src);
+                let dst_test_pos_to_neg_y = edge_pos_to_neg_y::<Baseline>(src);
 
                 assert_eq!(dst_sane_pos_to_neg_y, dst_test_pos_to_neg_y);
             }
@@ -499,7 +620,7 @@ mod tests {
                     }
                 }
 
-                let dst_test_neg_to_pos_z = edge_neg_to_pos_z(src);
+                let dst_test_neg_to_pos_z = edge_neg_to_pos_z::<Baseline>(src);
 
                 assert_eq!(dst_sane_neg_to_pos_z, dst_test_neg_to_pos_z);
             }
@@ -517,7 +638,7 @@ mod tests {
                     }
                 }
 
-                let dst_test_pos_to_neg_z = edge_pos_to_neg_z(src);
+                let dst_test_pos_to_neg_z = edge_pos_to_neg_z::<Baseline>(src);
 
                 assert_eq!(dst_sane_pos_to_neg_z, dst_test_pos_to_neg_z);
             }
@@ -586,7 +707,7 @@ mod tests {
                     }
                 }
 
-                let dst_test_neg_y = shift_neg_y(src);
+                let dst_test_neg_y = shift_neg_y::<Baseline>(src);
 
                 assert_eq!(dst_sane_neg_y, dst_test_neg_y);
             }
@@ -605,7 +726,7 @@ mod tests {
                     }
                 }
 
-                let dst_test_pos_y = shift_pos_y(src);
+                let dst_test_pos_y = shift_pos_y::<Baseline>(src);
 
                 assert_eq!(dst_sane_pos_y, dst_test_pos_y);
             }
@@ -624,7 +745,7 @@ mod tests {
                     }
                 }
 
-                let dst_test_neg_z = shift_neg_z(src);
+                let dst_test_neg_z = shift_neg_z::<Baseline>(src);
 
                 assert_eq!(dst_sane_neg_z, dst_test_neg_z);
             }
@@ -643,13 +764,149 @@ mod tests {
                     }
                 }
 
-                let dst_test_pos_z = shift_pos_z(src);
+                let dst_test_pos_z = shift_pos_z::<Baseline>(src);
 
                 assert_eq!(dst_sane_pos_z, dst_test_pos_z);
             }
         }
     }
 
+    fn traverse_with_level<L: Level>(
+        tile: &Tile,
+        start_sections: u8x64,
+        incoming_dir_section_sets: [u8x64; DIRECTION_COUNT],
+        outward_direction_masks: &[u8x64; DIRECTION_COUNT],
+        angle_visibility_masks: &[u8x64; 3],
+        visibility_mask: u8x64,
+    ) -> (u8x64, [u8x64; DIRECTION_COUNT], [u8x64; DIRECTION_COUNT]) {
+        let mut tile = tile.clone();
+        let mut incoming_dir_section_sets = incoming_dir_section_sets;
+        let mut visible_sections = visibility_mask;
+
+        tile.traverse::<ALL_DIRECTIONS, L>(
+            start_sections,
+            &mut incoming_dir_section_sets,
+            outward_direction_masks,
+            angle_visibility_masks,
+            &mut visible_sections,
+        );
+
+        (
+            visible_sections,
+            incoming_dir_section_sets,
+            tile.outgoing_dir_section_sets,
+        )
+    }
+
+    #[test]
+    fn traverse_level_equivalence_test() {
+        const ITERATIONS: u32 = 2000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for _ in 0..ITERATIONS {
+            let random_sections = |rand: &mut StdRng| {
+                let mut sections = SECTIONS_EMPTY;
+                rand.fill_bytes(sections.as_mut_array());
+                sections
+            };
+
+            let mut tile = Tile::default();
+            for sections in &mut tile.connection_section_sets {
+                *sections = random_sections(&mut rand) | random_sections(&mut rand);
+            }
+            let incoming_dir_section_sets: [u8x64; DIRECTION_COUNT] =
+                array::from_fn(|_| random_sections(&mut rand) & random_sections(&mut rand));
+            let start_sections = incoming_dir_section_sets
+                .iter()
+                .fold(SECTIONS_EMPTY, |a, b| a | b);
+            let camera_section_in_tile = u8x3::from_xyz(
+                rand.random_range(0..8),
+                rand.random_range(0..8),
+                rand.random_range(0..8),
+            );
+            let outward_direction_masks = gen_outward_direction_masks(camera_section_in_tile);
+            let angle_visibility_masks: [u8x64; 3] =
+                array::from_fn(|_| random_sections(&mut rand) | random_sections(&mut rand));
+            let visibility_mask = !(random_sections(&mut rand) & random_sections(&mut rand));
+
+            let portable = traverse_with_level::<Portable>(
+                &tile,
+                start_sections,
+                incoming_dir_section_sets,
+                &outward_direction_masks,
+                &angle_visibility_masks,
+                visibility_mask,
+            );
+
+            for level in SimdLevel::supported() {
+                let result = with_level!(level, L => traverse_with_level::<L>(
+                    &tile,
+                    start_sections,
+                    incoming_dir_section_sets,
+                    &outward_direction_masks,
+                    &angle_visibility_masks,
+                    visibility_mask,
+                ));
+
+                assert_eq!(portable, result, "portable != {level}");
+            }
+        }
+    }
+
+    fn moves<L: Level>(sections: u8x64) -> [u8x64; 8] {
+        [
+            shift_neg_y::<L>(sections),
+            shift_neg_z::<L>(sections),
+            shift_pos_y::<L>(sections),
+            shift_pos_z::<L>(sections),
+            edge_neg_to_pos_y::<L>(sections),
+            edge_neg_to_pos_z::<L>(sections),
+            edge_pos_to_neg_y::<L>(sections),
+            edge_pos_to_neg_z::<L>(sections),
+        ]
+    }
+
+    fn or_changed_with_level<L: Level>(sections: u8x64, added: u8x64) -> (u8x64, bool) {
+        let mut sections = sections;
+        let changed = or_changed::<L>(&mut sections, added);
+        (sections, changed)
+    }
+
+    #[test]
+    fn moves_level_equivalence_test() {
+        const ITERATIONS: u32 = 10000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for _ in 0..ITERATIONS {
+            let mut src = u8x64::splat(0);
+            rand.fill_bytes(src.as_mut_array());
+            // sparse sets, so both outcomes of or_changed are covered
+            let mut added = u8x64::splat(0);
+            for _ in 0..rand.random_range(0..4) {
+                set_bit(&mut added, rand.random_range(0..512));
+            }
+
+            let portable_moves = moves::<Portable>(src);
+            let portable_or = or_changed_with_level::<Portable>(src, added);
+
+            for level in SimdLevel::supported() {
+                // SAFETY: The level is supported by the CPU.
+                let (level_moves, level_or) = with_level!(level, L => unsafe {
+                    L::dispatch(|| (moves::<L>(src), or_changed_with_level::<L>(src, added)))
+                });
+
+                assert_eq!(
+                    portable_moves, level_moves,
+                    "portable != {level}, Input: {src:?}"
+                );
+                assert_eq!(
+                    portable_or, level_or,
+                    "portable != {level}, Input: {src:?}, Added: {added:?}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn outward_direction_mask_test() {
         for camera_x in 0..8 {
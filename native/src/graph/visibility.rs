@@ -45,3 +45,24 @@ pub const fn connection_index(dir_1: u8, dir_2: u8) -> usize {
 
     (large_idx * 4) + small_idx + (0b1100 >> large_idx) - 10
 }
+
+/// Creates visibility data where every face in `faces` is connected to every
+/// other face in `faces`, where `faces` is a set of Minecraft direction
+/// ordinals.
+pub const fn connect_faces(faces: u8) -> u64 {
+    let mut visibility_data = 0;
+
+    let mut from = DOWN;
+    while from <= EAST {
+        let mut to = DOWN;
+        while to <= EAST {
+            if from != to && (faces >> from) & 1 != 0 && (faces >> to) & 1 != 0 {
+                visibility_data |= 1 << (from * 8 + to);
+            }
+            to += 1;
+        }
+        from += 1;
+    }
+
+    visibility_data
+}
@@ -0,0 +1,149 @@
+//! Handles for objects that are owned by the native library and referred to
+//! from across the FFI boundary. Unlike raw pointers, a handle can be checked
+//! before it is used, so a handle that was already deleted is reported as an
+//! error rather than touching freed memory.
+
+/// Identifies an object in a [`HandleTable`]. The low 32 bits are the index of
+/// the object's slot, and the high 32 bits are the generation of the slot when
+/// the object was inserted. Generations start at 1, so 0 is never a valid
+/// handle.
+pub type Handle = u64;
+
+/// A table of objects that can be looked up by [`Handle`]. Removing an object
+/// bumps the generation of its slot, so any handles to it stay invalid after
+/// the slot is reused.
+pub struct HandleTable<T> {
+    slots: Vec<Slot<T>>,
+    free_slots: Vec<u32>,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+impl<T> HandleTable<T> {
+    pub const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+        }
+    }
+
+    /// Adds an object to the table and returns its handle.
+    ///
+    /// # Panics
+    /// If the table already has `u32::MAX` slots.
+    pub fn insert(&mut self, value: T) -> Handle {
+        let index = if let Some(index) = self.free_slots.pop() {
+            self.slots[index as usize].value = Some(value);
+            index
+        } else {
+            let index = u32::try_from(self.slots.len()).expect("handle table is full");
+            self.slots.push(Slot {
+                generation: 1,
+                value: Some(value),
+            });
+            index
+        };
+
+        Self::handle(index, self.slots[index as usize].generation)
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        let (index, generation) = Self::split(handle);
+
+        self.slots
+            .get(index as usize)
+            .filter(|slot| slot.generation == generation)?
+            .value
+            .as_ref()
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        let (index, generation) = Self::split(handle);
+
+        self.slots
+            .get_mut(index as usize)
+            .filter(|slot| slot.generation == generation)?
+            .value
+            .as_mut()
+    }
+
+    /// Removes the object from the table and returns it, or returns `None` if
+    /// the handle is invalid or was already removed.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let (index, generation) = Self::split(handle);
+
+        let slot = self
+            .slots
+            .get_mut(index as usize)
+            .filter(|slot| slot.generation == generation)?;
+        let value = slot.value.take()?;
+
+        // skip 0 when wrapping around, so it stays invalid
+        slot.generation = slot.generation.checked_add(1).unwrap_or(1);
+        self.free_slots.push(index);
+
+        Some(value)
+    }
+
+    /// Returns the number of objects in the table.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free_slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn handle(index: u32, generation: u32) -> Handle {
+        ((generation as u64) << 32) | index as u64
+    }
+
+    fn split(handle: Handle) -> (u32, u32) {
+        (handle as u32, (handle >> 32) as u32)
+    }
+}
+
+impl<T> Default for HandleTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_table_test() {
+        let mut table = HandleTable::new();
+        assert_eq!(table.get(0), None::<&&str>);
+
+        let first = table.insert("first");
+        let second = table.insert("second");
+        assert_ne!(first, 0);
+        assert_ne!(first, second);
+        assert_eq!(table.get(first), Some(&"first"));
+        assert_eq!(table.get(second), Some(&"second"));
+        assert_eq!(table.len(), 2);
+
+        assert_eq!(table.remove(first), Some("first"));
+        // use after removal and double removal
+        assert_eq!(table.get(first), None);
+        assert_eq!(table.get_mut(first), None);
+        assert_eq!(table.remove(first), None);
+        assert_eq!(table.len(), 1);
+
+        // the slot is reused with a new generation, so the old handle stays invalid
+        let third = table.insert("third");
+        assert_eq!(third as u32, first as u32);
+        assert_ne!(third, first);
+        assert_eq!(table.get(first), None);
+        assert_eq!(table.get(third), Some(&"third"));
+
+        // an index that was never allocated
+        assert_eq!(table.get((1 << 32) | 100), None);
+    }
+}
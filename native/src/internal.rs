@@ -0,0 +1,57 @@
+//! Re-exports of internal items used by the benchmarks in `benches/` and the
+//! command-line tool. This is only available with the `bench` or `cli`
+//! features, and isn't part of the public API.
+
+pub use crate::api::{BoundingBox, SearchOptions};
+pub use crate::ffi::{FFISearchOptions, FFITile};
+pub use crate::graph::context::GraphSearchContext;
+pub use crate::graph::coords::SectionGeometry;
+pub use crate::graph::direction::{ALL_ORDINALS, DOWN, EAST, NORTH, SOUTH, UP, WEST};
+#[cfg(feature = "cli")]
+pub use crate::graph::reference;
+pub use crate::graph::tile::fog::FogShape;
+pub use crate::graph::tile::frustum::{perspective_planes, Frustum};
+pub use crate::graph::tile::write_layers;
+pub use crate::graph::visibility::connect_faces;
+pub use crate::graph::Graph;
+pub use crate::math::*;
+pub use crate::simd::prelude::*;
+
+// The kernels are generic over a level type, which can't be named outside of
+// the crate, so these take the level as a value instead.
+
+pub fn gen_visibility_masks(
+    level: SimdLevel,
+    relative_tile_pos: f32x3,
+    geometry: SectionGeometry,
+) -> [u8x64; 3] {
+    assert!(level.is_supported());
+    with_level!(level, L => crate::graph::tile::angle::gen_visibility_masks::<L>(relative_tile_pos, geometry))
+}
+
+pub fn voxelize_fog(
+    level: SimdLevel,
+    fog_shape: FogShape,
+    relative_tile_pos: f32x3,
+    fog_distance: f32,
+    vertical_fog_distance: f32,
+    geometry: SectionGeometry,
+) -> u8x64 {
+    assert!(level.is_supported());
+    with_level!(level, L => crate::graph::tile::fog::voxelize::<L>(fog_shape, relative_tile_pos, fog_distance, vertical_fog_distance, geometry))
+}
+
+pub fn voxelize_planes(
+    level: SimdLevel,
+    frustum: &Frustum,
+    intersecting_planes: u8,
+    relative_tile_pos: f32x3,
+    visible_sections: &mut u8x64,
+) {
+    assert!(level.is_supported());
+    with_level!(level, L => frustum.voxelize_planes::<L>(intersecting_planes, relative_tile_pos, visible_sections))
+}
+
+pub mod trace {
+    pub use crate::trace::*;
+}
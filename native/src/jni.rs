@@ -0,0 +1,219 @@
+//! The JNI entry points for `HyperCullNativeLib`. Each of these forwards to the
+//! function with the same name in the C API in `ffi`, ignoring the JNI
+//! environment and class pointers that the JVM passes in.
+
+#![allow(non_snake_case)]
+
+use std::ffi::c_void;
+
+use crate::ffi::{
+    self, FFIBoundingBox, FFIBuildInfo, FFICamera, FFILayout, FFISearchOptions,
+    FFISectionDiagnosis, FFISlice, FFITile, FFITileCapture, GraphHandle,
+};
+use crate::logging::LogHandlerFn;
+use crate::panic::PanicHandlerFn;
+
+type JNIEnv = c_void;
+type JClass = c_void;
+
+#[no_mangle]
+pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_getAbiVersion(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+) -> u32 {
+    ffi::hypercull_get_abi_version()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_getLayout(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut FFILayout,
+) -> u8 {
+    ffi::hypercull_get_layout(return_value_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_getBuildInfo(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut FFIBuildInfo,
+) -> u8 {
+    ffi::hypercull_get_build_info(return_value_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_setPanicHandler(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    panic_handler_fn_ptr: PanicHandlerFn,
+) {
+    ffi::hypercull_set_panic_handler(panic_handler_fn_ptr)
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_setLogHandler(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    log_handler_fn_ptr: LogHandlerFn,
+    level: u8,
+) {
+    ffi::hypercull_set_log_handler(log_handler_fn_ptr, level)
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_setLogLevel(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    level: u8,
+) {
+    ffi::hypercull_set_log_level(level)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphCreate(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut GraphHandle,
+    render_distance: u8,
+    world_bottom_section_y: i8,
+    world_top_section_y: i8,
+) -> u8 {
+    ffi::hypercull_graph_create(
+        return_value_ptr,
+        render_distance,
+        world_bottom_section_y,
+        world_top_section_y,
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphSetSection(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    graph_handle: GraphHandle,
+    x: i32,
+    y: i32,
+    z: i32,
+    visibility_bitmask: u64,
+) -> u8 {
+    ffi::hypercull_graph_set_section(graph_handle, x, y, z, visibility_bitmask)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphSearch(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut FFISlice<FFITile>,
+    graph_handle: GraphHandle,
+    camera_ptr: *const FFICamera,
+    options_ptr: *const FFISearchOptions,
+) -> u8 {
+    ffi::hypercull_graph_search_with_options(
+        return_value_ptr,
+        graph_handle,
+        camera_ptr,
+        options_ptr,
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphDelete(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    graph_handle: GraphHandle,
+) -> u8 {
+    ffi::hypercull_graph_delete(graph_handle)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphGetSimdLevel(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut u8,
+    graph_handle: GraphHandle,
+) -> u8 {
+    ffi::hypercull_graph_get_simd_level(return_value_ptr, graph_handle)
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphCaptureNextSearch(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    graph_handle: GraphHandle,
+) -> u8 {
+    ffi::hypercull_graph_capture_next_search(graph_handle)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphGetCapture(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut FFISlice<FFITileCapture>,
+    graph_handle: GraphHandle,
+) -> u8 {
+    ffi::hypercull_graph_get_capture(return_value_ptr, graph_handle)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphWriteCapture(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    graph_handle: GraphHandle,
+    path_ptr: *const u8,
+    path_len: usize,
+) -> u8 {
+    ffi::hypercull_graph_write_capture(graph_handle, path_ptr, path_len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphDiagnoseSection(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut FFISectionDiagnosis,
+    graph_handle: GraphHandle,
+    x: i32,
+    y: i32,
+    z: i32,
+) -> u8 {
+    ffi::hypercull_graph_diagnose_section(return_value_ptr, graph_handle, x, y, z)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_graphTestBoxes(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    results_ptr: *mut bool,
+    graph_handle: GraphHandle,
+    boxes_ptr: *const FFIBoundingBox,
+    count: u32,
+) -> u8 {
+    ffi::hypercull_graph_test_boxes(results_ptr, graph_handle, boxes_ptr, count)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_errorGetMessage(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    return_value_ptr: *mut FFISlice<u8>,
+) {
+    ffi::hypercull_error_get_message(return_value_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_traceStart(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+    path_ptr: *const u8,
+    path_len: usize,
+) -> u8 {
+    ffi::hypercull_trace_start(path_ptr, path_len)
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_github_burgerindividual_hypercull_client_ffi_HyperCullNativeLib_traceStop(
+    _: *mut JNIEnv,
+    _: *mut JClass,
+) -> u8 {
+    ffi::hypercull_trace_stop()
+}
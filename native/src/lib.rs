@@ -1,11 +1,56 @@
-#![feature(portable_simd)]
+//! Tile-based culling for Minecraft-style section graphs.
+//!
+//! A [`Graph`] stores which faces of each loaded section can see each other,
+//! and searches it from a [`Camera`] to find the sections that are visible,
+//! combining frustum, fog, world height, angle and occlusion culling.
+//!
+//! ```
+//! use hypercull_native::{Camera, Graph, SearchOptions};
+//!
+//! let mut graph = Graph::new(12, -4, 19)?;
+//! graph.set_section([0, 4, 0], !0)?;
+//!
+//! let camera = Camera {
+//!     frustum_planes: [[0.0, 0.0, 0.0, 1.0]; 6],
+//!     pos: [8.5, 72.5, 8.5],
+//! };
+//! let results = graph.search(&camera, &SearchOptions::new(192.0))?;
+//!
+//! for section_coords in results.sections() {
+//!     println!("{section_coords:?} is visible");
+//! }
+//! # Ok::<(), hypercull_native::GraphError>(())
+//! ```
+//!
+//! The same functionality is exported as a C API, which is declared in
+//! `include/hypercull.h`.
+
 #![allow(dead_code)]
 
+mod api;
 mod bitset;
 mod ffi;
 mod graph;
+mod handle;
+#[cfg(any(feature = "bench", feature = "cli"))]
+#[doc(hidden)]
+pub mod internal;
+mod jni;
+mod logging;
 mod math;
 mod panic;
+mod simd;
+mod trace;
+
+pub use api::{
+    BoundingBox, Camera, Graph, SearchOptions, SearchResults, VisibleSections, VisibleTile,
+    VisibleTiles,
+};
+pub use graph::coords::SectionGeometry;
+pub use graph::diagnosis::SectionDiagnosis;
+pub use graph::error::GraphError;
+pub use graph::tile::fog::FogShape;
+pub use simd::level::SimdLevel;
 
 #[cfg(test)]
 pub const TESTS_RANDOM_SEED: u64 = 0x1c41cf821df0e3a9;
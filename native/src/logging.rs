@@ -0,0 +1,95 @@
+use std::sync::OnceLock;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+pub type LogHandlerFn = extern "C" fn(
+    level: u8,
+    target_ptr: *const u8,
+    target_len: i32,
+    message_ptr: *const u8,
+    message_len: i32,
+);
+
+static EXTERNAL_LOG_HANDLER: OnceLock<LogHandlerFn> = OnceLock::new();
+
+// the levels are numbered the same way as LevelFilter, so the most verbose
+// level has the highest number
+pub const LEVEL_OFF: u8 = 0;
+pub const LEVEL_ERROR: u8 = 1;
+pub const LEVEL_WARN: u8 = 2;
+pub const LEVEL_INFO: u8 = 3;
+pub const LEVEL_DEBUG: u8 = 4;
+pub const LEVEL_TRACE: u8 = 5;
+
+struct ExternalLogger;
+
+static LOGGER: ExternalLogger = ExternalLogger;
+
+impl Log for ExternalLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let Some(log_handler_fn) = EXTERNAL_LOG_HANDLER.get() else {
+            return;
+        };
+
+        let target = record.target();
+        let message = record.args().to_string();
+
+        log_handler_fn(
+            record.level() as u8,
+            target.as_ptr(),
+            target.len() as i32,
+            message.as_ptr(),
+            message.len() as i32,
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Forwards every log record at or below the given level to the handler. The
+/// handler can only be set once, but the level can be changed at any time with
+/// `set_level`.
+pub fn set_log_handler(log_handler_fn_ptr: LogHandlerFn, level: u8) {
+    // like with the panic handler, there's nothing we can do if this fails
+    let _ = EXTERNAL_LOG_HANDLER.set(log_handler_fn_ptr);
+    let _ = log::set_logger(&LOGGER);
+    set_level(level);
+}
+
+/// Sets the most verbose level that will be logged. Levels above `LEVEL_TRACE`
+/// are treated as `LEVEL_TRACE`.
+pub fn set_level(level: u8) {
+    log::set_max_level(level_filter(level));
+}
+
+fn level_filter(level: u8) -> LevelFilter {
+    LevelFilter::iter()
+        .nth(level as usize)
+        .unwrap_or(LevelFilter::Trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_filter_test() {
+        assert_eq!(level_filter(LEVEL_OFF), LevelFilter::Off);
+        assert_eq!(level_filter(LEVEL_ERROR), LevelFilter::Error);
+        assert_eq!(level_filter(LEVEL_WARN), LevelFilter::Warn);
+        assert_eq!(level_filter(LEVEL_INFO), LevelFilter::Info);
+        assert_eq!(level_filter(LEVEL_DEBUG), LevelFilter::Debug);
+        assert_eq!(level_filter(LEVEL_TRACE), LevelFilter::Trace);
+        assert_eq!(level_filter(u8::MAX), LevelFilter::Trace);
+
+        assert_eq!(log::Level::Warn as u8, LEVEL_WARN);
+    }
+}
@@ -1,8 +1,8 @@
 #![allow(non_camel_case_types)]
 
-use core_simd::simd::prelude::*;
-use core_simd::simd::*;
-use std_float::StdFloat;
+use crate::simd::level::Level;
+use crate::simd::prelude::*;
+use crate::simd::{MaskElement, SimdElement};
 
 pub const X: usize = 0;
 pub const Y: usize = 1;
@@ -46,16 +46,15 @@ where
 }
 
 pub trait MulAddFast {
-    fn mul_add_fast(self, mul: Self, add: Self) -> Self;
+    /// Computes `(self * mul) + add`, fusing the operations if that's fast on
+    /// the level.
+    fn mul_add_fast<L: Level>(self, mul: Self, add: Self) -> Self;
 }
 
-impl<const LANES: usize> MulAddFast for Simd<f32, LANES>
-where
-    LaneCount<LANES>: SupportedLaneCount,
-{
-    fn mul_add_fast(self, mul: Self, add: Self) -> Self {
-        // this could probably have better detection
-        if cfg!(target_feature = "fma") || cfg!(target_feature = "neon") {
+impl<const LANES: usize> MulAddFast for Simd<f32, LANES> {
+    #[inline(always)]
+    fn mul_add_fast<L: Level>(self, mul: Self, add: Self) -> Self {
+        if L::FAST_FMA {
             self.mul_add(mul, add)
         } else {
             self * mul + add
@@ -63,13 +62,10 @@ where
     }
 }
 
-impl<const LANES: usize> MulAddFast for Simd<f64, LANES>
-where
-    LaneCount<LANES>: SupportedLaneCount,
-{
-    fn mul_add_fast(self, mul: Self, add: Self) -> Self {
-        // this could probably have better detection
-        if cfg!(target_feature = "fma") || cfg!(target_feature = "neon") {
+impl MulAddFast for f32 {
+    #[inline(always)]
+    fn mul_add_fast<L: Level>(self, mul: Self, add: Self) -> Self {
+        if L::FAST_FMA {
             self.mul_add(mul, add)
         } else {
             self * mul + add
@@ -77,37 +73,43 @@ where
     }
 }
 
-impl MulAddFast for f32 {
-    fn mul_add_fast(self, mul: Self, add: Self) -> Self {
-        // this could probably have better detection
-        if cfg!(target_feature = "fma") || cfg!(target_feature = "neon") {
-            self.mul_add(mul, add)
+pub trait RoundFast {
+    fn floor_fast<L: Level>(self) -> Self;
+    fn ceil_fast<L: Level>(self) -> Self;
+}
+
+impl<const LANES: usize> RoundFast for Simd<f32, LANES> {
+    // When the level has instructions for rounding, LLVM vectorizes the lane-wise
+    // version into them. Otherwise, the backend's emulation is faster than libm.
+    #[inline(always)]
+    fn floor_fast<L: Level>(self) -> Self {
+        if L::FAST_ROUND {
+            self.map(f32::floor)
         } else {
-            self * mul + add
+            self.floor()
         }
     }
-}
 
-impl MulAddFast for f64 {
-    fn mul_add_fast(self, mul: Self, add: Self) -> Self {
-        // this could probably have better detection
-        if cfg!(target_feature = "fma") || cfg!(target_feature = "neon") {
-            self.mul_add(mul, add)
+    #[inline(always)]
+    fn ceil_fast<L: Level>(self) -> Self {
+        if L::FAST_ROUND {
+            self.map(f32::ceil)
         } else {
-            self * mul + add
+            self.ceil()
         }
     }
 }
 
-pub trait SignFast: SimdFloat {
+pub trait SignFast: Copy {
+    type Mask;
+
     fn is_sign_positive_fast(self) -> Self::Mask;
     fn is_sign_negative_fast(self) -> Self::Mask;
 }
 
-impl<const LANES: usize> SignFast for Simd<f32, LANES>
-where
-    LaneCount<LANES>: SupportedLaneCount,
-{
+impl<const LANES: usize> SignFast for Simd<f32, LANES> {
+    type Mask = Mask<i32, LANES>;
+
     fn is_sign_positive_fast(self) -> Self::Mask {
         (self.to_bits() & Simd::splat(F32_SIGN_BIT)).simd_eq(Simd::splat(0))
     }
@@ -123,10 +125,7 @@ pub trait SimdOrdFast {
     fn simd_clamp_fast(self, min: Self, max: Self) -> Self;
 }
 
-impl<const LANES: usize> SimdOrdFast for Simd<f32, LANES>
-where
-    LaneCount<LANES>: SupportedLaneCount,
-{
+impl<const LANES: usize> SimdOrdFast for Simd<f32, LANES> {
     fn simd_min_fast(self, other: Self) -> Self {
         self.simd_lt(other).select(self, other)
     }
@@ -147,10 +146,7 @@ pub trait RemEuclid {
     fn rem_euclid(self, rhs: Self) -> Self;
 }
 
-impl<const LANES: usize> RemEuclid for Simd<i32, LANES>
-where
-    LaneCount<LANES>: SupportedLaneCount,
-{
+impl<const LANES: usize> RemEuclid for Simd<i32, LANES> {
     fn rem_euclid(self, rhs: Self) -> Self {
         let lhs_f = self.cast::<f64>();
         let rhs_f = rhs.cast::<f64>();
@@ -1,4 +1,4 @@
-use std::io::{Cursor, Write};
+use std::io::{self, Cursor, Write};
 use std::panic::{self, PanicHookInfo};
 use std::sync::OnceLock;
 
@@ -6,6 +6,10 @@ pub type PanicHandlerFn = extern "C" fn(data: *const u8, len: i32) -> !;
 
 static EXTERNAL_PANIC_HANDLER: OnceLock<PanicHandlerFn> = OnceLock::new();
 
+// reports that fit in this are written to the stack, so panics caused by failed
+// allocations can still be reported
+const STACK_BUFFER_SIZE: usize = 5000;
+
 pub fn set_panic_handler(panic_handler_fn_ptr: PanicHandlerFn) {
     // there's nothing we can do if this fails, so we just ignore the result and
     // hope it succeeded
@@ -14,23 +18,218 @@ pub fn set_panic_handler(panic_handler_fn_ptr: PanicHandlerFn) {
 }
 
 fn panic_hook(info: &PanicHookInfo) {
-    let mut buffer = [0_u8; 5000];
-    let mut cursor = Cursor::new(buffer.as_mut());
-    let _ = write!(cursor, "{info}"); // we can't panic if this fails, so we ignore the result
+    // we can't really do anything if the panic handler function isn't populated
+    let Some(panic_handler_fn) = EXTERNAL_PANIC_HANDLER.get() else {
+        return;
+    };
 
     #[cfg(feature = "backtrace")]
-    {
-        let _ = write!(
-            cursor,
-            "\nBacktrace:\n{}",
-            std::backtrace::Backtrace::force_capture()
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+    #[cfg(not(feature = "backtrace"))]
+    let backtrace = String::new();
+
+    let current_thread = std::thread::current();
+    let report = PanicReport {
+        message: payload_as_str(info).unwrap_or("Box<dyn Any>"),
+        location: info
+            .location()
+            .map(|location| (location.file(), location.line(), location.column())),
+        thread_name: current_thread.name().unwrap_or("<unnamed>"),
+        frames: parse_backtrace(&backtrace),
+    };
+
+    let report_len = report.serialized_len();
+    if report_len <= STACK_BUFFER_SIZE {
+        let mut buffer = [0_u8; STACK_BUFFER_SIZE];
+        // we can't panic if this fails, so we ignore the result
+        let _ = report.write_to(&mut Cursor::new(buffer.as_mut()));
+        panic_handler_fn(buffer.as_ptr(), report_len as i32);
+    } else {
+        let mut buffer = Vec::with_capacity(report_len);
+        let _ = report.write_to(&mut buffer);
+        panic_handler_fn(buffer.as_ptr(), buffer.len() as i32);
+    }
+}
+
+fn payload_as_str<'a>(info: &'a PanicHookInfo) -> Option<&'a str> {
+    let payload = info.payload();
+
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
+
+/// A description of a panic, which is serialized and passed to the Java panic
+/// handler.
+///
+/// Every integer is little-endian, and every string is a `u32` byte length
+/// followed by that many bytes of UTF-8. The report is laid out as:
+/// - the message
+/// - the location's file, line, and column, where an empty file means the
+///   location is unknown
+/// - the name of the thread that panicked
+/// - a `u32` frame count, followed by each frame's function, file, and line,
+///   where an empty file means the location is unknown
+#[derive(Debug, PartialEq)]
+pub struct PanicReport<'a> {
+    pub message: &'a str,
+    pub location: Option<(&'a str, u32, u32)>,
+    pub thread_name: &'a str,
+    pub frames: Vec<BacktraceFrame<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BacktraceFrame<'a> {
+    pub function: &'a str,
+    pub location: Option<(&'a str, u32)>,
+}
+
+impl PanicReport<'_> {
+    pub fn serialized_len(&self) -> usize {
+        const STRING_HEADER_LEN: usize = size_of::<u32>();
+        const INT_LEN: usize = size_of::<u32>();
+
+        let location_file_len = self.location.map_or(0, |(file, _, _)| file.len());
+        let frames_len = self
+            .frames
+            .iter()
+            .map(|frame| {
+                let file_len = frame.location.map_or(0, |(file, _)| file.len());
+                (STRING_HEADER_LEN * 2) + frame.function.len() + file_len + INT_LEN
+            })
+            .sum::<usize>();
+
+        (STRING_HEADER_LEN + self.message.len())
+            + (STRING_HEADER_LEN + location_file_len + (INT_LEN * 2))
+            + (STRING_HEADER_LEN + self.thread_name.len())
+            + (INT_LEN + frames_len)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (file, line, column) = self.location.unwrap_or(("", 0, 0));
+
+        write_string(writer, self.message)?;
+        write_string(writer, file)?;
+        writer.write_all(&line.to_le_bytes())?;
+        writer.write_all(&column.to_le_bytes())?;
+        write_string(writer, self.thread_name)?;
+
+        writer.write_all(&(self.frames.len() as u32).to_le_bytes())?;
+        for frame in &self.frames {
+            let (file, line) = frame.location.unwrap_or(("", 0));
+
+            write_string(writer, frame.function)?;
+            write_string(writer, file)?;
+            writer.write_all(&line.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_string<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    writer.write_all(&(string.len() as u32).to_le_bytes())?;
+    writer.write_all(string.as_bytes())
+}
+
+/// Parses the output of `Backtrace`'s `Display` implementation, which has a
+/// line with the index and function of each frame, optionally followed by a
+/// line with its location:
+///
+/// ```text
+///    3: hypercull_native::graph::Graph::cull
+///              at ./src/graph/mod.rs:240:9
+/// ```
+///
+/// Lines that don't match this format are skipped.
+pub fn parse_backtrace(backtrace: &str) -> Vec<BacktraceFrame<'_>> {
+    let mut frames = Vec::<BacktraceFrame>::new();
+
+    for line in backtrace.lines().map(str::trim) {
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                frame.location = parse_frame_location(location);
+            }
+        } else if let Some((index, function)) = line.split_once(": ") {
+            if !index.is_empty() && index.bytes().all(|byte| byte.is_ascii_digit()) {
+                frames.push(BacktraceFrame {
+                    function,
+                    location: None,
+                });
+            }
+        }
+    }
+
+    frames
+}
+
+// the location is formatted as "file:line:column", where the file can contain
+// colons
+fn parse_frame_location(location: &str) -> Option<(&str, u32)> {
+    let (rest, _column) = location.rsplit_once(':')?;
+    let (file, line) = rest.rsplit_once(':')?;
+
+    Some((file, line.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKTRACE: &str = "   0: std::backtrace::Backtrace::force_capture
+             at /rustc/abc/library/std/src/backtrace.rs:312:13
+   1: hypercull_native::graph::Graph::cull
+             at ./src/graph/mod.rs:240:9
+   2: <unknown>
+   3: hypercull_native::ffi::search
+             at C:\\Users\\me\\src\\ffi.rs:12:1
+";
+
+    #[test]
+    fn parse_backtrace_test() {
+        let frames = parse_backtrace(BACKTRACE);
+
+        assert_eq!(
+            frames,
+            [
+                BacktraceFrame {
+                    function: "std::backtrace::Backtrace::force_capture",
+                    location: Some(("/rustc/abc/library/std/src/backtrace.rs", 312)),
+                },
+                BacktraceFrame {
+                    function: "hypercull_native::graph::Graph::cull",
+                    location: Some(("./src/graph/mod.rs", 240)),
+                },
+                BacktraceFrame {
+                    function: "<unknown>",
+                    location: None,
+                },
+                BacktraceFrame {
+                    function: "hypercull_native::ffi::search",
+                    location: Some(("C:\\Users\\me\\src\\ffi.rs", 12)),
+                },
+            ]
         );
     }
 
-    let length = cursor.position();
+    #[test]
+    fn serialized_len_test() {
+        let long_message = "a".repeat(STACK_BUFFER_SIZE);
 
-    // we can't really do anything if the panic handler function isn't populated
-    if let Some(panic_handler_fn) = EXTERNAL_PANIC_HANDLER.get() {
-        panic_handler_fn(buffer.as_ptr(), length as i32);
+        for message in ["short message", long_message.as_str()] {
+            for location in [None, Some(("src/graph/mod.rs", 10, 5))] {
+                let report = PanicReport {
+                    message,
+                    location,
+                    thread_name: "Render thread",
+                    frames: parse_backtrace(BACKTRACE),
+                };
+
+                let mut buffer = Vec::new();
+                report.write_to(&mut buffer).unwrap();
+                assert_eq!(buffer.len(), report.serialized_len());
+            }
+        }
     }
 }
@@ -0,0 +1,106 @@
+use std::arch::aarch64::*;
+use std::mem::transmute;
+
+use super::{Backend, Simd};
+
+/// The aarch64 backend, which uses NEON. NEON is part of the aarch64 baseline,
+/// so this works on every aarch64 CPU.
+pub struct Neon;
+
+impl Backend for Neon {
+    #[inline(always)]
+    fn floor_f32x4(value: [f32; 4]) -> [f32; 4] {
+        // SAFETY: Arrays of 4 f32s and float32x4_t have the same size, and any bit
+        // pattern is valid for both.
+        unsafe {
+            let value = transmute::<[f32; 4], float32x4_t>(value);
+            transmute::<float32x4_t, [f32; 4]>(vrndmq_f32(value))
+        }
+    }
+
+    #[inline(always)]
+    fn ceil_f32x4(value: [f32; 4]) -> [f32; 4] {
+        // SAFETY: See floor_f32x4.
+        unsafe {
+            let value = transmute::<[f32; 4], float32x4_t>(value);
+            transmute::<float32x4_t, [f32; 4]>(vrndpq_f32(value))
+        }
+    }
+
+    #[inline(always)]
+    fn mul_add_f32x4(value: [f32; 4], mul: [f32; 4], add: [f32; 4]) -> [f32; 4] {
+        // SAFETY: See floor_f32x4.
+        unsafe {
+            transmute::<float32x4_t, [f32; 4]>(vfmaq_f32(
+                transmute::<[f32; 4], float32x4_t>(add),
+                transmute::<[f32; 4], float32x4_t>(value),
+                transmute::<[f32; 4], float32x4_t>(mul),
+            ))
+        }
+    }
+
+    // NEON doesn't have a movemask instruction, so we shift each sign bit into its
+    // position in the bitmask and add the lanes together.
+    #[inline(always)]
+    fn bitmask_i8x16(value: [i8; 16]) -> u16 {
+        const BIT_POSITIONS: [i8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3, 4, 5, 6, 7];
+
+        // SAFETY: Arrays of 16 i8s and the 128-bit vector types have the same size,
+        // and any bit pattern is valid for all of them.
+        unsafe {
+            let sign_bits = vshrq_n_u8::<7>(transmute::<[i8; 16], uint8x16_t>(value));
+            let bits = vshlq_u8(sign_bits, transmute::<[i8; 16], int8x16_t>(BIT_POSITIONS));
+            let low = vaddv_u8(vget_low_u8(bits)) as u16;
+            let high = vaddv_u8(vget_high_u8(bits)) as u16;
+            low | (high << 8)
+        }
+    }
+
+    #[inline(always)]
+    fn bitmask_i32x4(value: [i32; 4]) -> u8 {
+        const BIT_POSITIONS: [i32; 4] = [0, 1, 2, 3];
+
+        // SAFETY: Arrays of 4 i32s and the 128-bit vector types have the same size,
+        // and any bit pattern is valid for all of them.
+        unsafe {
+            let sign_bits = vshrq_n_u32::<31>(transmute::<[i32; 4], uint32x4_t>(value));
+            let bits = vshlq_u32(sign_bits, transmute::<[i32; 4], int32x4_t>(BIT_POSITIONS));
+            vaddvq_u32(bits) as u8
+        }
+    }
+}
+
+// These let the kernels that are written with intrinsics directly pass vectors
+// in and out of them.
+macro_rules! impl_register_conversions {
+    ($($register:ty => $vector:ty;)*) => {
+        $(
+            impl From<$register> for $vector {
+                #[inline(always)]
+                fn from(value: $register) -> Self {
+                    // SAFETY: The types have the same size, and any bit pattern is valid for
+                    // both.
+                    unsafe { transmute::<$register, $vector>(value) }
+                }
+            }
+
+            impl From<$vector> for $register {
+                #[inline(always)]
+                fn from(value: $vector) -> Self {
+                    // SAFETY: See above.
+                    unsafe { transmute::<$vector, $register>(value) }
+                }
+            }
+        )*
+    };
+}
+
+impl_register_conversions! {
+    uint8x8_t => Simd<u8, 8>;
+    uint8x16_t => Simd<u8, 16>;
+    int8x16_t => Simd<i8, 16>;
+    uint32x4_t => Simd<u32, 4>;
+    int32x4_t => Simd<i32, 4>;
+    float32x4_t => Simd<f32, 4>;
+    uint8x16x4_t => Simd<u8, 64>;
+}
@@ -0,0 +1,146 @@
+use std::fmt::Debug;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+use super::Backend;
+
+/// A type that can be stored in the lanes of a [`Simd`](super::Simd).
+pub trait SimdElement: Copy + Default + PartialEq + PartialOrd + Debug + 'static {
+    /// The integer type used for the lanes of masks produced by comparing
+    /// vectors of this type. It always has the same width as the element.
+    type Mask: MaskElement;
+}
+
+/// A type that can be stored in the lanes of a [`Mask`](super::Mask). Each lane
+/// is either all 1 bits or all 0 bits.
+pub trait MaskElement:
+    SimdElement<Mask = Self>
+    + Eq
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+{
+    const TRUE: Self;
+    const FALSE: Self;
+
+    /// Packs the lanes into a bitmask, with lane 0 in the lowest bit. Backends
+    /// override this for the lane widths they have a movemask for.
+    #[inline(always)]
+    fn to_bitmask<const N: usize>(lanes: &[Self; N]) -> u64 {
+        lanes.iter().enumerate().fold(0, |bitmask, (i, lane)| {
+            bitmask | ((*lane != Self::FALSE) as u64) << i
+        })
+    }
+}
+
+/// Lane-wise conversion with the same semantics as an `as` cast.
+pub trait CastFrom<T> {
+    fn cast_from(value: T) -> Self;
+}
+
+/// Lane-wise float to integer conversion, without the saturation done by an
+/// `as` cast.
+pub trait ToIntUnchecked<I> {
+    /// # Safety
+    /// The value must not be NaN or infinite, and must be representable in `I`
+    /// after truncating the fractional part.
+    unsafe fn to_int_unchecked(self) -> I;
+}
+
+macro_rules! impl_element {
+    ($($element:ty => $mask:ty),* $(,)?) => {
+        $(
+            impl SimdElement for $element {
+                type Mask = $mask;
+            }
+        )*
+    };
+}
+
+impl_element!(
+    u8 => i8,
+    i8 => i8,
+    u16 => i16,
+    i16 => i16,
+    u32 => i32,
+    i32 => i32,
+    u64 => i64,
+    i64 => i64,
+    usize => isize,
+    isize => isize,
+    f32 => i32,
+    f64 => i64,
+);
+
+impl MaskElement for i8 {
+    const TRUE: Self = -1;
+    const FALSE: Self = 0;
+
+    #[inline(always)]
+    fn to_bitmask<const N: usize>(lanes: &[Self; N]) -> u64 {
+        super::to_bitmask_chunked(lanes, super::NativeBackend::bitmask_i8x16)
+    }
+}
+
+impl MaskElement for i32 {
+    const TRUE: Self = -1;
+    const FALSE: Self = 0;
+
+    #[inline(always)]
+    fn to_bitmask<const N: usize>(lanes: &[Self; N]) -> u64 {
+        super::to_bitmask_chunked(lanes, super::NativeBackend::bitmask_i32x4)
+    }
+}
+
+macro_rules! impl_mask_element {
+    ($($mask:ty),*) => {
+        $(
+            impl MaskElement for $mask {
+                const TRUE: Self = -1;
+                const FALSE: Self = 0;
+            }
+        )*
+    };
+}
+
+impl_mask_element!(i16, i64, isize);
+
+macro_rules! impl_cast_from {
+    ($($from:ty),*) => {
+        $(
+            impl_cast_from!(@to $from => u8, i8, u16, i16, u32, i32, u64, i64, usize, isize, f32, f64);
+        )*
+    };
+    (@to $from:ty => $($to:ty),*) => {
+        $(
+            impl CastFrom<$from> for $to {
+                #[inline(always)]
+                fn cast_from(value: $from) -> Self {
+                    value as $to
+                }
+            }
+        )*
+    };
+}
+
+impl_cast_from!(u8, i8, u16, i16, u32, i32, u64, i64, usize, isize, f32, f64);
+
+macro_rules! impl_to_int_unchecked {
+    ($($float:ty),*) => {
+        $(
+            impl_to_int_unchecked!(@to $float => u8, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+        )*
+    };
+    (@to $float:ty => $($int:ty),*) => {
+        $(
+            impl ToIntUnchecked<$int> for $float {
+                #[inline(always)]
+                unsafe fn to_int_unchecked(self) -> $int {
+                    <$float>::to_int_unchecked::<$int>(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_int_unchecked!(f32, f64);
@@ -0,0 +1,283 @@
+//! Runtime selection of the target features that the hot kernels are compiled
+//! for.
+//!
+//! Each kernel is generic over a [`Level`], and runs its body through
+//! [`Level::dispatch`], which enables the level's target features for
+//! everything that gets inlined into it. The graph picks the best level the CPU
+//! supports when it's created, so one library can run on every CPU of an
+//! architecture without giving up the newer instructions.
+
+use std::fmt;
+
+/// The sets of target features that the kernels are compiled for. The values
+/// are part of the FFI, and match the `SIMD_LEVEL_` constants in
+/// `HyperCullNativeLib.java`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum SimdLevel {
+    /// Only the features that the library was compiled with, which is SSE2 on
+    /// x86-64 and NEON on aarch64 by default.
+    Baseline = 0,
+    /// SSE4.1 and SSSE3 on x86-64.
+    Sse41 = 1,
+    /// AVX2, FMA, BMI1, BMI2 and LZCNT on x86-64, which is `x86-64-v3`
+    /// without MOVBE and F16C.
+    Avx2 = 2,
+    /// Everything in [`SimdLevel::Avx2`], along with AVX-512F and AVX-512BW.
+    /// This fits a whole tile in one register.
+    Avx512 = 3,
+}
+
+impl SimdLevel {
+    pub const ALL: [Self; 4] = [Self::Baseline, Self::Sse41, Self::Avx2, Self::Avx512];
+
+    /// Returns the best level that the CPU supports.
+    pub fn detect() -> Self {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|level| level.is_supported())
+            .unwrap_or(Self::Baseline)
+    }
+
+    /// Returns the best level whose features were all enabled when the library
+    /// was compiled, which every CPU that can run the library supports.
+    pub const fn compiled() -> Self {
+        if cfg!(all(
+            target_feature = "avx2",
+            target_feature = "fma",
+            target_feature = "bmi1",
+            target_feature = "bmi2",
+            target_feature = "lzcnt",
+        )) {
+            if cfg!(all(target_feature = "avx512f", target_feature = "avx512bw")) {
+                Self::Avx512
+            } else {
+                Self::Avx2
+            }
+        } else if cfg!(all(target_feature = "sse4.1", target_feature = "ssse3")) {
+            Self::Sse41
+        } else {
+            Self::Baseline
+        }
+    }
+
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Baseline => true,
+            #[cfg(target_arch = "x86_64")]
+            Self::Sse41 => is_x86_feature_detected!("sse4.1") && is_x86_feature_detected!("ssse3"),
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx2 => {
+                is_x86_feature_detected!("avx2")
+                    && is_x86_feature_detected!("fma")
+                    && is_x86_feature_detected!("bmi1")
+                    && is_x86_feature_detected!("bmi2")
+                    && is_x86_feature_detected!("lzcnt")
+            }
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx512 => {
+                Self::Avx2.is_supported()
+                    && is_x86_feature_detected!("avx512f")
+                    && is_x86_feature_detected!("avx512bw")
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+
+    /// Returns every level that the CPU supports, from worst to best.
+    pub fn supported() -> impl Iterator<Item = Self> {
+        Self::ALL.into_iter().filter(|level| level.is_supported())
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| *level as u8 == value)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Sse41 => "sse4.1",
+            Self::Avx2 => "avx2",
+            Self::Avx512 => "avx512",
+        }
+    }
+}
+
+impl fmt::Display for SimdLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A [`SimdLevel`] as a type, so the kernels can be compiled once for each
+/// level.
+///
+/// Code that is generic over a level must only run on CPUs that support it.
+/// [`with_level`] is the only place that turns a [`SimdLevel`] into a type, so
+/// this holds as long as the level passed to it came from
+/// [`SimdLevel::detect`] or was checked with [`SimdLevel::is_supported`].
+pub trait Level: 'static {
+    const LEVEL: SimdLevel;
+
+    /// Whether a fused multiply-add is at least as fast as a separate multiply
+    /// and add.
+    const FAST_FMA: bool;
+
+    /// Whether floor and ceil compile to single instructions when they're
+    /// implemented lane-wise. When they don't, the backend's versions are used.
+    const FAST_ROUND: bool;
+
+    const AVX2: bool;
+
+    const AVX512: bool;
+
+    const NEON: bool;
+
+    /// Calls `f` from a function that has the level's target features enabled.
+    /// Everything that's inlined into `f` is compiled with them, so kernels
+    /// should wrap their whole body in this.
+    ///
+    /// # Safety
+    /// The CPU must support the level.
+    unsafe fn dispatch<R>(f: impl FnOnce() -> R) -> R;
+}
+
+pub struct Baseline;
+
+impl Level for Baseline {
+    const LEVEL: SimdLevel = SimdLevel::Baseline;
+    const FAST_FMA: bool = cfg!(target_feature = "fma") || cfg!(target_feature = "neon");
+    const FAST_ROUND: bool = false;
+    const AVX2: bool = cfg!(target_feature = "avx2");
+    const AVX512: bool = cfg!(all(target_feature = "avx512f", target_feature = "avx512bw"));
+    const NEON: bool = cfg!(all(target_arch = "aarch64", target_feature = "neon"));
+
+    #[inline(always)]
+    unsafe fn dispatch<R>(f: impl FnOnce() -> R) -> R {
+        f()
+    }
+}
+
+/// [`Baseline`] without any of the paths that are written with intrinsics, so
+/// tests can compare those paths against the portable code on every target.
+#[cfg(test)]
+pub struct Portable;
+
+#[cfg(test)]
+impl Level for Portable {
+    const LEVEL: SimdLevel = SimdLevel::Baseline;
+    const FAST_FMA: bool = Baseline::FAST_FMA;
+    const FAST_ROUND: bool = false;
+    const AVX2: bool = false;
+    const AVX512: bool = false;
+    const NEON: bool = false;
+
+    #[inline(always)]
+    unsafe fn dispatch<R>(f: impl FnOnce() -> R) -> R {
+        f()
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub struct Sse41;
+
+#[cfg(target_arch = "x86_64")]
+impl Level for Sse41 {
+    const LEVEL: SimdLevel = SimdLevel::Sse41;
+    const FAST_FMA: bool = cfg!(target_feature = "fma");
+    const FAST_ROUND: bool = true;
+    const AVX2: bool = cfg!(target_feature = "avx2");
+    const AVX512: bool = cfg!(all(target_feature = "avx512f", target_feature = "avx512bw"));
+    const NEON: bool = false;
+
+    #[inline(always)]
+    unsafe fn dispatch<R>(f: impl FnOnce() -> R) -> R {
+        #[target_feature(enable = "sse4.1,ssse3")]
+        #[inline]
+        unsafe fn enable<R>(f: impl FnOnce() -> R) -> R {
+            f()
+        }
+
+        enable(f)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub struct Avx2;
+
+#[cfg(target_arch = "x86_64")]
+impl Level for Avx2 {
+    const LEVEL: SimdLevel = SimdLevel::Avx2;
+    const FAST_FMA: bool = true;
+    const FAST_ROUND: bool = true;
+    const AVX2: bool = true;
+    const AVX512: bool = cfg!(all(target_feature = "avx512f", target_feature = "avx512bw"));
+    const NEON: bool = false;
+
+    #[inline(always)]
+    unsafe fn dispatch<R>(f: impl FnOnce() -> R) -> R {
+        #[target_feature(enable = "avx2,fma,bmi1,bmi2,lzcnt")]
+        #[inline]
+        unsafe fn enable<R>(f: impl FnOnce() -> R) -> R {
+            f()
+        }
+
+        enable(f)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub struct Avx512;
+
+#[cfg(target_arch = "x86_64")]
+impl Level for Avx512 {
+    const LEVEL: SimdLevel = SimdLevel::Avx512;
+    const FAST_FMA: bool = true;
+    const FAST_ROUND: bool = true;
+    const AVX2: bool = true;
+    const AVX512: bool = true;
+    const NEON: bool = false;
+
+    #[inline(always)]
+    unsafe fn dispatch<R>(f: impl FnOnce() -> R) -> R {
+        #[target_feature(enable = "avx2,fma,bmi1,bmi2,lzcnt,avx512f,avx512bw")]
+        #[inline]
+        unsafe fn enable<R>(f: impl FnOnce() -> R) -> R {
+            f()
+        }
+
+        enable(f)
+    }
+}
+
+/// Evaluates `$body` with `$level` as a [`Level`] type named `$L`.
+/// Levels that don't exist on the target are evaluated as [`Baseline`].
+macro_rules! with_level {
+    ($level:expr, $L:ident => $body:expr) => {
+        match $level {
+            #[cfg(target_arch = "x86_64")]
+            $crate::simd::level::SimdLevel::Avx512 => {
+                type $L = $crate::simd::level::Avx512;
+                $body
+            }
+            #[cfg(target_arch = "x86_64")]
+            $crate::simd::level::SimdLevel::Avx2 => {
+                type $L = $crate::simd::level::Avx2;
+                $body
+            }
+            #[cfg(target_arch = "x86_64")]
+            $crate::simd::level::SimdLevel::Sse41 => {
+                type $L = $crate::simd::level::Sse41;
+                $body
+            }
+            _ => {
+                type $L = $crate::simd::level::Baseline;
+                $body
+            }
+        }
+    };
+}
+
+pub(crate) use with_level;
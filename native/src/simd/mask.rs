@@ -0,0 +1,179 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use std::{array, fmt};
+
+use super::element::{MaskElement, SimdElement};
+use super::Simd;
+
+/// A mask of `N` lanes, mirroring the parts of `core::simd::Mask` that the
+/// crate uses. Each lane is stored as a `T` that is either all 1 bits or all 0
+/// bits, so it can be used directly as the condition of a vector select.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Mask<T, const N: usize>([T; N]);
+
+impl<T: MaskElement, const N: usize> Mask<T, N> {
+    #[inline(always)]
+    pub const fn splat(value: bool) -> Self {
+        Self([if value { T::TRUE } else { T::FALSE }; N])
+    }
+
+    #[inline(always)]
+    pub fn from_array(array: [bool; N]) -> Self {
+        Self(array.map(|value| if value { T::TRUE } else { T::FALSE }))
+    }
+
+    #[inline(always)]
+    pub fn to_array(self) -> [bool; N] {
+        self.0.map(|lane| lane != T::FALSE)
+    }
+
+    /// Converts a vector where every lane is all 1 bits or all 0 bits into a
+    /// mask.
+    ///
+    /// # Safety
+    /// Every lane must be either all 1 bits or all 0 bits.
+    #[inline(always)]
+    pub unsafe fn from_int_unchecked(value: Simd<T, N>) -> Self {
+        Self(value.to_array())
+    }
+
+    /// Returns each lane as all 1 bits if it is set, and all 0 bits otherwise.
+    #[inline(always)]
+    pub fn to_int(self) -> Simd<T, N> {
+        Simd::from_array(self.0)
+    }
+
+    #[inline(always)]
+    pub fn test(&self, index: usize) -> bool {
+        self.0[index] != T::FALSE
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, index: usize, value: bool) {
+        self.0[index] = if value { T::TRUE } else { T::FALSE };
+    }
+
+    #[inline(always)]
+    pub fn any(self) -> bool {
+        self.to_bitmask() != 0
+    }
+
+    #[inline(always)]
+    pub fn all(self) -> bool {
+        self.to_bitmask() == u64::MAX >> (64 - N)
+    }
+
+    /// Packs the lanes into a bitmask, with lane 0 in the lowest bit.
+    #[inline(always)]
+    pub fn to_bitmask(self) -> u64 {
+        const {
+            assert!(
+                N > 0 && N <= 64,
+                "bitmasks only fit masks of up to 64 lanes"
+            )
+        };
+        T::to_bitmask(&self.0)
+    }
+
+    /// Creates a mask from the lowest `N` bits of the bitmask, with lane 0 in
+    /// the lowest bit.
+    #[inline(always)]
+    pub fn from_bitmask(bitmask: u64) -> Self {
+        const {
+            assert!(
+                N > 0 && N <= 64,
+                "bitmasks only fit masks of up to 64 lanes"
+            )
+        };
+        Self::from_array(array::from_fn(|i| (bitmask >> i) & 1 != 0))
+    }
+
+    /// Chooses each lane from `true_values` if it is set, and from
+    /// `false_values` otherwise.
+    #[inline(always)]
+    pub fn select<U: SimdElement<Mask = T>>(
+        self,
+        true_values: Simd<U, N>,
+        false_values: Simd<U, N>,
+    ) -> Simd<U, N> {
+        Simd::from_array(array::from_fn(|i| {
+            if self.0[i] != T::FALSE {
+                true_values[i]
+            } else {
+                false_values[i]
+            }
+        }))
+    }
+
+    /// Returns the first `M` lanes, filling any lanes past `N` with `value`.
+    #[inline(always)]
+    pub fn resize<const M: usize>(self, value: bool) -> Mask<T, M> {
+        let fill = if value { T::TRUE } else { T::FALSE };
+        Mask(array::from_fn(|i| if i < N { self.0[i] } else { fill }))
+    }
+
+    #[inline(always)]
+    pub fn cast<U: MaskElement>(self) -> Mask<U, N> {
+        Mask(
+            self.0
+                .map(|lane| if lane != T::FALSE { U::TRUE } else { U::FALSE }),
+        )
+    }
+}
+
+impl<T: MaskElement, const N: usize> Default for Mask<T, N> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::splat(false)
+    }
+}
+
+impl<T: MaskElement, const N: usize> From<[bool; N]> for Mask<T, N> {
+    #[inline(always)]
+    fn from(array: [bool; N]) -> Self {
+        Self::from_array(array)
+    }
+}
+
+impl<T: MaskElement, const N: usize> fmt::Debug for Mask<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_array().fmt(f)
+    }
+}
+
+macro_rules! impl_mask_op {
+    ($($trait:ident::$fn:ident, $assign_trait:ident::$assign_fn:ident);* $(;)?) => {
+        $(
+            impl<T: MaskElement, const N: usize> $trait for Mask<T, N> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn $fn(self, rhs: Self) -> Self {
+                    Self(array::from_fn(|i| $trait::$fn(self.0[i], rhs.0[i])))
+                }
+            }
+
+            impl<T: MaskElement, const N: usize> $assign_trait for Mask<T, N> {
+                #[inline(always)]
+                fn $assign_fn(&mut self, rhs: Self) {
+                    *self = $trait::$fn(*self, rhs);
+                }
+            }
+        )*
+    };
+}
+
+impl_mask_op!(
+    BitAnd::bitand, BitAndAssign::bitand_assign;
+    BitOr::bitor, BitOrAssign::bitor_assign;
+    BitXor::bitxor, BitXorAssign::bitxor_assign;
+);
+
+impl<T: MaskElement, const N: usize> Not for Mask<T, N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn not(self) -> Self {
+        Self(self.0.map(T::not))
+    }
+}
@@ -0,0 +1,324 @@
+//! A stable replacement for the parts of `core::simd` that the crate uses.
+//!
+//! The vector and mask types mirror the `core::simd` API, so the culling code
+//! reads the same as it would with portable SIMD. Almost every operation is
+//! plain lane-wise code that LLVM vectorizes for whatever target features are
+//! enabled. The operations that it can't vectorize on its own are implemented
+//! by a [`Backend`], which is `std::arch` code on x86-64 and aarch64, and
+//! scalar code on every other target or when the `scalar_simd` feature is
+//! enabled.
+//!
+//! The hot kernels are also compiled for newer target features than the ones
+//! the library was built with, which are picked at runtime. See [`level`].
+
+#![allow(non_camel_case_types)]
+
+mod element;
+pub mod level;
+mod mask;
+mod ops;
+mod traits;
+mod vector;
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+mod scalar;
+#[cfg(target_arch = "x86_64")]
+mod x86;
+
+use std::array;
+
+pub use element::{MaskElement, SimdElement};
+pub use mask::Mask;
+pub use traits::{SimdFloat, SimdInt, SimdOrd, SimdSignedInt, StdFloat};
+pub use vector::Simd;
+
+#[cfg(all(target_arch = "aarch64", not(feature = "scalar_simd")))]
+pub type NativeBackend = aarch64::Neon;
+#[cfg(any(
+    feature = "scalar_simd",
+    not(any(target_arch = "x86_64", target_arch = "aarch64"))
+))]
+pub type NativeBackend = scalar::Scalar;
+#[cfg(all(target_arch = "x86_64", not(feature = "scalar_simd")))]
+pub type NativeBackend = x86::X86;
+
+/// The operations that need architecture-specific code to be fast. Each one
+/// works on a single 128-bit register's worth of lanes, and wider vectors are
+/// split into chunks of it.
+///
+/// Every backend must return bit-identical results to [`scalar::Scalar`].
+pub trait Backend {
+    fn floor_f32x4(value: [f32; 4]) -> [f32; 4];
+
+    fn ceil_f32x4(value: [f32; 4]) -> [f32; 4];
+
+    /// Computes `(value * mul) + add` with a single rounding.
+    fn mul_add_f32x4(value: [f32; 4], mul: [f32; 4], add: [f32; 4]) -> [f32; 4];
+
+    /// Packs the sign bit of each lane into a bitmask, with lane 0 in the
+    /// lowest bit.
+    fn bitmask_i8x16(value: [i8; 16]) -> u16;
+
+    /// Packs the sign bit of each lane into a bitmask, with lane 0 in the
+    /// lowest bit.
+    fn bitmask_i32x4(value: [i32; 4]) -> u8;
+}
+
+/// Applies `chunk_fn` to each full chunk of `C` lanes, and `lane_fn` to the
+/// remaining lanes.
+#[inline(always)]
+fn map_chunked<T: Copy, const N: usize, const C: usize>(
+    lanes: [T; N],
+    chunk_fn: impl Fn([T; C]) -> [T; C],
+    lane_fn: impl Fn(T) -> T,
+) -> [T; N] {
+    let mut result = lanes;
+    let chunked_len = N - (N % C);
+
+    for start in (0..chunked_len).step_by(C) {
+        let chunk = chunk_fn(array::from_fn(|i| lanes[start + i]));
+        result[start..start + C].copy_from_slice(&chunk);
+    }
+    for i in chunked_len..N {
+        result[i] = lane_fn(lanes[i]);
+    }
+
+    result
+}
+
+/// The same as [`map_chunked`], but with three inputs.
+#[inline(always)]
+fn zip3_chunked<T: Copy, const N: usize, const C: usize>(
+    a: [T; N],
+    b: [T; N],
+    c: [T; N],
+    chunk_fn: impl Fn([T; C], [T; C], [T; C]) -> [T; C],
+    lane_fn: impl Fn(T, T, T) -> T,
+) -> [T; N] {
+    let mut result = a;
+    let chunked_len = N - (N % C);
+
+    for start in (0..chunked_len).step_by(C) {
+        let chunk = chunk_fn(
+            array::from_fn(|i| a[start + i]),
+            array::from_fn(|i| b[start + i]),
+            array::from_fn(|i| c[start + i]),
+        );
+        result[start..start + C].copy_from_slice(&chunk);
+    }
+    for i in chunked_len..N {
+        result[i] = lane_fn(a[i], b[i], c[i]);
+    }
+
+    result
+}
+
+/// Builds a bitmask out of each full chunk of `C` lanes with `chunk_fn`, and
+/// out of the sign bit of each remaining lane.
+#[inline(always)]
+fn to_bitmask_chunked<T: MaskElement, B: Into<u64>, const N: usize, const C: usize>(
+    lanes: &[T; N],
+    chunk_fn: impl Fn([T; C]) -> B,
+) -> u64 {
+    let mut bitmask = 0;
+    let chunked_len = N - (N % C);
+
+    for start in (0..chunked_len).step_by(C) {
+        bitmask |= chunk_fn(array::from_fn(|i| lanes[start + i])).into() << start;
+    }
+    for (i, lane) in lanes.iter().enumerate().skip(chunked_len) {
+        bitmask |= ((*lane != T::FALSE) as u64) << i;
+    }
+
+    bitmask
+}
+
+/// Creates a vector with each lane chosen from one or two vectors by index,
+/// like `core::simd::simd_swizzle!`. When two vectors are given, indices past
+/// the end of the first vector select from the second.
+macro_rules! simd_swizzle {
+    ($vector:expr, $index:expr $(,)?) => {
+        $vector.swizzle(const { $index })
+    };
+    ($first:expr, $second:expr, $index:expr $(,)?) => {
+        $first.swizzle2($second, const { $index })
+    };
+}
+
+pub(crate) use simd_swizzle;
+
+macro_rules! aliases {
+    ($($element:ty => $($alias:ident: $lanes:literal),*;)*) => {
+        $($(
+            pub type $alias = Simd<$element, $lanes>;
+        )*)*
+    };
+}
+
+aliases! {
+    u8 => u8x4: 4, u8x8: 8, u8x32: 32, u8x64: 64;
+    u32 => u32x8: 8;
+    i32 => i32x4: 4, i32x64: 64;
+    u64 => u64x8: 8;
+    f32 => f32x4: 4, f32x8: 8;
+}
+
+pub type mask8x8 = Mask<i8, 8>;
+pub type mask64x8 = Mask<i64, 8>;
+
+pub mod prelude {
+    pub(crate) use super::level::with_level;
+    pub use super::level::{Level, SimdLevel};
+    pub(crate) use super::simd_swizzle;
+    pub use super::{
+        f32x4, f32x8, i32x64, mask64x8, mask8x8, u32x8, u64x8, u8x64, u8x8, Mask, Simd, SimdFloat,
+        SimdInt, SimdOrd, SimdSignedInt, StdFloat,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::*;
+
+    use super::prelude::*;
+    use super::scalar::Scalar;
+    use super::*;
+    use crate::TESTS_RANDOM_SEED;
+
+    // Includes the values where rounding is most likely to go wrong, along with
+    // the special values.
+    const SPECIAL_F32S: [f32; 20] = [
+        0.0,
+        -0.0,
+        0.5,
+        -0.5,
+        1.0,
+        -1.0,
+        0.99999994,
+        -0.99999994,
+        8388607.5,
+        -8388607.5,
+        8388608.0,
+        -8388609.0,
+        2147483648.0,
+        -2147483904.0,
+        1e30,
+        -1e30,
+        f32::MIN_POSITIVE,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::NAN,
+    ];
+
+    fn random_f32(rand: &mut StdRng) -> f32 {
+        match rand.random_range(0..4) {
+            0 => SPECIAL_F32S[rand.random_range(0..SPECIAL_F32S.len())],
+            1 => rand.random_range(-10.0..10.0),
+            2 => rand.random_range(-1e10..1e10),
+            // any bit pattern, including NaNs with payloads and subnormals
+            _ => f32::from_bits(rand.random()),
+        }
+    }
+
+    fn assert_bits_eq(scalar: [f32; 4], native: [f32; 4], inputs: &[[f32; 4]]) {
+        assert_eq!(
+            scalar.map(f32::to_bits),
+            native.map(f32::to_bits),
+            "scalar != native, Inputs: {inputs:?}"
+        );
+    }
+
+    #[test]
+    fn backend_equivalence_test() {
+        const ITERATIONS: u32 = 100000;
+        let mut rand = StdRng::seed_from_u64(TESTS_RANDOM_SEED);
+
+        for _ in 0..ITERATIONS {
+            let a = array::from_fn(|_| random_f32(&mut rand));
+            let b = array::from_fn(|_| random_f32(&mut rand));
+            let c = array::from_fn(|_| random_f32(&mut rand));
+
+            assert_bits_eq(Scalar::floor_f32x4(a), NativeBackend::floor_f32x4(a), &[a]);
+            assert_bits_eq(Scalar::ceil_f32x4(a), NativeBackend::ceil_f32x4(a), &[a]);
+
+            let scalar_mul_add = Scalar::mul_add_f32x4(a, b, c);
+            let native_mul_add = NativeBackend::mul_add_f32x4(a, b, c);
+            // the sign and payload of a NaN result aren't specified
+            if !scalar_mul_add.iter().any(|lane| lane.is_nan()) {
+                assert_bits_eq(scalar_mul_add, native_mul_add, &[a, b, c]);
+            }
+
+            let bytes: [i8; 16] = rand.random();
+            assert_eq!(
+                Scalar::bitmask_i8x16(bytes),
+                NativeBackend::bitmask_i8x16(bytes),
+                "scalar != native, Input: {bytes:?}"
+            );
+
+            let ints: [i32; 4] = rand.random();
+            assert_eq!(
+                Scalar::bitmask_i32x4(ints),
+                NativeBackend::bitmask_i32x4(ints),
+                "scalar != native, Input: {ints:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn lane_ops_test() {
+        // shifts take the shift amount modulo the lane width, and arithmetic wraps
+        let shifted = (u8x4::splat(0b10) << u8x4::from_array([0, 1, 7, 9])) - u8x4::splat(1);
+        assert_eq!(shifted.to_array(), [1, 3, 255, 3]);
+
+        let vector = i32x4::from_array([-3, 7, i32::MIN, 0]);
+        assert_eq!(vector.abs().to_array(), [3, 7, i32::MIN, 0]);
+        assert_eq!(
+            vector
+                .simd_clamp(Simd::splat(-1), Simd::splat(5))
+                .to_array(),
+            [-1, 5, -1, 0]
+        );
+        assert_eq!(vector.cast::<i8>().to_array(), [-3, 7, 0, 0]);
+
+        let floats = f32x8::from_array([-1.5, -0.5, -0.0, 0.0, 0.5, 1.5, 7.0, 1e20]);
+        assert_eq!(
+            floats.floor().to_array(),
+            [-2.0, -1.0, -0.0, 0.0, 0.0, 1.0, 7.0, 1e20]
+        );
+        assert_eq!(
+            floats.ceil().to_array(),
+            [-1.0, -0.0, -0.0, 0.0, 1.0, 2.0, 7.0, 1e20]
+        );
+        assert_eq!(
+            floats.is_sign_negative().to_bitmask(),
+            0b0000_0111,
+            "{floats:?}"
+        );
+        assert_eq!(floats.resize::<3>(0.0).reduce_sum(), -2.0);
+
+        let mask = mask64x8::from_bitmask(0b1010_0110);
+        assert_eq!(mask.to_bitmask(), 0b1010_0110);
+        assert!(mask.any() && !mask.all() && (mask | !mask).all());
+        assert_eq!(
+            mask.to_int().to_le_bytes::<64>().extract::<8, 8>(),
+            u8x8::splat(255)
+        );
+        assert_eq!(
+            u64x8::from_le_bytes(mask.to_int().to_le_bytes::<64>()),
+            mask.to_int().cast::<u64>()
+        );
+    }
+
+    #[test]
+    fn swizzle_test() {
+        let first = i32x4::from_array([0, 1, 2, 3]);
+        let second = i32x4::from_array([4, 5, 6, 7]);
+
+        assert_eq!(simd_swizzle!(first, [3, 3, 0]).to_array(), [3, 3, 0]);
+        assert_eq!(
+            simd_swizzle!(first, second, [7, 0, 4, 2, 5, 1]).to_array(),
+            [7, 0, 4, 2, 5, 1]
+        );
+    }
+}
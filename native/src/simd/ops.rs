@@ -0,0 +1,170 @@
+use std::ops::*;
+
+use super::element::SimdElement;
+use super::Simd;
+
+// Integer arithmetic wraps, and shift amounts are taken modulo the lane width,
+// matching core::simd.
+macro_rules! impl_int_ops {
+    ($($int:ty),*) => {
+        $(
+            impl_lane_op!($int: Add::add => <$int>::wrapping_add);
+            impl_lane_op!($int: Sub::sub => <$int>::wrapping_sub);
+            impl_lane_op!($int: Mul::mul => <$int>::wrapping_mul);
+            impl_lane_op!($int: Div::div => |lhs: $int, rhs: $int| lhs / rhs);
+            impl_lane_op!($int: Rem::rem => |lhs: $int, rhs: $int| lhs % rhs);
+            impl_lane_op!($int: BitAnd::bitand => |lhs: $int, rhs: $int| lhs & rhs);
+            impl_lane_op!($int: BitOr::bitor => |lhs: $int, rhs: $int| lhs | rhs);
+            impl_lane_op!($int: BitXor::bitxor => |lhs: $int, rhs: $int| lhs ^ rhs);
+            impl_lane_op!($int: Shl::shl => |lhs: $int, rhs: $int| lhs.wrapping_shl(rhs as u32));
+            impl_lane_op!($int: Shr::shr => |lhs: $int, rhs: $int| lhs.wrapping_shr(rhs as u32));
+
+            impl<const N: usize> Shl<$int> for Simd<$int, N> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn shl(self, rhs: $int) -> Self {
+                    self << Self::splat(rhs)
+                }
+            }
+
+            impl<const N: usize> Shr<$int> for Simd<$int, N> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn shr(self, rhs: $int) -> Self {
+                    self >> Self::splat(rhs)
+                }
+            }
+
+            impl<const N: usize> Not for Simd<$int, N> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn not(self) -> Self {
+                    self.map(|lane| !lane)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed_int_ops {
+    ($($int:ty),*) => {
+        $(
+            impl<const N: usize> Neg for Simd<$int, N> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn neg(self) -> Self {
+                    self.map(<$int>::wrapping_neg)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float_ops {
+    ($($float:ty),*) => {
+        $(
+            impl_lane_op!($float: Add::add => |lhs: $float, rhs: $float| lhs + rhs);
+            impl_lane_op!($float: Sub::sub => |lhs: $float, rhs: $float| lhs - rhs);
+            impl_lane_op!($float: Mul::mul => |lhs: $float, rhs: $float| lhs * rhs);
+            impl_lane_op!($float: Div::div => |lhs: $float, rhs: $float| lhs / rhs);
+            impl_lane_op!($float: Rem::rem => |lhs: $float, rhs: $float| lhs % rhs);
+
+            impl<const N: usize> Neg for Simd<$float, N> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn neg(self) -> Self {
+                    self.map(|lane| -lane)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_lane_op {
+    ($element:ty: $trait:ident::$fn:ident => $lane_fn:expr) => {
+        impl<const N: usize> $trait for Simd<$element, N> {
+            type Output = Self;
+
+            #[inline(always)]
+            fn $fn(self, rhs: Self) -> Self {
+                self.zip_map(rhs, $lane_fn)
+            }
+        }
+    };
+}
+
+impl_int_ops!(u8, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+impl_signed_int_ops!(i8, i16, i32, i64, isize);
+impl_float_ops!(f32, f64);
+
+// Operations on references and compound assignment are forwarded to the
+// by-value implementations above.
+macro_rules! impl_forwarded_ops {
+    ($($trait:ident::$fn:ident, $assign_trait:ident::$assign_fn:ident);* $(;)?) => {
+        $(
+            impl<'a, T: SimdElement, const N: usize> $trait<&'a Simd<T, N>> for Simd<T, N>
+            where
+                Simd<T, N>: $trait<Output = Simd<T, N>>,
+            {
+                type Output = Self;
+
+                #[inline(always)]
+                fn $fn(self, rhs: &'a Self) -> Self {
+                    $trait::$fn(self, *rhs)
+                }
+            }
+
+            impl<'a, T: SimdElement, const N: usize> $trait<Simd<T, N>> for &'a Simd<T, N>
+            where
+                Simd<T, N>: $trait<Output = Simd<T, N>>,
+            {
+                type Output = Simd<T, N>;
+
+                #[inline(always)]
+                fn $fn(self, rhs: Simd<T, N>) -> Simd<T, N> {
+                    $trait::$fn(*self, rhs)
+                }
+            }
+
+            impl<'a, 'b, T: SimdElement, const N: usize> $trait<&'a Simd<T, N>> for &'b Simd<T, N>
+            where
+                Simd<T, N>: $trait<Output = Simd<T, N>>,
+            {
+                type Output = Simd<T, N>;
+
+                #[inline(always)]
+                fn $fn(self, rhs: &'a Simd<T, N>) -> Simd<T, N> {
+                    $trait::$fn(*self, *rhs)
+                }
+            }
+
+            impl<T: SimdElement, U, const N: usize> $assign_trait<U> for Simd<T, N>
+            where
+                Simd<T, N>: $trait<U, Output = Simd<T, N>>,
+            {
+                #[inline(always)]
+                fn $assign_fn(&mut self, rhs: U) {
+                    *self = $trait::$fn(*self, rhs);
+                }
+            }
+        )*
+    };
+}
+
+impl_forwarded_ops!(
+    Add::add, AddAssign::add_assign;
+    Sub::sub, SubAssign::sub_assign;
+    Mul::mul, MulAssign::mul_assign;
+    Div::div, DivAssign::div_assign;
+    Rem::rem, RemAssign::rem_assign;
+    BitAnd::bitand, BitAndAssign::bitand_assign;
+    BitOr::bitor, BitOrAssign::bitor_assign;
+    BitXor::bitxor, BitXorAssign::bitxor_assign;
+    Shl::shl, ShlAssign::shl_assign;
+    Shr::shr, ShrAssign::shr_assign;
+);
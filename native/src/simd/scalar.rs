@@ -0,0 +1,39 @@
+use std::array;
+
+use super::Backend;
+
+/// The reference backend, which is plain Rust that works on every target.
+pub struct Scalar;
+
+impl Backend for Scalar {
+    #[inline(always)]
+    fn floor_f32x4(value: [f32; 4]) -> [f32; 4] {
+        value.map(f32::floor)
+    }
+
+    #[inline(always)]
+    fn ceil_f32x4(value: [f32; 4]) -> [f32; 4] {
+        value.map(f32::ceil)
+    }
+
+    #[inline(always)]
+    fn mul_add_f32x4(value: [f32; 4], mul: [f32; 4], add: [f32; 4]) -> [f32; 4] {
+        array::from_fn(|i| value[i].mul_add(mul[i], add[i]))
+    }
+
+    #[inline(always)]
+    fn bitmask_i8x16(value: [i8; 16]) -> u16 {
+        value
+            .iter()
+            .enumerate()
+            .fold(0, |bitmask, (i, lane)| bitmask | ((*lane < 0) as u16) << i)
+    }
+
+    #[inline(always)]
+    fn bitmask_i32x4(value: [i32; 4]) -> u8 {
+        value
+            .iter()
+            .enumerate()
+            .fold(0, |bitmask, (i, lane)| bitmask | ((*lane < 0) as u8) << i)
+    }
+}
@@ -0,0 +1,238 @@
+//! Operations that only apply to some element types. Like in `core::simd`,
+//! these are traits rather than inherent methods, so calls on vectors with an
+//! inferred element type (such as `Simd::splat(0.0).simd_max(x)`) still
+//! resolve.
+
+use std::array;
+
+use super::element::SimdElement;
+use super::{Backend, Mask, NativeBackend, Simd};
+
+pub trait SimdOrd {
+    fn simd_min(self, other: Self) -> Self;
+    fn simd_max(self, other: Self) -> Self;
+    fn simd_clamp(self, min: Self, max: Self) -> Self;
+}
+
+pub trait SimdInt {
+    type Scalar;
+
+    /// Adds the lanes together, wrapping on overflow.
+    fn reduce_sum(self) -> Self::Scalar;
+    fn wrapping_neg(self) -> Self;
+}
+
+pub trait SimdSignedInt {
+    /// Wraps for the minimum value, like `wrapping_abs`.
+    fn abs(self) -> Self;
+}
+
+pub trait SimdFloat: Copy {
+    type Scalar;
+    type Bits;
+    type Mask;
+
+    fn to_bits(self) -> Self::Bits;
+    fn from_bits(bits: Self::Bits) -> Self;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn is_sign_positive(self) -> Self::Mask;
+    fn is_sign_negative(self) -> Self::Mask;
+    fn is_nan(self) -> Self::Mask;
+    fn is_finite(self) -> Self::Mask;
+    /// Returns the minimum of each lane, ignoring NaN.
+    fn simd_min(self, other: Self) -> Self;
+    /// Returns the maximum of each lane, ignoring NaN.
+    fn simd_max(self, other: Self) -> Self;
+    fn simd_clamp(self, min: Self, max: Self) -> Self;
+    /// Adds the lanes in order, starting from lane 0.
+    fn reduce_sum(self) -> Self::Scalar;
+}
+
+/// The float operations that are implemented by libm for scalars, which is
+/// what `std_float::StdFloat` covers for `core::simd`.
+pub trait StdFloat {
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    /// Computes `(self * mul) + add` with a single rounding.
+    fn mul_add(self, mul: Self, add: Self) -> Self;
+}
+
+macro_rules! impl_int_traits {
+    ($($int:ty),*) => {
+        $(
+            impl<const N: usize> SimdOrd for Simd<$int, N> {
+                #[inline(always)]
+                fn simd_min(self, other: Self) -> Self {
+                    self.zip_map(other, <$int>::min)
+                }
+
+                #[inline(always)]
+                fn simd_max(self, other: Self) -> Self {
+                    self.zip_map(other, <$int>::max)
+                }
+
+                #[inline(always)]
+                fn simd_clamp(self, min: Self, max: Self) -> Self {
+                    self.simd_max(min).simd_min(max)
+                }
+            }
+
+            impl<const N: usize> SimdInt for Simd<$int, N> {
+                type Scalar = $int;
+
+                #[inline(always)]
+                fn reduce_sum(self) -> $int {
+                    self.to_array().into_iter().fold(0, <$int>::wrapping_add)
+                }
+
+                #[inline(always)]
+                fn wrapping_neg(self) -> Self {
+                    self.map(<$int>::wrapping_neg)
+                }
+            }
+        )*
+    };
+}
+
+impl_int_traits!(u8, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+
+macro_rules! impl_signed_int_traits {
+    ($($int:ty),*) => {
+        $(
+            impl<const N: usize> SimdSignedInt for Simd<$int, N> {
+                #[inline(always)]
+                fn abs(self) -> Self {
+                    self.map(<$int>::wrapping_abs)
+                }
+            }
+        )*
+    };
+}
+
+impl_signed_int_traits!(i8, i16, i32, i64, isize);
+
+macro_rules! impl_float_traits {
+    ($($float:ty => $bits:ty),*) => {
+        $(
+            impl<const N: usize> SimdFloat for Simd<$float, N> {
+                type Scalar = $float;
+                type Bits = Simd<$bits, N>;
+                type Mask = Mask<<$float as SimdElement>::Mask, N>;
+
+                #[inline(always)]
+                fn to_bits(self) -> Self::Bits {
+                    self.map(<$float>::to_bits)
+                }
+
+                #[inline(always)]
+                fn from_bits(bits: Self::Bits) -> Self {
+                    bits.map(<$float>::from_bits)
+                }
+
+                #[inline(always)]
+                fn abs(self) -> Self {
+                    self.map(<$float>::abs)
+                }
+
+                #[inline(always)]
+                fn sqrt(self) -> Self {
+                    self.map(<$float>::sqrt)
+                }
+
+                #[inline(always)]
+                fn is_sign_positive(self) -> Self::Mask {
+                    Mask::from_array(self.to_array().map(<$float>::is_sign_positive))
+                }
+
+                #[inline(always)]
+                fn is_sign_negative(self) -> Self::Mask {
+                    Mask::from_array(self.to_array().map(<$float>::is_sign_negative))
+                }
+
+                #[inline(always)]
+                fn is_nan(self) -> Self::Mask {
+                    Mask::from_array(self.to_array().map(<$float>::is_nan))
+                }
+
+                #[inline(always)]
+                fn is_finite(self) -> Self::Mask {
+                    Mask::from_array(self.to_array().map(<$float>::is_finite))
+                }
+
+                #[inline(always)]
+                fn simd_min(self, other: Self) -> Self {
+                    self.zip_map(other, <$float>::min)
+                }
+
+                #[inline(always)]
+                fn simd_max(self, other: Self) -> Self {
+                    self.zip_map(other, <$float>::max)
+                }
+
+                #[inline(always)]
+                fn simd_clamp(self, min: Self, max: Self) -> Self {
+                    self.simd_max(min).simd_min(max)
+                }
+
+                #[inline(always)]
+                fn reduce_sum(self) -> $float {
+                    self.to_array().into_iter().fold(0.0, |sum, lane| sum + lane)
+                }
+            }
+        )*
+    };
+}
+
+impl_float_traits!(f32 => u32, f64 => u64);
+
+// Without the target features for them, the std versions of these are libm
+// calls that LLVM can't vectorize, so the f32 versions go through the backend.
+impl<const N: usize> StdFloat for Simd<f32, N> {
+    #[inline(always)]
+    fn floor(self) -> Self {
+        Simd::from_array(super::map_chunked(
+            self.to_array(),
+            NativeBackend::floor_f32x4,
+            f32::floor,
+        ))
+    }
+
+    #[inline(always)]
+    fn ceil(self) -> Self {
+        Simd::from_array(super::map_chunked(
+            self.to_array(),
+            NativeBackend::ceil_f32x4,
+            f32::ceil,
+        ))
+    }
+
+    #[inline(always)]
+    fn mul_add(self, mul: Self, add: Self) -> Self {
+        Simd::from_array(super::zip3_chunked(
+            self.to_array(),
+            mul.to_array(),
+            add.to_array(),
+            NativeBackend::mul_add_f32x4,
+            f32::mul_add,
+        ))
+    }
+}
+
+impl<const N: usize> StdFloat for Simd<f64, N> {
+    #[inline(always)]
+    fn floor(self) -> Self {
+        self.map(f64::floor)
+    }
+
+    #[inline(always)]
+    fn ceil(self) -> Self {
+        self.map(f64::ceil)
+    }
+
+    #[inline(always)]
+    fn mul_add(self, mul: Self, add: Self) -> Self {
+        let (value, mul, add) = (self.to_array(), mul.to_array(), add.to_array());
+        Simd::from_array(array::from_fn(|i| value[i].mul_add(mul[i], add[i])))
+    }
+}
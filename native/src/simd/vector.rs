@@ -0,0 +1,258 @@
+use std::mem::size_of;
+use std::ops::{Index, IndexMut};
+use std::slice::SliceIndex;
+use std::{array, fmt};
+
+use super::element::{CastFrom, SimdElement, ToIntUnchecked};
+use super::Mask;
+
+/// A vector of `N` lanes of `T`, mirroring the parts of `core::simd::Simd` that
+/// the crate uses.
+///
+/// Every operation is written lane-by-lane over the backing array, which LLVM
+/// turns into vector instructions once it is inlined. The few operations that
+/// it can't do this well for are routed through the [`Backend`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Simd<T, const N: usize>([T; N]);
+
+impl<T: SimdElement, const N: usize> Simd<T, N> {
+    pub const LEN: usize = N;
+
+    #[inline(always)]
+    pub const fn splat(value: T) -> Self {
+        Self([value; N])
+    }
+
+    #[inline(always)]
+    pub const fn from_array(array: [T; N]) -> Self {
+        Self(array)
+    }
+
+    #[inline(always)]
+    pub const fn to_array(self) -> [T; N] {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn as_array(&self) -> &[T; N] {
+        &self.0
+    }
+
+    #[inline(always)]
+    pub fn as_mut_array(&mut self) -> &mut [T; N] {
+        &mut self.0
+    }
+
+    /// Panics if the slice is shorter than `N`.
+    #[inline(always)]
+    pub fn from_slice(slice: &[T]) -> Self {
+        assert!(slice.len() >= N, "slice is shorter than the vector");
+        Self(array::from_fn(|i| slice[i]))
+    }
+
+    #[inline(always)]
+    pub fn map<U: SimdElement>(self, f: impl FnMut(T) -> U) -> Simd<U, N> {
+        Simd(self.0.map(f))
+    }
+
+    #[inline(always)]
+    pub(crate) fn zip_map<U: SimdElement, R: SimdElement>(
+        self,
+        other: Simd<U, N>,
+        mut f: impl FnMut(T, U) -> R,
+    ) -> Simd<R, N> {
+        Simd(array::from_fn(|i| f(self.0[i], other.0[i])))
+    }
+
+    #[inline(always)]
+    fn compare(self, other: Self, f: impl Fn(&T, &T) -> bool) -> Mask<T::Mask, N> {
+        Mask::from_array(array::from_fn(|i| f(&self.0[i], &other.0[i])))
+    }
+
+    #[inline(always)]
+    pub fn simd_eq(self, other: Self) -> Mask<T::Mask, N> {
+        self.compare(other, T::eq)
+    }
+
+    #[inline(always)]
+    pub fn simd_ne(self, other: Self) -> Mask<T::Mask, N> {
+        self.compare(other, T::ne)
+    }
+
+    #[inline(always)]
+    pub fn simd_lt(self, other: Self) -> Mask<T::Mask, N> {
+        self.compare(other, T::lt)
+    }
+
+    #[inline(always)]
+    pub fn simd_le(self, other: Self) -> Mask<T::Mask, N> {
+        self.compare(other, T::le)
+    }
+
+    #[inline(always)]
+    pub fn simd_gt(self, other: Self) -> Mask<T::Mask, N> {
+        self.compare(other, T::gt)
+    }
+
+    #[inline(always)]
+    pub fn simd_ge(self, other: Self) -> Mask<T::Mask, N> {
+        self.compare(other, T::ge)
+    }
+
+    /// Converts each lane with the semantics of an `as` cast.
+    #[inline(always)]
+    pub fn cast<U: SimdElement + CastFrom<T>>(self) -> Simd<U, N> {
+        self.map(U::cast_from)
+    }
+
+    /// Converts each lane from a float to an integer, truncating the fractional
+    /// part.
+    ///
+    /// # Safety
+    /// Every lane must be finite and representable in `I` after truncation.
+    #[inline(always)]
+    pub unsafe fn to_int_unchecked<I: SimdElement>(self) -> Simd<I, N>
+    where
+        T: ToIntUnchecked<I>,
+    {
+        self.map(|lane| T::to_int_unchecked(lane))
+    }
+
+    /// Returns the first `M` lanes, filling any lanes past `N` with `value`.
+    #[inline(always)]
+    pub fn resize<const M: usize>(self, value: T) -> Simd<T, M> {
+        Simd(array::from_fn(|i| if i < N { self.0[i] } else { value }))
+    }
+
+    /// Returns the `LEN` lanes starting at `START`.
+    #[inline(always)]
+    pub fn extract<const START: usize, const LEN: usize>(self) -> Simd<T, LEN> {
+        const { assert!(START + LEN <= N, "extracted lanes are out of bounds") };
+        Simd(array::from_fn(|i| self.0[START + i]))
+    }
+
+    /// Returns a vector where each lane `i` is lane `index[i]` of `self`. Use
+    /// [`simd_swizzle!`](super::simd_swizzle) rather than calling this
+    /// directly.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn swizzle<const M: usize>(self, index: [usize; M]) -> Simd<T, M> {
+        Simd(index.map(|lane_index| self.0[lane_index]))
+    }
+
+    /// Returns a vector where each lane `i` is lane `index[i]` of `self`
+    /// concatenated with `other`. Use [`simd_swizzle!`](super::simd_swizzle)
+    /// rather than calling this directly.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn swizzle2<const M: usize>(self, other: Self, index: [usize; M]) -> Simd<T, M> {
+        Simd(index.map(|lane_index| {
+            if lane_index < N {
+                self.0[lane_index]
+            } else {
+                other.0[lane_index - N]
+            }
+        }))
+    }
+
+    /// Reinterprets the vector as bytes in native byte order. `M` must be the
+    /// size of the vector in bytes.
+    #[inline(always)]
+    pub fn to_ne_bytes<const M: usize>(self) -> Simd<u8, M> {
+        const {
+            assert!(
+                M == N * size_of::<T>(),
+                "byte count must match the vector size"
+            )
+        };
+        // SAFETY: The sizes are checked above, and every element type is plain old
+        // data.
+        Simd(unsafe { std::mem::transmute_copy::<[T; N], [u8; M]>(&self.0) })
+    }
+
+    /// Reinterprets the vector as bytes with each lane in little-endian byte
+    /// order. `M` must be the size of the vector in bytes.
+    #[inline(always)]
+    pub fn to_le_bytes<const M: usize>(self) -> Simd<u8, M> {
+        let mut bytes = self.to_ne_bytes::<M>();
+        if cfg!(target_endian = "big") {
+            bytes
+                .0
+                .chunks_exact_mut(size_of::<T>())
+                .for_each(<[u8]>::reverse);
+        }
+        bytes
+    }
+
+    /// Reinterprets bytes in native byte order as a vector. `M` must be the
+    /// size of the vector in bytes.
+    #[inline(always)]
+    pub fn from_ne_bytes<const M: usize>(bytes: Simd<u8, M>) -> Self {
+        const {
+            assert!(
+                M == N * size_of::<T>(),
+                "byte count must match the vector size"
+            )
+        };
+        // SAFETY: The sizes are checked above, and every element type is valid for
+        // any bit pattern.
+        Self(unsafe { std::mem::transmute_copy::<[u8; M], [T; N]>(&bytes.0) })
+    }
+
+    /// Reinterprets bytes with each lane in little-endian byte order as a
+    /// vector. `M` must be the size of the vector in bytes.
+    #[inline(always)]
+    pub fn from_le_bytes<const M: usize>(mut bytes: Simd<u8, M>) -> Self {
+        if cfg!(target_endian = "big") {
+            bytes
+                .0
+                .chunks_exact_mut(size_of::<T>())
+                .for_each(<[u8]>::reverse);
+        }
+        Self::from_ne_bytes(bytes)
+    }
+}
+
+impl<T: SimdElement, const N: usize> Default for Simd<T, N> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::splat(T::default())
+    }
+}
+
+impl<T: SimdElement, const N: usize> From<[T; N]> for Simd<T, N> {
+    #[inline(always)]
+    fn from(array: [T; N]) -> Self {
+        Self(array)
+    }
+}
+
+impl<T: SimdElement, const N: usize> From<Simd<T, N>> for [T; N] {
+    #[inline(always)]
+    fn from(vector: Simd<T, N>) -> Self {
+        vector.0
+    }
+}
+
+impl<T: SimdElement, const N: usize> fmt::Debug for Simd<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T, I: SliceIndex<[T]>, const N: usize> Index<I> for Simd<T, N> {
+    type Output = I::Output;
+
+    #[inline(always)]
+    fn index(&self, index: I) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<T, I: SliceIndex<[T]>, const N: usize> IndexMut<I> for Simd<T, N> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
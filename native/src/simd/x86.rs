@@ -0,0 +1,144 @@
+use std::arch::x86_64::*;
+use std::mem::transmute;
+
+#[cfg(not(target_feature = "fma"))]
+use super::scalar::Scalar;
+use super::{Backend, Simd};
+
+/// The x86-64 backend. It only relies on SSE2, which every x86-64 CPU has, and
+/// switches to newer instructions when they are enabled at compile time.
+pub struct X86;
+
+impl Backend for X86 {
+    #[inline(always)]
+    fn floor_f32x4(value: [f32; 4]) -> [f32; 4] {
+        // SAFETY: Arrays of 4 f32s and __m128 have the same size, and any bit pattern
+        // is valid for both.
+        unsafe {
+            let value = transmute::<[f32; 4], __m128>(value);
+            transmute::<__m128, [f32; 4]>(floor_ps(value))
+        }
+    }
+
+    #[inline(always)]
+    fn ceil_f32x4(value: [f32; 4]) -> [f32; 4] {
+        // SAFETY: See floor_f32x4.
+        unsafe {
+            let value = transmute::<[f32; 4], __m128>(value);
+            transmute::<__m128, [f32; 4]>(ceil_ps(value))
+        }
+    }
+
+    #[inline(always)]
+    fn mul_add_f32x4(value: [f32; 4], mul: [f32; 4], add: [f32; 4]) -> [f32; 4] {
+        #[cfg(target_feature = "fma")]
+        // SAFETY: See floor_f32x4.
+        unsafe {
+            transmute::<__m128, [f32; 4]>(_mm_fmadd_ps(
+                transmute::<[f32; 4], __m128>(value),
+                transmute::<[f32; 4], __m128>(mul),
+                transmute::<[f32; 4], __m128>(add),
+            ))
+        }
+
+        // There's no fused instruction without FMA, and emulating one isn't any faster
+        // than libm.
+        #[cfg(not(target_feature = "fma"))]
+        Scalar::mul_add_f32x4(value, mul, add)
+    }
+
+    #[inline(always)]
+    fn bitmask_i8x16(value: [i8; 16]) -> u16 {
+        // SAFETY: Arrays of 16 i8s and __m128i have the same size, and any bit
+        // pattern is valid for both.
+        unsafe { _mm_movemask_epi8(transmute::<[i8; 16], __m128i>(value)) as u16 }
+    }
+
+    #[inline(always)]
+    fn bitmask_i32x4(value: [i32; 4]) -> u8 {
+        // SAFETY: Arrays of 4 i32s and __m128 have the same size, and any bit pattern
+        // is valid for both.
+        unsafe { _mm_movemask_ps(transmute::<[i32; 4], __m128>(value)) as u8 }
+    }
+}
+
+#[cfg(target_feature = "sse4.1")]
+#[inline(always)]
+unsafe fn floor_ps(value: __m128) -> __m128 {
+    _mm_floor_ps(value)
+}
+
+#[cfg(target_feature = "sse4.1")]
+#[inline(always)]
+unsafe fn ceil_ps(value: __m128) -> __m128 {
+    _mm_ceil_ps(value)
+}
+
+// Without SSE4.1, we round by truncating through an integer conversion, then
+// correct the lanes that truncation rounded in the wrong direction.
+#[cfg(not(target_feature = "sse4.1"))]
+#[inline(always)]
+unsafe fn floor_ps(value: __m128) -> __m128 {
+    let truncated = _mm_cvtepi32_ps(_mm_cvttps_epi32(value));
+    let correction = _mm_and_ps(_mm_cmpgt_ps(truncated, value), _mm_set1_ps(1.0));
+    fix_rounded_ps(value, _mm_sub_ps(truncated, correction))
+}
+
+#[cfg(not(target_feature = "sse4.1"))]
+#[inline(always)]
+unsafe fn ceil_ps(value: __m128) -> __m128 {
+    let truncated = _mm_cvtepi32_ps(_mm_cvttps_epi32(value));
+    let correction = _mm_and_ps(_mm_cmplt_ps(truncated, value), _mm_set1_ps(1.0));
+    fix_rounded_ps(value, _mm_add_ps(truncated, correction))
+}
+
+#[cfg(not(target_feature = "sse4.1"))]
+#[inline(always)]
+unsafe fn fix_rounded_ps(value: __m128, rounded: __m128) -> __m128 {
+    let sign_bit = _mm_set1_ps(-0.0);
+    // Values with a magnitude of at least 2^23 have no fractional part, and they're
+    // the only ones that the integer conversion can overflow on. NaN fails the
+    // comparison, so it's passed through as well.
+    let magnitude = _mm_andnot_ps(sign_bit, value);
+    let has_fraction = _mm_cmplt_ps(magnitude, _mm_set1_ps(8388608.0));
+    // Rounding never changes the sign, even when the result is 0.
+    let rounded_signed = _mm_or_ps(rounded, _mm_and_ps(value, sign_bit));
+
+    _mm_or_ps(
+        _mm_and_ps(has_fraction, rounded_signed),
+        _mm_andnot_ps(has_fraction, value),
+    )
+}
+
+// These let the kernels that are written with intrinsics directly pass vectors
+// in and out of them.
+macro_rules! impl_register_conversions {
+    ($($register:ty => $($vector:ty),*;)*) => {
+        $($(
+            impl From<$register> for $vector {
+                #[inline(always)]
+                fn from(value: $register) -> Self {
+                    // SAFETY: The types have the same size, and any bit pattern is valid for
+                    // both.
+                    unsafe { transmute::<$register, $vector>(value) }
+                }
+            }
+
+            impl From<$vector> for $register {
+                #[inline(always)]
+                fn from(value: $vector) -> Self {
+                    // SAFETY: See above.
+                    unsafe { transmute::<$vector, $register>(value) }
+                }
+            }
+        )*)*
+    };
+}
+
+impl_register_conversions! {
+    __m128i => Simd<u8, 16>, Simd<i8, 16>, Simd<u32, 4>, Simd<i32, 4>, Simd<u64, 2>;
+    __m128 => Simd<f32, 4>;
+    __m256i => Simd<u8, 32>, Simd<i8, 32>, Simd<u32, 8>, Simd<i32, 8>, Simd<u64, 4>;
+    __m256 => Simd<f32, 8>;
+    __m512i => Simd<u8, 64>, Simd<u64, 8>;
+}
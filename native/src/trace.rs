@@ -0,0 +1,758 @@
+//! Records the graph calls made through the FFI to a compact binary trace, so
+//! that a session from the game can be replayed and checked outside of it.
+//!
+//! A trace starts with `MAGIC` and `VERSION`, followed by a sequence of events.
+//! Each event is a tag byte followed by its fields, all in little-endian.
+//! Graphs are identified by the `GraphHandle` value they had when the trace
+//! was recorded.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::api::SearchOptions;
+use crate::ffi::{FFISearchOptions, FFITile};
+use crate::graph::context::GraphSearchContext;
+use crate::graph::coords::SectionGeometry;
+use crate::graph::error::GraphError;
+use crate::graph::Graph;
+use crate::simd::prelude::*;
+
+pub const MAGIC: [u8; 8] = *b"HCTRACE\0";
+pub const VERSION: u32 = 4;
+
+const CREATE_TAG: u8 = 0;
+const SET_SECTION_TAG: u8 = 1;
+const SEARCH_TAG: u8 = 2;
+const DELETE_TAG: u8 = 3;
+const CREATE_CUBIC_TAG: u8 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceEvent {
+    Create {
+        graph_id: u64,
+        render_distance: u8,
+        world_bottom_section_y: i8,
+        world_top_section_y: i8,
+        section_length: u32,
+        bounds_extension: f32,
+    },
+    CreateCubic {
+        graph_id: u64,
+        render_distance: u8,
+        vertical_render_distance: u8,
+        section_length: u32,
+        bounds_extension: f32,
+    },
+    SetSection {
+        graph_id: u64,
+        section_coords: [i32; 3],
+        visibility_data: u64,
+    },
+    Search {
+        graph_id: u64,
+        frustum_planes: [[f32; 4]; 6],
+        camera_pos: [f64; 3],
+        // recorded as they were passed in, so they're validated again when replayed
+        options: FFISearchOptions,
+        // the hash of the visible tiles returned by the search
+        visible_hash: u64,
+    },
+    Delete {
+        graph_id: u64,
+    },
+}
+
+impl TraceEvent {
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            TraceEvent::Create {
+                graph_id,
+                render_distance,
+                world_bottom_section_y,
+                world_top_section_y,
+                section_length,
+                bounds_extension,
+            } => {
+                writer.write_all(&[CREATE_TAG])?;
+                writer.write_all(&graph_id.to_le_bytes())?;
+                writer.write_all(&[
+                    *render_distance,
+                    *world_bottom_section_y as u8,
+                    *world_top_section_y as u8,
+                ])?;
+                writer.write_all(&section_length.to_le_bytes())?;
+                writer.write_all(&bounds_extension.to_le_bytes())
+            }
+            TraceEvent::CreateCubic {
+                graph_id,
+                render_distance,
+                vertical_render_distance,
+                section_length,
+                bounds_extension,
+            } => {
+                writer.write_all(&[CREATE_CUBIC_TAG])?;
+                writer.write_all(&graph_id.to_le_bytes())?;
+                writer.write_all(&[*render_distance, *vertical_render_distance])?;
+                writer.write_all(&section_length.to_le_bytes())?;
+                writer.write_all(&bounds_extension.to_le_bytes())
+            }
+            TraceEvent::SetSection {
+                graph_id,
+                section_coords,
+                visibility_data,
+            } => {
+                writer.write_all(&[SET_SECTION_TAG])?;
+                writer.write_all(&graph_id.to_le_bytes())?;
+                for coord in section_coords {
+                    writer.write_all(&coord.to_le_bytes())?;
+                }
+                writer.write_all(&visibility_data.to_le_bytes())
+            }
+            TraceEvent::Search {
+                graph_id,
+                frustum_planes,
+                camera_pos,
+                options,
+                visible_hash,
+            } => {
+                writer.write_all(&[SEARCH_TAG])?;
+                writer.write_all(&graph_id.to_le_bytes())?;
+                for component in frustum_planes.as_flattened() {
+                    writer.write_all(&component.to_le_bytes())?;
+                }
+                for component in camera_pos {
+                    writer.write_all(&component.to_le_bytes())?;
+                }
+                writer.write_all(&options.search_distance.to_le_bytes())?;
+                writer.write_all(&options.vertical_search_distance.to_le_bytes())?;
+                writer.write_all(&options.bounds_extension.to_le_bytes())?;
+                writer.write_all(&[
+                    options.fog_shape,
+                    options.use_occlusion_culling as u8,
+                    options.use_angle_culling as u8,
+                    options.use_outward_direction_masks as u8,
+                    options.use_fog_culling as u8,
+                    options.use_height_culling as u8,
+                ])?;
+                writer.write_all(&visible_hash.to_le_bytes())
+            }
+            TraceEvent::Delete { graph_id } => {
+                writer.write_all(&[DELETE_TAG])?;
+                writer.write_all(&graph_id.to_le_bytes())
+            }
+        }
+    }
+
+    /// Returns `None` if the reader is at the end of the trace.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut tag = [0_u8];
+        if reader.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+
+        let graph_id = u64::from_le_bytes(read_array(reader)?);
+
+        let event = match tag[0] {
+            CREATE_TAG => {
+                let [render_distance, world_bottom_section_y, world_top_section_y] =
+                    read_array(reader)?;
+                let section_length = u32::from_le_bytes(read_array(reader)?);
+                let bounds_extension = f32::from_le_bytes(read_array(reader)?);
+
+                TraceEvent::Create {
+                    graph_id,
+                    render_distance,
+                    world_bottom_section_y: world_bottom_section_y as i8,
+                    world_top_section_y: world_top_section_y as i8,
+                    section_length,
+                    bounds_extension,
+                }
+            }
+            CREATE_CUBIC_TAG => {
+                let [render_distance, vertical_render_distance] = read_array(reader)?;
+
+                TraceEvent::CreateCubic {
+                    graph_id,
+                    render_distance,
+                    vertical_render_distance,
+                    section_length: u32::from_le_bytes(read_array(reader)?),
+                    bounds_extension: f32::from_le_bytes(read_array(reader)?),
+                }
+            }
+            SET_SECTION_TAG => {
+                let mut section_coords = [0; 3];
+                for coord in &mut section_coords {
+                    *coord = i32::from_le_bytes(read_array(reader)?);
+                }
+
+                TraceEvent::SetSection {
+                    graph_id,
+                    section_coords,
+                    visibility_data: u64::from_le_bytes(read_array(reader)?),
+                }
+            }
+            SEARCH_TAG => {
+                let mut frustum_planes = [[0.0; 4]; 6];
+                for component in frustum_planes.as_flattened_mut() {
+                    *component = f32::from_le_bytes(read_array(reader)?);
+                }
+
+                let mut camera_pos = [0.0; 3];
+                for component in &mut camera_pos {
+                    *component = f64::from_le_bytes(read_array(reader)?);
+                }
+
+                let search_distance = f32::from_le_bytes(read_array(reader)?);
+                let vertical_search_distance = f32::from_le_bytes(read_array(reader)?);
+                let bounds_extension = f32::from_le_bytes(read_array(reader)?);
+                let [fog_shape, use_occlusion_culling, use_angle_culling, use_outward_direction_masks, use_fog_culling, use_height_culling] =
+                    read_array(reader)?;
+
+                TraceEvent::Search {
+                    graph_id,
+                    frustum_planes,
+                    camera_pos,
+                    options: FFISearchOptions {
+                        search_distance,
+                        vertical_search_distance,
+                        bounds_extension,
+                        fog_shape,
+                        use_occlusion_culling: use_occlusion_culling != 0,
+                        use_angle_culling: use_angle_culling != 0,
+                        use_outward_direction_masks: use_outward_direction_masks != 0,
+                        use_fog_culling: use_fog_culling != 0,
+                        use_height_culling: use_height_culling != 0,
+                    },
+                    visible_hash: u64::from_le_bytes(read_array(reader)?),
+                }
+            }
+            DELETE_TAG => TraceEvent::Delete { graph_id },
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown trace event tag: {tag}"),
+                ))
+            }
+        };
+
+        Ok(Some(event))
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn write_header(writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())
+}
+
+pub fn read_header(reader: &mut impl Read) -> io::Result<()> {
+    if read_array::<8>(reader)? != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "File is not a trace",
+        ));
+    }
+
+    let version = u32::from_le_bytes(read_array(reader)?);
+    if version != VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unsupported trace version - Version: {version}, Expected: {VERSION}"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Hashes the tiles returned by a search with 64-bit FNV-1a, which is stable
+/// across platforms and Rust versions.
+pub fn hash_visible_tiles(visible_tiles: &[FFITile]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut hash_bytes = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(PRIME);
+        }
+    };
+
+    for tile in visible_tiles {
+        for coord in tile.origin_section_coords {
+            hash_bytes(&coord.to_le_bytes());
+        }
+        for sections in tile.visible_sections {
+            hash_bytes(&sections.to_le_bytes());
+        }
+    }
+
+    hash
+}
+
+struct TraceRecorder {
+    writer: BufWriter<File>,
+    error: Option<io::Error>,
+}
+
+// checked before locking the recorder, so calls aren't slowed down when nothing
+// is being recorded
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static RECORDER: Mutex<Option<TraceRecorder>> = Mutex::new(None);
+
+/// Starts recording every graph call made through the FFI to a new trace file
+/// at the given path. Any trace that was already being recorded is stopped.
+pub fn start_recording(path: &Path) -> io::Result<()> {
+    let _ = stop_recording();
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_header(&mut writer)?;
+
+    *RECORDER.lock().unwrap() = Some(TraceRecorder {
+        writer,
+        error: None,
+    });
+    RECORDING.store(true, Ordering::Release);
+
+    Ok(())
+}
+
+/// Stops recording and flushes the trace file. Returns the first error that
+/// occurred while recording, if there was one.
+pub fn stop_recording() -> io::Result<()> {
+    RECORDING.store(false, Ordering::Release);
+
+    let Some(mut recorder) = RECORDER.lock().unwrap().take() else {
+        return Ok(());
+    };
+
+    if let Some(error) = recorder.error {
+        return Err(error);
+    }
+
+    recorder.writer.flush()
+}
+
+/// Adds an event to the trace being recorded. The event is only created if a
+/// trace is being recorded.
+pub fn record(event: impl FnOnce() -> TraceEvent) {
+    if !RECORDING.load(Ordering::Acquire) {
+        return;
+    }
+
+    let mut recorder_guard = RECORDER.lock().unwrap();
+    let Some(recorder) = recorder_guard.as_mut() else {
+        return;
+    };
+
+    // after the first error, nothing else is written so the trace stays readable up
+    // to that point
+    if recorder.error.is_none() {
+        if let Err(error) = event().write_to(&mut recorder.writer) {
+            recorder.error = Some(error);
+        }
+    }
+}
+
+/// A search that returned different visible tiles when replayed.
+#[derive(Debug, PartialEq)]
+pub struct SearchMismatch {
+    // the index of the search in the trace, counting only search events
+    pub search_index: usize,
+    pub graph_id: u64,
+    pub expected_hash: u64,
+    pub actual_hash: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub event_count: usize,
+    pub search_count: usize,
+    pub mismatches: Vec<SearchMismatch>,
+}
+
+/// The result of replaying a single search event.
+pub struct ReplayedSearch {
+    pub search_index: usize,
+    pub graph_id: u64,
+    pub context: GraphSearchContext,
+    pub duration: Duration,
+    pub expected_hash: u64,
+    pub actual_hash: u64,
+}
+
+/// Drives fresh graphs through the events of a trace, one event at a time.
+#[derive(Default)]
+pub struct Replayer {
+    graphs: HashMap<u64, Graph>,
+    pub report: ReplayReport,
+}
+
+impl Replayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn graph(&self, graph_id: u64) -> Option<&Graph> {
+        self.graphs.get(&graph_id)
+    }
+
+    pub fn graph_mut(&mut self, graph_id: u64) -> Option<&mut Graph> {
+        self.graphs.get_mut(&graph_id)
+    }
+
+    /// The IDs of the graphs that have been created and not yet deleted.
+    pub fn graph_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.graphs.keys().copied()
+    }
+
+    /// Applies an event to the graphs. If the event was a search, the search is
+    /// run and its results are checked against the recorded hash.
+    pub fn apply(&mut self, event: TraceEvent) -> io::Result<Option<ReplayedSearch>> {
+        self.report.event_count += 1;
+
+        let missing_graph = |graph_id: u64| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Trace uses a graph that doesn't exist - Graph ID: {graph_id:#x}"),
+            )
+        };
+
+        // only successful calls are recorded, so these errors mean the trace is corrupt
+        let invalid_event = |error: GraphError| io::Error::new(io::ErrorKind::InvalidData, error);
+
+        match event {
+            TraceEvent::Create {
+                graph_id,
+                render_distance,
+                world_bottom_section_y,
+                world_top_section_y,
+                section_length,
+                bounds_extension,
+            } => {
+                let geometry = SectionGeometry::new(section_length, bounds_extension)
+                    .map_err(invalid_event)?;
+                let graph = Graph::try_new_with_geometry(
+                    render_distance,
+                    world_bottom_section_y,
+                    world_top_section_y,
+                    geometry,
+                )
+                .map_err(invalid_event)?;
+                self.graphs.insert(graph_id, graph);
+            }
+            TraceEvent::CreateCubic {
+                graph_id,
+                render_distance,
+                vertical_render_distance,
+                section_length,
+                bounds_extension,
+            } => {
+                let geometry = SectionGeometry::new(section_length, bounds_extension)
+                    .map_err(invalid_event)?;
+                let graph =
+                    Graph::try_new_cubic(render_distance, vertical_render_distance, geometry)
+                        .map_err(invalid_event)?;
+                self.graphs.insert(graph_id, graph);
+            }
+            TraceEvent::SetSection {
+                graph_id,
+                section_coords,
+                visibility_data,
+            } => {
+                let graph = self
+                    .graphs
+                    .get_mut(&graph_id)
+                    .ok_or_else(|| missing_graph(graph_id))?;
+
+                graph
+                    .try_set_section(Simd::from_array(section_coords), visibility_data)
+                    .map_err(invalid_event)?;
+            }
+            TraceEvent::Search {
+                graph_id,
+                frustum_planes,
+                camera_pos,
+                options,
+                visible_hash,
+            } => {
+                let graph = self
+                    .graphs
+                    .get_mut(&graph_id)
+                    .ok_or_else(|| missing_graph(graph_id))?;
+
+                let context = GraphSearchContext::try_new(
+                    &graph.coord_space,
+                    frustum_planes.map(Simd::from_array),
+                    Simd::from_array(camera_pos),
+                    &SearchOptions::try_from(&options).map_err(invalid_event)?,
+                )
+                .map_err(invalid_event)?;
+
+                let start = Instant::now();
+                graph.cull(&context);
+                let duration = start.elapsed();
+
+                let search = ReplayedSearch {
+                    search_index: self.report.search_count,
+                    graph_id,
+                    context,
+                    duration,
+                    expected_hash: visible_hash,
+                    actual_hash: hash_visible_tiles(&graph.visible_tiles),
+                };
+
+                if search.actual_hash != search.expected_hash {
+                    self.report.mismatches.push(SearchMismatch {
+                        search_index: search.search_index,
+                        graph_id,
+                        expected_hash: search.expected_hash,
+                        actual_hash: search.actual_hash,
+                    });
+                }
+
+                self.report.search_count += 1;
+                return Ok(Some(search));
+            }
+            TraceEvent::Delete { graph_id } => {
+                self.graphs
+                    .remove(&graph_id)
+                    .ok_or_else(|| missing_graph(graph_id))?;
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Reads the events of a trace one at a time, after checking the header.
+pub struct TraceReader<R: Read> {
+    reader: R,
+}
+
+impl TraceReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> TraceReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        read_header(&mut reader)?;
+        Ok(Self { reader })
+    }
+
+    /// Returns `None` once the end of the trace is reached.
+    pub fn next_event(&mut self) -> io::Result<Option<TraceEvent>> {
+        TraceEvent::read_from(&mut self.reader)
+    }
+}
+
+pub fn replay_file(path: &Path) -> io::Result<ReplayReport> {
+    replay(BufReader::new(File::open(path)?))
+}
+
+/// Drives fresh graphs through the events in a trace, and checks the results of
+/// each search against the recorded hashes.
+pub fn replay(reader: impl Read) -> io::Result<ReplayReport> {
+    let mut trace_reader = TraceReader::new(reader)?;
+    let mut replayer = Replayer::new();
+
+    while let Some(event) = trace_reader.next_event()? {
+        replayer.apply(event)?;
+    }
+
+    Ok(replayer.report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::ffi::*;
+    use crate::graph::tile::fog::FogShape;
+
+    const FRUSTUM_PLANES: [[f32; 4]; 6] = [
+        [1.0, 0.0, 1.0, 0.0],
+        [0.0, 1.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0, 0.05],
+        [-1.0, 0.0, 1.0, 0.0],
+        [0.0, -1.0, 1.0, 0.0],
+        [0.0, 0.0, -1.0, 1000.0],
+    ];
+
+    // slot 3 in its first generation
+    const GRAPH_ID: u64 = (1 << 32) | 3;
+
+    #[test]
+    fn event_round_trip_test() {
+        let events = [
+            TraceEvent::Create {
+                graph_id: GRAPH_ID,
+                render_distance: 12,
+                world_bottom_section_y: -4,
+                world_top_section_y: 19,
+                section_length: 32,
+                bounds_extension: 1.5,
+            },
+            TraceEvent::CreateCubic {
+                graph_id: 0x7f00_1234_9abc,
+                render_distance: 8,
+                vertical_render_distance: 5,
+                section_length: 16,
+                bounds_extension: 1.0,
+            },
+            TraceEvent::SetSection {
+                graph_id: GRAPH_ID,
+                section_coords: [-3, 7, 1_000_000],
+                visibility_data: 0x0102_0408_1020_4080,
+            },
+            TraceEvent::Search {
+                graph_id: GRAPH_ID,
+                frustum_planes: FRUSTUM_PLANES,
+                camera_pos: [-0.25, 72.5, 123456.789],
+                options: FFISearchOptions {
+                    vertical_search_distance: 96.0,
+                    bounds_extension: 0.5,
+                    use_angle_culling: false,
+                    use_height_culling: false,
+                    ..FFISearchOptions::new(192.0, true, FogShape::Spherical as u8)
+                },
+                visible_hash: 0xdead_beef,
+            },
+            TraceEvent::Delete { graph_id: GRAPH_ID },
+        ];
+
+        let mut bytes = Vec::new();
+        for event in &events {
+            event.write_to(&mut bytes).unwrap();
+        }
+
+        let mut reader = Cursor::new(bytes);
+        for event in &events {
+            assert_eq!(
+                TraceEvent::read_from(&mut reader).unwrap().as_ref(),
+                Some(event)
+            );
+        }
+        assert_eq!(TraceEvent::read_from(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn record_and_replay_test() {
+        let _lock = lock_ffi_tests();
+        let path = std::env::temp_dir().join(format!("hypercull-trace-{}.bin", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        unsafe {
+            assert_eq!(
+                hypercull_trace_start(path_str.as_ptr(), path_str.len()),
+                GraphError::OK
+            );
+
+            let mut graph_handle = 0;
+            assert_eq!(
+                hypercull_graph_create(&mut graph_handle, 4, -4, 19),
+                GraphError::OK
+            );
+
+            for x in -4..4 {
+                for y in -4..20 {
+                    for z in -4..4 {
+                        let visibility_data = if (x + y + z) % 3 == 0 { 0 } else { !0 };
+                        hypercull_graph_set_section(graph_handle, x, y, z, visibility_data);
+                    }
+                }
+            }
+
+            let mut recorded_hashes = Vec::new();
+            for (camera_y, fog_shape) in [
+                (72.5, FogShape::Cylindrical),
+                (8.0, FogShape::Spherical),
+                (400.0, FogShape::Square),
+            ] {
+                let camera = FFICamera {
+                    frustum_planes: FRUSTUM_PLANES,
+                    pos: [8.5, camera_y, 8.5],
+                };
+                let mut visible_tiles = FFISlice {
+                    data_ptr: std::ptr::null(),
+                    count: 0,
+                };
+
+                hypercull_graph_search_with_fog_shape(
+                    &mut visible_tiles,
+                    graph_handle,
+                    &camera,
+                    64.0,
+                    true,
+                    fog_shape as u8,
+                );
+                recorded_hashes.push(hash_visible_tiles(std::slice::from_raw_parts(
+                    visible_tiles.data_ptr,
+                    visible_tiles.count,
+                )));
+            }
+
+            // every option should make it into the trace, not just the ones the older
+            // search functions take
+            let camera = FFICamera {
+                frustum_planes: FRUSTUM_PLANES,
+                pos: [8.5, 72.5, 8.5],
+            };
+            let options = FFISearchOptions {
+                vertical_search_distance: 20.0,
+                bounds_extension: 4.0,
+                use_angle_culling: false,
+                use_outward_direction_masks: false,
+                ..FFISearchOptions::new(64.0, true, FogShape::Square as u8)
+            };
+            let mut visible_tiles = FFISlice {
+                data_ptr: std::ptr::null(),
+                count: 0,
+            };
+            assert_eq!(
+                hypercull_graph_search_with_options(
+                    &mut visible_tiles,
+                    graph_handle,
+                    &camera,
+                    &options
+                ),
+                GraphError::OK
+            );
+
+            assert_eq!(hypercull_graph_delete(graph_handle), GraphError::OK);
+
+            assert_eq!(hypercull_trace_stop(), GraphError::OK);
+
+            // the searches should see different things from each camera position, so the
+            // hashes are actually checking something
+            assert_ne!(recorded_hashes[0], recorded_hashes[1]);
+        }
+
+        let report = replay_file(&path).unwrap();
+        assert_eq!(report.event_count, 1 + (8 * 24 * 8) + 4 + 1);
+        assert_eq!(report.search_count, 4);
+        assert_eq!(report.mismatches, []);
+
+        // corrupt the hash of the last search, which is right before the delete event
+        let mut bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let hash_offset = bytes.len() - 9 - 8;
+        bytes[hash_offset] ^= 1;
+
+        let report = replay(Cursor::new(bytes)).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].search_index, 3);
+    }
+}